@@ -0,0 +1,453 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+use game_models::ai::behaviour::{AiBrain, AiContext, AiMovement, AiProfileDefinition, AiState, AiStateChanged, AiStateGroup, AiTarget, Blackboard, NoiseEvent};
+use game_models::attributes::Attributes;
+use game_models::combat::{Combatant, MeleeAttack};
+use game_models::definitions::DefinitionLibrary;
+use game_models::entities::definition::AiProfile;
+use game_models::factions::{Factions, Relation, ThreatTable};
+use game_models::health::{DamageEvent, DamageType, Dead, Health};
+use game_models::navigation::agent::NavAgent;
+use game_models::pacing::PacingDirector;
+use game_models::rng::SeededRng;
+use game_models::states::is_state_simulating;
+use game_models::status_effects::StatusEffects;
+use game_models::weather::Weather;
+
+pub struct AiLogicComponent;
+
+impl Plugin for AiLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate,
+                        (
+                            attach_ai_brains,
+                            perceive_targets,
+                            hear_noises,
+                            update_state_machines,
+                            run_state_actions,
+                            apply_ai_movement
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Creates the [`AiBrain`] of entities spawned with an [`AiProfile`] once the profile is loaded.
+/// The blackboard random source is seeded with the entity id, so runs are reproducible.
+///
+/// # Parameters
+/// * `new_agents` - Entities with a profile but without a brain.
+/// * `library` - Loaded AI profiles.
+/// * `profiles` - Profile assets.
+#[coverage(off)]
+fn attach_ai_brains(
+    mut commands: Commands,
+    new_agents: Query<(Entity, &AiProfile, &Transform), Without<AiBrain>>,
+    library: Res<DefinitionLibrary<AiProfileDefinition>>,
+    profiles: Res<Assets<AiProfileDefinition>>,
+) {
+    for (entity, profile, transform) in new_agents.iter() {
+        let Some(definition) = library.get(&profile.0, &profiles) else {
+            continue;
+        };
+
+        let mut brain = AiBrain::new(profile.0.clone());
+        brain.state = definition.initial;
+        commands.entity(entity).insert((
+            brain,
+            Blackboard {
+                home: Some(transform.translation),
+                rng: SeededRng::new(entity.to_bits()),
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Selects the blackboard target among the living entities within sight range: hostile
+/// ones by faction and all with threat in the [`ThreatTable`]. The one with the most
+/// threat wins, otherwise the nearest. Agents or targets without a faction treat every
+/// [`AiTarget`] as hostile. Friends are ignored despite their threat while friendly fire
+/// is disabled. The sight range grows and shrinks with the aggression of the
+/// [`PacingDirector`] and the visibility of the [`Weather`].
+///
+/// # Parameters
+/// * `agents` - AI controlled entities.
+/// * `targets` - Possible targets, AI and [`AiTarget`]s.
+/// * `factions` - Relations between the agents and the targets.
+/// * `pacing` - Aggression multiplier.
+/// * `weather` - Visibility multiplier.
+/// * `library` - Loaded AI profiles for the sight range.
+/// * `profiles` - Profile assets.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn perceive_targets(
+    mut agents: Query<(Entity, &AiBrain, &mut Blackboard, &ThreatTable, &Transform), Without<Dead>>,
+    targets: Query<(Entity, &Transform, Has<AiTarget>), (Or<(With<AiTarget>, With<AiBrain>)>, Without<Dead>)>,
+    factions: Factions,
+    pacing: Res<PacingDirector>,
+    weather: Res<Weather>,
+    library: Res<DefinitionLibrary<AiProfileDefinition>>,
+    profiles: Res<Assets<AiProfileDefinition>>,
+) {
+    for (entity, brain, mut blackboard, threat, transform) in agents.iter_mut() {
+        let Some(profile) = library.get(&brain.profile, &profiles) else {
+            continue;
+        };
+        let sight_range = profile.parameters.sight_range * pacing.aggression * weather.current.visibility;
+
+        let candidates = targets
+            .iter()
+            .filter(|(target, ..)| *target != entity && factions.can_damage(entity, *target))
+            .filter(|(target, _, ai_target)| threat.threat(*target) > 0.0
+                || factions.relation_of(entity, *target).map_or(*ai_target, |relation| relation == Relation::Hostile))
+            .map(|(target, target_transform, _)| (target, target_transform.translation.distance(transform.translation)))
+            .filter(|(_, distance)| *distance <= sight_range);
+
+        match threat.select(candidates).and_then(|target| targets.get(target).ok()) {
+            Some((target, target_transform, _)) => {
+                blackboard.target = Some(target);
+                blackboard.last_known_target_position = Some(target_transform.translation);
+            }
+            None => blackboard.target = None,
+        }
+    }
+}
+
+/// Stores heard [`NoiseEvent`]s in the blackboard of all agents in hearing range.
+/// Rain and storms mask noises and shorten the hearing range.
+///
+/// # Parameters
+/// * `noises` - Noises emitted since the last fixed step.
+/// * `agents` - AI controlled entities.
+/// * `weather` - Noise masking.
+/// * `library` - Loaded AI profiles for the hearing range.
+/// * `profiles` - Profile assets.
+#[coverage(off)]
+fn hear_noises(
+    mut noises: MessageReader<NoiseEvent>,
+    mut agents: Query<(Entity, &AiBrain, &mut Blackboard, &Transform), Without<Dead>>,
+    weather: Res<Weather>,
+    library: Res<DefinitionLibrary<AiProfileDefinition>>,
+    profiles: Res<Assets<AiProfileDefinition>>,
+) {
+    let audible = 1.0 - weather.current.noise_masking.clamp(0.0, 1.0);
+    for noise in noises.read() {
+        for (entity, brain, mut blackboard, transform) in agents.iter_mut() {
+            if noise.source == Some(entity) {
+                continue;
+            }
+            let Some(profile) = library.get(&brain.profile, &profiles) else {
+                continue;
+            };
+            let range = noise.radius.min(profile.parameters.hearing_range) * audible;
+            if transform.translation.distance(noise.position) <= range {
+                blackboard.noise_position = Some(noise.position);
+            }
+        }
+    }
+}
+
+/// Evaluates the transitions of every agent's profile and switches states.
+/// Entering a state prepares its destination in the blackboard. Heard noises are
+/// forgotten once investigated or when the agent turns hostile.
+///
+/// # Parameters
+/// * `time` - Fixed time step.
+/// * `changed` - Writer for state changes.
+/// * `agents` - AI controlled entities.
+/// * `library` - Loaded AI profiles.
+/// * `profiles` - Profile assets.
+#[coverage(off)]
+fn update_state_machines(
+    time: Res<Time>,
+    mut changed: MessageWriter<AiStateChanged>,
+    mut agents: Query<(Entity, &mut AiBrain, &mut Blackboard, &Transform, Option<&Health>, Option<&StatusEffects>), Without<Dead>>,
+    library: Res<DefinitionLibrary<AiProfileDefinition>>,
+    profiles: Res<Assets<AiProfileDefinition>>,
+) {
+    for (entity, mut brain, mut blackboard, transform, health, effects) in agents.iter_mut() {
+        let Some(profile) = library.get(&brain.profile, &profiles) else {
+            continue;
+        };
+        brain.time_in_state += time.delta_secs();
+
+        let position = transform.translation;
+        let home = blackboard.home.unwrap_or(position);
+        let context = AiContext {
+            target_distance: blackboard.last_known_target_position
+                .filter(|_| blackboard.target.is_some())
+                .map(|p| p.distance(position)),
+            heard_noise: blackboard.noise_position.is_some(),
+            health_fraction: health.map_or(1.0, Health::fraction),
+            distance_to_home: flat_distance(position, home),
+            distance_to_destination: blackboard.destination.map(|d| flat_distance(position, d)),
+            time_in_state: brain.time_in_state,
+            frightened: effects.is_some_and(|effects| effects.has_tag("fear")),
+        };
+
+        let Some(next) = profile.next_state(brain.state, &context, &mut blackboard.rng) else {
+            continue;
+        };
+
+        blackboard.destination = match next {
+            AiState::Wander => {
+                let offset = blackboard.rng.point_in_circle(profile.parameters.wander_radius);
+                Some(home + Vec3::new(offset.x, 0.0, offset.y))
+            }
+            AiState::Investigate => blackboard.noise_position.take(),
+            AiState::ReturnHome => Some(home),
+            _ => None,
+        };
+        // The noise was investigated, or the agent got busy with a target instead.
+        if brain.state == AiState::Investigate || next.group() == AiStateGroup::Hostile {
+            blackboard.noise_position = None;
+        }
+
+        changed.write(AiStateChanged { entity, from: brain.state, to: next });
+        brain.state = next;
+        brain.time_in_state = 0.0;
+    }
+}
+
+/// Executes the action of the active state: choose a movement direction and attack.
+/// Agents with a [`NavAgent`] follow their path to the goal, without one (or while the
+/// path is computed) they walk straight to it. Agents with a [`Combatant`] attack with
+/// their combo, all others deal their damage directly.
+///
+/// # Parameters
+/// * `time` - Fixed time step.
+/// * `damage` - Writer for direct attacks of agents without a [`Combatant`].
+/// * `melee` - Writer for the combo attacks of agents with a [`Combatant`].
+/// * `agents` - AI controlled entities.
+/// * `targets` - Positions of possible targets, AI and [`AiTarget`]s.
+/// * `library` - Loaded AI profiles.
+/// * `profiles` - Profile assets.
+#[coverage(off)]
+pub(crate) fn run_state_actions(
+    time: Res<Time>,
+    mut damage: MessageWriter<DamageEvent>,
+    mut melee: MessageWriter<MeleeAttack>,
+    mut agents: Query<(Entity, &AiBrain, &mut Blackboard, &mut AiMovement, &Transform, Option<&Attributes>, Option<&mut NavAgent>, Has<Combatant>), Without<Dead>>,
+    targets: Query<&Transform, Or<(With<AiTarget>, With<AiBrain>)>>,
+    library: Res<DefinitionLibrary<AiProfileDefinition>>,
+    profiles: Res<Assets<AiProfileDefinition>>,
+) {
+    for (entity, brain, mut blackboard, mut movement, transform, attributes, nav_agent, combatant) in agents.iter_mut() {
+        let Some(profile) = library.get(&brain.profile, &profiles) else {
+            continue;
+        };
+        let parameters = &profile.parameters;
+        let position = transform.translation;
+        let move_speed = attributes.map_or(4.0, |a| a.move_speed.value);
+        let target_position = blackboard.target
+            .and_then(|target| targets.get(target).ok())
+            .map(|t| t.translation)
+            .or(blackboard.last_known_target_position);
+
+        blackboard.attack_cooldown = (blackboard.attack_cooldown - time.delta_secs()).max(0.0);
+
+        let goal = match brain.state {
+            AiState::Wander | AiState::Investigate | AiState::ReturnHome => blackboard.destination,
+            AiState::Chase => target_position,
+            _ => None,
+        };
+        let waypoint = match nav_agent {
+            Some(mut nav_agent) => {
+                nav_agent.set_goal(goal);
+                nav_agent.steering_target(position).or(goal)
+            }
+            None => goal,
+        };
+
+        let direction = match brain.state {
+            AiState::Idle => Vec3::ZERO,
+            AiState::Wander | AiState::Investigate | AiState::ReturnHome => {
+                waypoint.map_or(Vec3::ZERO, |w| flat_direction(position, w)) * parameters.calm_speed
+            }
+            AiState::Chase => {
+                waypoint.map_or(Vec3::ZERO, |w| flat_direction(position, w)) * parameters.hostile_speed
+            }
+            AiState::Flee => {
+                target_position.map_or(Vec3::ZERO, |t| -flat_direction(position, t)) * parameters.hostile_speed
+            }
+            AiState::Attack => {
+                if let (Some(target), Some(target_position)) = (blackboard.target, target_position) {
+                    let in_range = position.distance(target_position) <= parameters.attack_range;
+                    if in_range && blackboard.attack_cooldown <= 0.0 && combatant {
+                        melee.write(MeleeAttack { entity });
+                        blackboard.attack_cooldown = parameters.attack_cooldown;
+                    } else if in_range && blackboard.attack_cooldown <= 0.0 {
+                        damage.write(DamageEvent {
+                            target,
+                            source: Some(entity),
+                            damage_type: DamageType::Physical,
+                            amount: attributes.map_or(10.0, |a| a.damage.value),
+                        });
+                        blackboard.attack_cooldown = parameters.attack_cooldown;
+                    }
+                }
+                Vec3::ZERO
+            }
+        };
+
+        movement.desired_velocity = direction * move_speed;
+    }
+}
+
+/// Applies [`AiMovement`] to the physics velocity (keeping gravity) or, without a
+/// rigid body, directly to the transform. Agents turn towards their movement.
+///
+/// # Parameters
+/// * `time` - Fixed time step.
+/// * `agents` - Moving AI entities.
+#[coverage(off)]
+pub(crate) fn apply_ai_movement(
+    time: Res<Time>,
+    mut agents: Query<(&AiMovement, &mut Transform, Option<&mut Velocity>), Without<Dead>>,
+) {
+    for (movement, mut transform, velocity) in agents.iter_mut() {
+        let desired = movement.desired_velocity;
+        match velocity {
+            Some(mut velocity) => {
+                velocity.linvel.x = desired.x;
+                velocity.linvel.z = desired.z;
+            }
+            None => transform.translation += desired * time.delta_secs(),
+        }
+
+        if desired.length_squared() > 0.01 {
+            let look_at = transform.translation + Vec3::new(desired.x, 0.0, desired.z);
+            transform.look_at(look_at, Vec3::Y);
+        }
+    }
+}
+
+/// Distance on the XZ plane.
+fn flat_distance(a: Vec3, b: Vec3) -> f32 {
+    Vec2::new(a.x - b.x, a.z - b.z).length()
+}
+
+/// Normalized direction on the XZ plane from `from` to `to`, zero if both are equal.
+fn flat_direction(from: Vec3, to: Vec3) -> Vec3 {
+    Vec3::new(to.x - from.x, 0.0, to.z - from.z).normalize_or_zero()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use bevy::time::TimeUpdateStrategy;
+    use game_models::ai::behaviour::{AiCondition, AiParameters, AiTransition, AiTransitionFrom};
+
+    fn test_profile() -> AiProfileDefinition {
+        AiProfileDefinition {
+            id: "test".to_string(),
+            initial: AiState::Idle,
+            parameters: AiParameters {
+                hearing_range: 10.0,
+                wander_radius: 6.0,
+                ..default()
+            },
+            transitions: vec![
+                AiTransition {
+                    from: AiTransitionFrom::Group(AiStateGroup::Calm),
+                    to: AiState::Investigate,
+                    when: AiCondition::HeardNoise,
+                },
+                AiTransition {
+                    from: AiTransitionFrom::State(AiState::Investigate),
+                    to: AiState::ReturnHome,
+                    when: AiCondition::TimeInState(0.5),
+                },
+                AiTransition {
+                    from: AiTransitionFrom::State(AiState::Idle),
+                    to: AiState::Wander,
+                    when: AiCondition::Chance(0.1),
+                },
+            ],
+        }
+    }
+
+    /// Headless app which runs the perception and state machine on a 50 Hz fixed step.
+    /// Every update advances the time by exactly one fixed step.
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(Time::<Fixed>::from_hz(50.0))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(20)))
+            .init_resource::<Weather>()
+            .init_resource::<Assets<AiProfileDefinition>>()
+            .init_resource::<DefinitionLibrary<AiProfileDefinition>>()
+            .add_message::<NoiseEvent>()
+            .add_message::<AiStateChanged>()
+            .add_systems(FixedUpdate, (attach_ai_brains, hear_noises, update_state_machines).chain());
+
+        let handle = app.world_mut().resource_mut::<Assets<AiProfileDefinition>>().add(test_profile());
+        app.world_mut().resource_mut::<DefinitionLibrary<AiProfileDefinition>>().insert("test", handle);
+        app
+    }
+
+    fn spawn_agent(app: &mut App) -> Entity {
+        let agent = app.world_mut().spawn((AiProfile("test".to_string()), Transform::default())).id();
+        app.update();
+        agent
+    }
+
+    fn noise(app: &mut App, position: Vec3, radius: f32) {
+        app.world_mut().write_message(NoiseEvent { position, radius, source: None });
+    }
+
+    #[test]
+    fn heard_noise_is_investigated_and_forgotten() {
+        let mut app = test_app();
+        let agent = spawn_agent(&mut app);
+
+        noise(&mut app, Vec3::new(5.0, 0.0, 0.0), 8.0);
+        app.update();
+
+        let brain = app.world().get::<AiBrain>(agent).unwrap();
+        let blackboard = app.world().get::<Blackboard>(agent).unwrap();
+        assert_eq!(brain.state, AiState::Investigate);
+        assert_eq!(blackboard.destination, Some(Vec3::new(5.0, 0.0, 0.0)));
+        assert_eq!(blackboard.noise_position, None);
+
+        noise(&mut app, Vec3::new(-3.0, 0.0, 0.0), 8.0);
+        for _ in 0..30 {
+            app.update();
+        }
+
+        let brain = app.world().get::<AiBrain>(agent).unwrap();
+        let blackboard = app.world().get::<Blackboard>(agent).unwrap();
+        assert_eq!(brain.state, AiState::ReturnHome);
+        assert_eq!(blackboard.noise_position, None);
+    }
+
+    #[test]
+    fn noise_carries_at_most_its_radius() {
+        let mut app = test_app();
+        let agent = spawn_agent(&mut app);
+
+        noise(&mut app, Vec3::new(6.0, 0.0, 0.0), 4.0);
+        app.update();
+
+        assert_ne!(app.world().get::<AiBrain>(agent).unwrap().state, AiState::Investigate);
+        assert_eq!(app.world().get::<Blackboard>(agent).unwrap().noise_position, None);
+    }
+
+    #[test]
+    fn same_entities_behave_the_same() {
+        let run = || {
+            let mut app = test_app();
+            let agent = spawn_agent(&mut app);
+            for _ in 0..100 {
+                app.update();
+            }
+            let brain = app.world().get::<AiBrain>(agent).unwrap().clone();
+            let blackboard = app.world().get::<Blackboard>(agent).unwrap().clone();
+            (brain.state, brain.time_in_state, blackboard.destination)
+        };
+
+        assert_eq!(run(), run());
+    }
+}
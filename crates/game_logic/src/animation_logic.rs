@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use bevy::animation::AnimationSystems;
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use bevy::transform::TransformSystems;
+use game_models::animation::{marker_passed, AnimationEventFired, AnimationInput, AnimationSets, AnimationStateChanged, CharacterAnimation, CLIP_START};
+use game_models::combat::{AttackStarted, Combatant, HitOutcome, MeleeHit, Staggered};
+use game_models::health::Dead;
+use game_models::projectiles::ProjectileHit;
+use game_models::ragdoll::Ragdoll;
+use game_models::states::is_state_simulating;
+
+pub struct AnimationLogicComponent;
+
+impl Plugin for AnimationLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            build_animation_graphs,
+                            bind_animation_players,
+                            record_animation_triggers,
+                            update_animation_states,
+                            emit_animation_events
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+        app.add_systems(PostUpdate,
+                        apply_root_motion
+                            .after(AnimationSystems)
+                            .before(TransformSystems::Propagate)
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Loads the glTF source of the animation set and builds an animation graph with one
+/// node per clip once it is loaded.
+///
+/// # Parameters
+/// * `asset_server` - Loads the glTF sources.
+/// * `sets` - Animation set definitions.
+/// * `gltfs` - Loaded glTF files with their named animations.
+/// * `graphs` - Storage of the built graphs.
+/// * `characters` - Animated characters without a graph.
+#[coverage(off)]
+fn build_animation_graphs(
+    asset_server: Res<AssetServer>,
+    sets: AnimationSets,
+    gltfs: Res<Assets<Gltf>>,
+    mut graphs: ResMut<Assets<AnimationGraph>>,
+    mut characters: Query<&mut CharacterAnimation>,
+) {
+    for mut animation in characters.iter_mut() {
+        if animation.graph.is_some() {
+            continue;
+        }
+        let Some(set) = sets.get(&animation.set) else {
+            continue;
+        };
+        let Some(source) = animation.source.clone() else {
+            animation.source = Some(asset_server.load(set.source.clone()));
+            continue;
+        };
+        let Some(gltf) = gltfs.get(&source) else {
+            continue;
+        };
+
+        let mut graph = AnimationGraph::new();
+        let mut nodes = HashMap::new();
+        for (key, clip) in set.clips.iter() {
+            match gltf.named_animations.get(clip.clip.as_str()) {
+                Some(handle) => {
+                    let root = graph.root;
+                    nodes.insert(key.clone(), graph.add_clip(handle.clone(), 1.0, root));
+                }
+                None => warn!("Animation '{}' of set '{}' is missing in '{}'", clip.clip, set.id, set.source),
+            }
+        }
+        animation.nodes = nodes;
+        animation.graph = Some(graphs.add(graph));
+    }
+}
+
+/// Binds the [`AnimationPlayer`] in the spawned scene of a character to its graph and
+/// looks up the root bone.
+///
+/// # Parameters
+/// * `commands` - Adds the graph and transitions to the players.
+/// * `sets` - Animation set definitions with the root bone names.
+/// * `characters` - Animated characters with a graph but without a player.
+/// * `children` - Hierarchy of the scenes.
+/// * `animation_players` - Players spawned with the scenes.
+/// * `bones` - Names and transforms of the scene nodes.
+#[coverage(off)]
+fn bind_animation_players(
+    mut commands: Commands,
+    sets: AnimationSets,
+    mut characters: Query<(Entity, &mut CharacterAnimation)>,
+    children: Query<&Children>,
+    animation_players: Query<(), With<AnimationPlayer>>,
+    bones: Query<(&Name, &Transform)>,
+) {
+    for (entity, mut animation) in characters.iter_mut() {
+        if animation.player.is_some() {
+            continue;
+        }
+        let Some(graph) = animation.graph.clone() else {
+            continue;
+        };
+        let Some(player) = children.iter_descendants(entity).find(|descendant| animation_players.contains(*descendant)) else {
+            continue;
+        };
+
+        commands.entity(player).insert((AnimationGraphHandle(graph), AnimationTransitions::new()));
+        animation.player = Some(player);
+        animation.clip = None;
+
+        let root_bone = sets.get(&animation.set).and_then(|set| set.root_bone.clone());
+        animation.root_bone = root_bone.and_then(|bone| children.iter_descendants(entity)
+            .find(|descendant| bones.get(*descendant).is_ok_and(|(name, _)| name.as_str() == bone)));
+        if let Some((_, transform)) = animation.root_bone.and_then(|bone| bones.get(bone).ok()) {
+            animation.root_rest = transform.translation;
+        }
+    }
+}
+
+/// Remembers the clip of started attacks and holds the hit state of hit characters.
+///
+/// # Parameters
+/// * `attacks` - Started attack steps.
+/// * `melee_hits` - Melee hits, blocked and parried ones play no hit reaction.
+/// * `projectile_hits` - Projectile hits.
+/// * `sets` - Animation set definitions with the hit duration.
+/// * `characters` - Animated characters.
+#[coverage(off)]
+fn record_animation_triggers(
+    mut attacks: MessageReader<AttackStarted>,
+    mut melee_hits: MessageReader<MeleeHit>,
+    mut projectile_hits: MessageReader<ProjectileHit>,
+    sets: AnimationSets,
+    mut characters: Query<&mut CharacterAnimation>,
+) {
+    for attack in attacks.read() {
+        if let Ok(mut animation) = characters.get_mut(attack.entity) {
+            animation.attack_clip = attack.animation.clone();
+            // Restarts the clip if consecutive steps share it.
+            animation.clip = None;
+        }
+    }
+
+    let hit_targets = melee_hits.read()
+        .filter(|hit| hit.outcome == HitOutcome::Hit)
+        .map(|hit| hit.target)
+        .chain(projectile_hits.read().map(|hit| hit.target));
+    for target in hit_targets {
+        let Ok(mut animation) = characters.get_mut(target) else {
+            continue;
+        };
+        if let Some(set) = sets.get(&animation.set) {
+            animation.hit_remaining = set.hit_duration;
+        }
+    }
+}
+
+/// Measures the velocity of animated characters, selects their state and blends into the
+/// clip of the state when it changed. Ragdolls are driven by physics instead.
+///
+/// # Parameters
+/// * `time` - Global time.
+/// * `sets` - Animation set definitions.
+/// * `characters` - Animated characters with their movement and combat state, without ragdolls.
+/// * `players` - Bound animation players.
+/// * `changed` - Notification writer for state changes.
+#[coverage(off)]
+fn update_animation_states(
+    time: Res<Time>,
+    sets: AnimationSets,
+    mut characters: Query<(Entity, &mut CharacterAnimation, &GlobalTransform, Option<&Combatant>, Has<Dead>, Has<Staggered>), Without<Ragdoll>>,
+    mut players: Query<(&mut AnimationPlayer, &mut AnimationTransitions)>,
+    mut changed: MessageWriter<AnimationStateChanged>,
+) {
+    let delta = time.delta_secs();
+    for (entity, mut animation, transform, combatant, dead, staggered) in characters.iter_mut() {
+        let position = transform.translation();
+        if delta > 0.0
+            && let Some(last) = animation.last_position {
+            animation.velocity = (position - last) / delta;
+        }
+        animation.last_position = Some(position);
+        animation.hit_remaining = (animation.hit_remaining - delta).max(0.0);
+
+        let Some(set) = sets.get(&animation.set) else {
+            continue;
+        };
+        let attacking = combatant.is_some_and(Combatant::is_attacking);
+        if !attacking {
+            animation.attack_clip = None;
+        }
+        let state = set.select_state(&AnimationInput {
+            velocity: animation.velocity,
+            attacking,
+            hit: staggered || animation.hit_remaining > 0.0,
+            dead,
+        });
+        if state != animation.state {
+            changed.write(AnimationStateChanged { entity, from: animation.state, to: state });
+            animation.state = state;
+        }
+
+        let Some(key) = set.clip_for(state, animation.attack_clip.as_deref()) else {
+            continue;
+        };
+        if animation.clip.as_deref() == Some(key) {
+            continue;
+        }
+        let (Some(player), Some(&node), Some(clip)) = (animation.player, animation.nodes.get(key), set.clips.get(key)) else {
+            continue;
+        };
+        let Ok((mut animation_player, mut transitions)) = players.get_mut(player) else {
+            continue;
+        };
+
+        let active = transitions.play(&mut animation_player, node, Duration::from_secs_f32(set.blend_for(key)));
+        active.set_speed(clip.speed);
+        if clip.looping {
+            active.repeat();
+        }
+        animation.clip = Some(key.to_string());
+        animation.last_seek = CLIP_START;
+        animation.last_root = None;
+    }
+}
+
+/// Emits the markers of the playing clips which the playback passed since the last frame.
+///
+/// # Parameters
+/// * `sets` - Animation set definitions with the markers.
+/// * `characters` - Animated characters.
+/// * `players` - Bound animation players.
+/// * `events` - Writer for the passed markers.
+#[coverage(off)]
+fn emit_animation_events(
+    sets: AnimationSets,
+    mut characters: Query<(Entity, &mut CharacterAnimation)>,
+    players: Query<&AnimationPlayer>,
+    mut events: MessageWriter<AnimationEventFired>,
+) {
+    for (entity, mut animation) in characters.iter_mut() {
+        let (Some(player), Some(node), Some(key)) = (animation.player, animation.current_node(), animation.clip.clone()) else {
+            continue;
+        };
+        let Some(clip) = sets.get(&animation.set).and_then(|set| set.clips.get(&key)) else {
+            continue;
+        };
+        let Some(seek) = players.get(player).ok()
+            .and_then(|player| player.animation(node))
+            .map(|active| active.seek_time()) else {
+            continue;
+        };
+
+        for marker in clip.events.iter().filter(|marker| marker_passed(marker.time, animation.last_seek, seek)) {
+            events.write(AnimationEventFired { entity, clip: key.clone(), event: marker.event.clone() });
+        }
+        animation.last_seek = seek;
+    }
+}
+
+/// Moves characters playing a root motion clip by the horizontal movement of their root
+/// bone and keeps the bone in place. Runs after the clips were sampled.
+///
+/// # Parameters
+/// * `sets` - Animation set definitions.
+/// * `characters` - Animated characters.
+/// * `players` - Bound animation players, to detect looping clips.
+/// * `bones` - Root bones.
+#[coverage(off)]
+fn apply_root_motion(
+    sets: AnimationSets,
+    mut characters: Query<(&mut CharacterAnimation, &mut Transform), Without<Ragdoll>>,
+    players: Query<&AnimationPlayer>,
+    mut bones: Query<&mut Transform, Without<CharacterAnimation>>,
+) {
+    for (mut animation, mut transform) in characters.iter_mut() {
+        let (Some(bone), Some(player), Some(node), Some(key)) = (animation.root_bone, animation.player, animation.current_node(), animation.clip.as_ref()) else {
+            continue;
+        };
+        let root_motion = sets.get(&animation.set)
+            .and_then(|set| set.clips.get(key))
+            .is_some_and(|clip| clip.root_motion);
+        if !root_motion {
+            continue;
+        }
+        let Ok(mut bone_transform) = bones.get_mut(bone) else {
+            continue;
+        };
+        let looped = players.get(player).ok()
+            .and_then(|player| player.animation(node))
+            .is_some_and(|active| active.seek_time() < animation.last_seek);
+
+        let current = bone_transform.translation;
+        if let Some(last) = animation.last_root
+            && !looped {
+            let delta = current - last;
+            transform.translation += transform.rotation * Vec3::new(delta.x, 0.0, delta.z);
+        }
+        animation.last_root = Some(current);
+        bone_transform.translation.x = animation.root_rest.x;
+        bone_transform.translation.z = animation.root_rest.z;
+    }
+}
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use game_models::attributes::{AttributeModifiers, Attributes};
+use game_models::health::Health;
+use game_models::states::is_state_simulating;
+
+pub struct AttributeLogicComponent;
+
+impl Plugin for AttributeLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, tick_attribute_modifiers.run_if(is_state_simulating));
+        app.add_systems(PostUpdate, (recompute_attributes, sync_max_health).chain());
+    }
+}
+
+/// Counts down timed [`AttributeModifiers`]. Change detection is only triggered
+/// when a modifier expired, so the attributes are not recomputed every frame.
+///
+/// # Parameters
+/// * `time` - Global time used to count down the modifiers.
+/// * `modifiers` - Modifiers of all entities with attributes.
+#[coverage(off)]
+fn tick_attribute_modifiers(time: Res<Time>, mut modifiers: Query<&mut AttributeModifiers>) {
+    let delta = time.delta_secs();
+    for mut modifiers in modifiers.iter_mut() {
+        if modifiers.bypass_change_detection().tick(delta) {
+            modifiers.set_changed();
+        }
+    }
+}
+
+/// Recomputes the final [`Attributes`] of all entities whose base values or modifiers changed.
+/// The recompute itself bypasses change detection to avoid an endless update loop.
+///
+/// # Parameters
+/// * `attributes` - Changed attributes together with their modifiers.
+#[coverage(off)]
+fn recompute_attributes(
+    mut attributes: Query<
+        (&mut Attributes, &AttributeModifiers),
+        Or<(Changed<Attributes>, Changed<AttributeModifiers>)>
+    >,
+) {
+    for (mut attributes, modifiers) in attributes.iter_mut() {
+        attributes.bypass_change_detection().recompute(modifiers);
+    }
+}
+
+/// Keeps [`Health::max`] in sync with the `max_health` attribute.
+///
+/// # Parameters
+/// * `targets` - Entities whose attributes changed this frame.
+#[coverage(off)]
+fn sync_max_health(
+    mut targets: Query<
+        (&Attributes, &mut Health),
+        Or<(Changed<Attributes>, Changed<AttributeModifiers>)>
+    >,
+) {
+    for (attributes, mut health) in targets.iter_mut() {
+        if attributes.max_health.value != health.max {
+            health.set_max(attributes.max_health.value);
+        }
+    }
+}
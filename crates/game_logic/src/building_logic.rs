@@ -0,0 +1,523 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_rapier3d::prelude::{ColliderDisabled, QueryFilter, RapierContext, ReadRapierContext};
+use game_models::ai::behaviour::AiTarget;
+use game_models::building::{snap_to_grid, BuildGhost, BuildMode, BuildingSettings, Door, PlaceStructure, PlacementError, PlacementFailed, Structure, StructureDefinition, StructureDestroyed, StructurePlaced, Structures};
+use game_models::config::GlobalConfig;
+use game_models::dialogue::is_dialogue_inactive;
+use game_models::entities::definition::{ColliderShape, DefinitionSpawner, EntityDefinition};
+use game_models::entities::player::Player;
+use game_models::health::Dead;
+use game_models::interaction::{Interactable, Interacted};
+use game_models::items::inventory::Inventory;
+use game_models::navigation::agent::RebakeNavChunk;
+use game_models::navigation::grid::NavMesh;
+use game_models::states::is_state_simulating;
+
+pub struct BuildingLogicComponent;
+
+impl Plugin for BuildingLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            build_mode_input.run_if(is_dialogue_inactive),
+                            update_build_target,
+                            sync_build_ghost,
+                            handle_place_requests,
+                            toggle_doors,
+                            collapse_destroyed_structures
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Writers for the notifications of [`handle_place_requests`].
+#[derive(SystemParam)]
+struct BuildingNotifications<'w> {
+    placed: MessageWriter<'w, StructurePlaced>,
+    failed: MessageWriter<'w, PlacementFailed>,
+    rebake: MessageWriter<'w, RebakeNavChunk>,
+}
+
+/// Center of the spawned entity for a structure standing on `position`.
+fn structure_center(entity: &EntityDefinition, position: Vec3) -> Vec3 {
+    position + Vec3::Y * entity.collider.as_ref().map_or(0.0, |shape| shape.half_height())
+}
+
+/// Hinge of a door spawned from `entity`: the left edge of its collider.
+fn door_hinge(entity: &EntityDefinition) -> Vec3 {
+    match entity.collider {
+        Some(ColliderShape::Cuboid { half_extents: [x, _, _] }) => Vec3::new(-x, 0.0, 0.0),
+        _ => Vec3::ZERO,
+    }
+}
+
+/// Bounding box (center and half size) of `entity` placed at `position` with the
+/// rotation `rotation` around the Y axis, shrunk by the overlap margin.
+fn placement_bounds(entity: &EntityDefinition, settings: &BuildingSettings, position: Vec3, rotation: f32) -> Option<(Vec3, Vec3)> {
+    let half = entity.collider.as_ref()?.half_extents();
+    let (sin, cos) = rotation.sin_cos();
+    let (sin, cos) = (sin.abs(), cos.abs());
+    let rotated = Vec3::new(cos * half.x + sin * half.z, half.y, sin * half.x + cos * half.z);
+    Some((structure_center(entity, position), (rotated - Vec3::splat(settings.overlap_margin)).max(Vec3::ZERO)))
+}
+
+fn bounds_overlap((a_center, a_half): (Vec3, Vec3), (b_center, b_half): (Vec3, Vec3)) -> bool {
+    (a_center - b_center).abs().cmplt(a_half + b_half).all()
+}
+
+/// Checks reach, cost and free space of a structure at `position`. Without physics the
+/// overlap check is skipped.
+#[allow(clippy::too_many_arguments)]
+fn check_placement(
+    context: Option<&RapierContext>,
+    settings: &BuildingSettings,
+    structure: &StructureDefinition,
+    entity: &EntityDefinition,
+    position: Vec3,
+    rotation: f32,
+    builder: Vec3,
+    inventory: &Inventory,
+) -> Result<(), PlacementError> {
+    if builder.distance(position) > settings.max_distance {
+        return Err(PlacementError::TooFar);
+    }
+    structure.check_cost(inventory)?;
+
+    if let (Some(context), Some(shape)) = (context, &entity.collider) {
+        let mut blocked = false;
+        context.intersect_shape(
+            structure_center(entity, position),
+            Quat::from_rotation_y(rotation),
+            &shape.to_shrunk_collider(settings.overlap_margin),
+            QueryFilter::default().exclude_sensors(),
+            |_| {
+                blocked = true;
+                false
+            },
+        );
+        if blocked {
+            return Err(PlacementError::Blocked);
+        }
+    }
+    Ok(())
+}
+
+/// Handles the build keys of the player. The build key cycles through the structures
+/// and leaves build mode after the last one, Escape leaves it directly. The right mouse
+/// button places the preview, the left one stays free for the camera.
+///
+/// # Parameters
+/// * `keyboard` - Keyboard input.
+/// * `mouse` - Mouse input.
+/// * `global_config` - Key bindings.
+/// * `mode` - Build mode of the player.
+/// * `structures` - Structure definitions.
+/// * `players` - Builders controlled by the player.
+/// * `requests` - Writer for placement requests.
+#[coverage(off)]
+fn build_mode_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    global_config: Res<GlobalConfig>,
+    mut mode: ResMut<BuildMode>,
+    structures: Structures,
+    players: Query<Entity, With<Player>>,
+    mut requests: MessageWriter<PlaceStructure>,
+) {
+    let input = &global_config.input_config;
+    if keyboard.just_pressed(input.get_build_mode_key()) {
+        let ids = structures.ids();
+        let next = match &mode.selected {
+            Some(selected) => ids
+                .iter()
+                .position(|id| *id == selected.as_str())
+                .and_then(|index| ids.get(index + 1)),
+            None => ids.first(),
+        };
+        mode.select(next.map(|id| id.to_string()));
+    }
+    if !mode.is_active() {
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::Escape) {
+        mode.select(None);
+        return;
+    }
+
+    let Some(structure) = mode.selected.as_deref().and_then(|id| structures.get(id)) else {
+        return;
+    };
+    if keyboard.just_pressed(input.get_build_rotate_key()) {
+        let step = structure.rotation_step;
+        mode.rotate(step);
+    }
+
+    if mouse.just_pressed(MouseButton::Right) && mode.can_place() {
+        let Some(position) = mode.target else {
+            return;
+        };
+        for builder in players.iter() {
+            requests.write(PlaceStructure {
+                builder,
+                structure: structure.id.clone(),
+                position,
+                rotation: mode.rotation,
+            });
+        }
+    }
+}
+
+/// Snaps the ground point under the cursor to the grid of the selected structure and
+/// checks whether the player can place it there. Falls back to the plane `y = 0` when
+/// the cursor ray hits no fixed collider.
+///
+/// # Parameters
+/// * `mode` - Build mode of the player.
+/// * `settings` - Reach and overlap margin.
+/// * `structures` - Structure definitions.
+/// * `windows` - Cursor position.
+/// * `cameras` - Cursor ray.
+/// * `players` - Position and inventory of the builder.
+/// * `rapier` - Ground ray cast and overlap checks.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn update_build_target(
+    mut mode: ResMut<BuildMode>,
+    settings: Res<BuildingSettings>,
+    structures: Structures,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    players: Query<(&GlobalTransform, &Inventory), With<Player>>,
+    rapier: ReadRapierContext,
+) {
+    let Some(selected) = mode.selected.clone() else {
+        return;
+    };
+    let context = rapier.single().ok();
+
+    let ray = windows
+        .single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+        .and_then(|cursor| cameras
+            .iter()
+            .find_map(|(camera, transform)| camera.viewport_to_world(transform, cursor).ok()));
+    let ground = ray.and_then(|ray| {
+        let distance = match &context {
+            Some(context) => context
+                .cast_ray(ray.origin, *ray.direction, 100.0, true, QueryFilter::only_fixed().exclude_sensors())
+                .map(|(_, distance)| distance),
+            None => None,
+        };
+        distance
+            .or_else(|| ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y)))
+            .map(|distance| ray.get_point(distance))
+    });
+
+    let (target, error) = match (structures.get(&selected), ground) {
+        (Some(structure), Some(ground)) => {
+            let target = snap_to_grid(ground, structure.grid);
+            let error = match (structures.entity(structure), players.single()) {
+                (Some(entity), Ok((builder, inventory))) => check_placement(
+                    context.as_ref(),
+                    &settings,
+                    structure,
+                    entity,
+                    target,
+                    mode.rotation,
+                    builder.translation(),
+                    inventory,
+                ).err(),
+                (None, _) => Some(PlacementError::UnknownStructure(selected.clone())),
+                (_, Err(_)) => Some(PlacementError::InvalidBuilder),
+            };
+            (Some(target), error)
+        }
+        (None, _) => (None, Some(PlacementError::UnknownStructure(selected.clone()))),
+        (_, None) => (None, None),
+    };
+
+    if mode.target != target || mode.error != error {
+        mode.target = target;
+        mode.error = error;
+    }
+}
+
+/// Keeps the transparent preview in sync with the build mode: spawns it for the
+/// selected structure, moves it to the target and colors it by the placement validity.
+///
+/// # Parameters
+/// * `mode` - Build mode of the player.
+/// * `settings` - Preview colors.
+/// * `structures` - Structure definitions.
+/// * `meshes` - Mesh assets for new previews.
+/// * `materials` - Preview materials.
+/// * `ghosts` - Existing previews.
+#[coverage(off)]
+fn sync_build_ghost(
+    mut commands: Commands,
+    mode: Res<BuildMode>,
+    settings: Res<BuildingSettings>,
+    structures: Structures,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut ghosts: Query<(Entity, &BuildGhost, &mut Transform, &mut Visibility, &MeshMaterial3d<StandardMaterial>)>,
+) {
+    let entity = mode.selected
+        .as_deref()
+        .and_then(|id| structures.get(id))
+        .and_then(|structure| structures.entity(structure));
+    let color = if mode.error.is_none() { settings.valid_color } else { settings.invalid_color };
+
+    let mut found = false;
+    for (ghost_entity, ghost, mut transform, mut visibility, material) in ghosts.iter_mut() {
+        if entity.is_none() || mode.selected.as_deref() != Some(ghost.structure.as_str()) {
+            commands.entity(ghost_entity).despawn();
+            continue;
+        }
+        found = true;
+
+        match (mode.target, entity) {
+            (Some(target), Some(entity)) => {
+                transform.translation = structure_center(entity, target);
+                transform.rotation = Quat::from_rotation_y(mode.rotation);
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            _ => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+        if let Some(material) = materials.get_mut(&material.0)
+            && material.base_color != color {
+            material.base_color = color;
+        }
+    }
+
+    if found {
+        return;
+    }
+    let (Some(selected), Some(entity)) = (&mode.selected, entity) else {
+        return;
+    };
+    let mesh = entity.model
+        .primitive_mesh()
+        .unwrap_or_else(|| Mesh::from(Cuboid::new(1.0, 1.0, 1.0)));
+    commands.spawn((
+        Name::new(format!("Build Ghost: {}", selected)),
+        BuildGhost { structure: selected.clone() },
+        Mesh3d(meshes.add(mesh)),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: color,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })),
+        Transform::default(),
+        Visibility::Hidden,
+    ));
+}
+
+/// Validates placement requests, takes the cost from the inventory of the builder and
+/// spawns the structure. The cost is only taken once the structure spawned. Structures
+/// placed this frame are not in the physics world yet, so later requests are checked
+/// against their bounding boxes as well. Structures get their durability from the
+/// entity definition, targetable ones are attacked by enemies like the player.
+///
+/// # Parameters
+/// * `requests` - Placement requests.
+/// * `notifications` - Writers for placed and failed structures and nav rebakes.
+/// * `spawner` - Spawns the structure entities.
+/// * `structures` - Structure definitions.
+/// * `settings` - Reach and overlap margin.
+/// * `rapier` - Overlap checks.
+/// * `builders` - Position and inventory of the builders.
+/// * `nav_mesh` - Chunk lookup for the nav rebake.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn handle_place_requests(
+    mut requests: MessageReader<PlaceStructure>,
+    mut notifications: BuildingNotifications,
+    mut spawner: DefinitionSpawner,
+    structures: Structures,
+    settings: Res<BuildingSettings>,
+    rapier: ReadRapierContext,
+    mut builders: Query<(&GlobalTransform, &mut Inventory)>,
+    nav_mesh: Res<NavMesh>,
+) {
+    let context = rapier.single().ok();
+    let mut placed: Vec<(Vec3, Vec3)> = Vec::new();
+
+    for request in requests.read() {
+        let checked = structures
+            .get(&request.structure)
+            .and_then(|structure| structures.entity(structure).map(|entity| (structure, entity)))
+            .ok_or_else(|| PlacementError::UnknownStructure(request.structure.clone()))
+            .and_then(|(structure, entity)| {
+                let (builder, inventory) = builders.get(request.builder).map_err(|_| PlacementError::InvalidBuilder)?;
+                check_placement(
+                    context.as_ref(),
+                    &settings,
+                    structure,
+                    entity,
+                    request.position,
+                    request.rotation,
+                    builder.translation(),
+                    inventory,
+                )?;
+                if placement_bounds(entity, &settings, request.position, request.rotation)
+                    .is_some_and(|bounds| placed.iter().any(|other| bounds_overlap(bounds, *other))) {
+                    return Err(PlacementError::Blocked);
+                }
+                Ok((structure, entity))
+            });
+
+        let (structure, entity) = match checked {
+            Ok(checked) => checked,
+            Err(error) => {
+                debug!("Placing '{}' failed for {:?}: {}", request.structure, request.builder, error);
+                notifications.failed.write(PlacementFailed {
+                    builder: request.builder,
+                    structure: request.structure.clone(),
+                    error,
+                });
+                continue;
+            }
+        };
+
+        let Ok((_, mut inventory)) = builders.get_mut(request.builder) else {
+            continue;
+        };
+        let mut remaining = inventory.clone();
+        if let Err(error) = structure.take_cost(&mut remaining) {
+            debug!("Placing '{}' failed for {:?}: {}", request.structure, request.builder, error);
+            notifications.failed.write(PlacementFailed {
+                builder: request.builder,
+                structure: request.structure.clone(),
+                error,
+            });
+            continue;
+        }
+
+        let transform = Transform::from_translation(structure_center(entity, request.position))
+            .with_rotation(Quat::from_rotation_y(request.rotation));
+        let Some(spawned) = spawner.spawn_from_definition(&structure.entity, transform) else {
+            continue;
+        };
+        *inventory = remaining;
+        placed.extend(placement_bounds(entity, &settings, request.position, request.rotation));
+
+        let mut entity_commands = spawner.commands().entity(spawned);
+        entity_commands.insert(Structure { definition: structure.id.clone() });
+        if structure.targetable {
+            entity_commands.insert(AiTarget);
+        }
+        if structure.door {
+            entity_commands.insert((Door::with_hinge(door_hinge(entity)), Interactable::new("interact.door_open", 2.0)));
+        }
+
+        notifications.placed.write(StructurePlaced {
+            entity: spawned,
+            builder: request.builder,
+            structure: structure.id.clone(),
+        });
+        notifications.rebake.write(RebakeNavChunk { chunk: nav_mesh.grid().chunk_at(request.position) });
+    }
+}
+
+/// Opens and closes doors on interaction. Open doors swing by 90 degrees around their
+/// hinge and lose their collision. Doors stay open while something stands in the way.
+///
+/// # Parameters
+/// * `interactions` - Interactions of any kind.
+/// * `doors` - Door structures.
+/// * `structures` - Structure definitions for the door colliders.
+/// * `settings` - Overlap margin.
+/// * `rapier` - Overlap checks before a door closes.
+/// * `rebake` - Writer for nav rebakes, doors change the walkable area.
+/// * `nav_mesh` - Chunk lookup for the nav rebake.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn toggle_doors(
+    mut commands: Commands,
+    mut interactions: MessageReader<Interacted>,
+    mut doors: Query<(&mut Door, &mut Interactable, &mut Transform, &Structure), Without<Dead>>,
+    structures: Structures,
+    settings: Res<BuildingSettings>,
+    rapier: ReadRapierContext,
+    mut rebake: MessageWriter<RebakeNavChunk>,
+    nav_mesh: Res<NavMesh>,
+) {
+    let context = rapier.single().ok();
+
+    for interaction in interactions.read() {
+        let Ok((mut door, mut interactable, mut transform, structure)) = doors.get_mut(interaction.target) else {
+            continue;
+        };
+
+        let swung = door.swing(&transform);
+        if door.open
+            && let Some(context) = &context
+            && let Some(shape) = structures
+                .get(&structure.definition)
+                .and_then(|structure| structures.entity(structure))
+                .and_then(|entity| entity.collider.as_ref()) {
+            let mut blocked = false;
+            context.intersect_shape(
+                swung.translation,
+                swung.rotation,
+                &shape.to_shrunk_collider(settings.overlap_margin),
+                QueryFilter::default().exclude_sensors().exclude_collider(interaction.target),
+                |_| {
+                    blocked = true;
+                    false
+                },
+            );
+            if blocked {
+                debug!("Door {:?} is blocked and stays open", interaction.target);
+                continue;
+            }
+        }
+
+        door.open = !door.open;
+        *transform = swung;
+        if door.open {
+            interactable.prompt = "interact.door_close".to_string();
+            commands.entity(interaction.target).insert(ColliderDisabled);
+        } else {
+            interactable.prompt = "interact.door_open".to_string();
+            commands.entity(interaction.target).remove::<ColliderDisabled>();
+        }
+        rebake.write(RebakeNavChunk { chunk: nav_mesh.grid().chunk_at(transform.translation) });
+    }
+}
+
+/// Disables the collision of structures whose durability reached zero until they are
+/// despawned and reports them.
+///
+/// # Parameters
+/// * `destroyed` - Structures which died this frame.
+/// * `notifications` - Writer for destroyed structures.
+/// * `rebake` - Writer for nav rebakes, the walkable area grows.
+/// * `nav_mesh` - Chunk lookup for the nav rebake.
+#[coverage(off)]
+fn collapse_destroyed_structures(
+    mut commands: Commands,
+    destroyed: Query<(Entity, &Structure, &GlobalTransform), Added<Dead>>,
+    mut notifications: MessageWriter<StructureDestroyed>,
+    mut rebake: MessageWriter<RebakeNavChunk>,
+    nav_mesh: Res<NavMesh>,
+) {
+    for (entity, structure, transform) in destroyed.iter() {
+        info!("Structure '{}' was destroyed", structure.definition);
+        commands.entity(entity).insert(ColliderDisabled);
+        notifications.write(StructureDestroyed {
+            entity,
+            structure: structure.definition.clone(),
+            position: transform.translation(),
+        });
+        rebake.write(RebakeNavChunk { chunk: nav_mesh.grid().chunk_at(transform.translation()) });
+    }
+}
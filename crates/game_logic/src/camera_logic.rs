@@ -2,30 +2,30 @@ use bevy::camera::visibility::RenderLayers;
 use bevy::input::mouse::{MouseMotion, MouseWheel};
 use bevy::prelude::*;
 use bevy::pbr::MeshMaterial3d;
+use bevy_rapier3d::prelude::{Collider, RigidBody};
 use game_models::camera::OrbitCamera;
+use game_models::ai::behaviour::AiTarget;
+use game_models::attributes::Attributes;
+use game_models::combat::Combatant;
+use game_models::entities::character::Character;
+use game_models::entities::definition::EntityStats;
 use game_models::entities::player::Player;
+use game_models::factions::Faction;
+use game_models::health::{Health, InvulnerabilityFrames};
+use game_models::interaction::Interactor;
+use game_models::items::crafting::Workstation;
+use game_models::items::equipment::Equipment;
+use game_models::items::inventory::Inventory;
+use game_models::quests::QuestLog;
+use game_models::survival::Needs;
 
 pub fn setup_test_scene(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    commands.insert_resource(AmbientLight {
-        color: Color::WHITE,
-        brightness: 75.0,
-        affects_lightmapped_meshes: false,
-    });
     spawn_test_player_cube(&mut commands, &mut meshes, &mut materials);
-    commands.spawn((
-        PointLight {
-            intensity: 12000.0,
-            range: 30.0,
-            color: Color::srgb(1.0, 0.95, 0.85),
-            shadows_enabled: false,
-            ..default()
-        },
-        Transform::from_xyz(6.0, 10.0, 6.0),
-    ));
+    commands.spawn((Name::new("Character"), Character::default()));
 
     commands.spawn((
         Camera3d::default(),
@@ -65,6 +65,23 @@ fn spawn_test_player_cube(
         Transform::from_xyz(0.0, 0.5, 0.0),
         RenderLayers::from_layers(&[0, 1, 2]),
         Player,
+        (
+            Health::new(100.0),
+            InvulnerabilityFrames::default(),
+            Attributes::from(&EntityStats::default()),
+            Needs::default(),
+            Combatant::default(),
+        ),
+        (
+            Inventory::new(24),
+            Equipment::default(),
+            Workstation::hand(),
+        ),
+        (AiTarget, Faction::player()),
+        Interactor::default(),
+        QuestLog::default(),
+        RigidBody::KinematicPositionBased,
+        Collider::cuboid(0.5, 0.5, 0.5),
     ));
 }
 
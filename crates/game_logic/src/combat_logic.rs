@@ -0,0 +1,486 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{ActiveCollisionTypes, ActiveEvents, Collider, ColliderDisabled, CollisionEvent, ExternalImpulse, RigidBody, Sensor};
+use game_models::ai::behaviour::{AiBrain, AiStateGroup, Blackboard};
+use game_models::attributes::Attributes;
+use game_models::combat::{AttackPhase, AttackStarted, CombatSettings, CombatStatus, Combatant, Combos, HitOutcome, HitStop, Hitbox, Knockback, MeleeAttack, MeleeHit, SetBlocking, Staggered};
+use game_models::config::GlobalConfig;
+use game_models::dialogue::is_dialogue_inactive;
+use game_models::entities::player::Player;
+use game_models::factions::Factions;
+use game_models::health::{DamageEvent, Dead, Health};
+use game_models::items::equipment::{Equipment, EquipmentSlot};
+use game_models::items::item::Items;
+use game_models::states::{is_state_simulating, AppState, InGameStates};
+use game_models::survival::{ExertStamina, Needs};
+
+pub struct CombatLogicComponent;
+
+impl Plugin for CombatLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            player_combat_input.run_if(is_dialogue_inactive),
+                            attach_hitboxes,
+                            tick_stagger,
+                            handle_combat_requests,
+                            advance_attacks,
+                            detect_melee_hits,
+                            apply_knockback,
+                            update_combat_state
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+        // Runs on real time, so a hit-stop always ends, even if the simulation stops.
+        app.add_systems(Update, apply_hit_stop);
+    }
+}
+
+/// Writers for the results of [`detect_melee_hits`].
+#[derive(SystemParam)]
+struct HitNotifications<'w> {
+    damage: MessageWriter<'w, DamageEvent>,
+    exert: MessageWriter<'w, ExertStamina>,
+    hits: MessageWriter<'w, MeleeHit>,
+}
+
+/// Combo of the next attack: the combo of the equipped weapon, then the combo of the
+/// combatant, then [`CombatSettings::default_combo`].
+fn resolve_combo(combatant: &Combatant, equipment: Option<&Equipment>, items: &Items, settings: &CombatSettings) -> String {
+    equipment
+        .and_then(|equipment| equipment.get(EquipmentSlot::Weapon))
+        .and_then(|weapon| items.get(weapon))
+        .and_then(|item| item.equipment.as_ref())
+        .and_then(|data| data.combo.clone())
+        .or_else(|| combatant.combo.clone())
+        .unwrap_or_else(|| settings.default_combo.clone())
+}
+
+/// Turns the attack and block keys into requests for the player.
+///
+/// # Parameters
+/// * `keyboard` - Keyboard input.
+/// * `global_config` - Key bindings.
+/// * `players` - Combat state of the player.
+/// * `attacks` - Writer for attacks.
+/// * `blocks` - Writer for block changes.
+#[coverage(off)]
+fn player_combat_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    global_config: Res<GlobalConfig>,
+    players: Query<(Entity, &Combatant), (With<Player>, Without<Dead>)>,
+    mut attacks: MessageWriter<MeleeAttack>,
+    mut blocks: MessageWriter<SetBlocking>,
+) {
+    let input = &global_config.input_config;
+    let attack = keyboard.just_pressed(input.get_attack_key());
+    let block = keyboard.pressed(input.get_block_key());
+
+    for (entity, combatant) in players.iter() {
+        if attack {
+            attacks.write(MeleeAttack { entity });
+        }
+        if block != combatant.blocking {
+            blocks.write(SetBlocking { entity, blocking: block });
+        }
+    }
+}
+
+/// Spawns the disabled hitbox sensor of new [`Combatant`]s.
+///
+/// # Parameters
+/// * `commands` - Spawns the sensor children.
+/// * `combatants` - Newly added combatants.
+#[coverage(off)]
+fn attach_hitboxes(
+    mut commands: Commands,
+    mut combatants: Query<(Entity, &mut Combatant), Added<Combatant>>,
+) {
+    for (entity, mut combatant, needs) in combatants.iter_mut() {
+        let hitbox = commands.spawn((
+            Name::new("Hitbox"),
+            Hitbox { owner: entity },
+            Collider::cuboid(0.5, 0.5, 0.5),
+            Sensor,
+            ActiveEvents::COLLISION_EVENTS,
+            ActiveCollisionTypes::all(),
+            ColliderDisabled,
+            Transform::default(),
+            ChildOf(entity),
+        )).id();
+        combatant.hitbox = Some(hitbox);
+    }
+}
+
+/// Counts down [`Staggered`] timers and removes the component once they finished.
+///
+/// # Parameters
+/// * `commands` - Removes finished staggers.
+/// * `time` - Global time used to tick the timers.
+/// * `staggered` - All staggered characters.
+#[coverage(off)]
+fn tick_stagger(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut staggered: Query<(Entity, &mut Staggered)>,
+) {
+    for (entity, mut staggered) in staggered.iter_mut() {
+        if staggered.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).remove::<Staggered>();
+        }
+    }
+}
+
+/// Starts or chains attacks and raises or lowers blocks. Staggered characters are
+/// ignored, characters with [`Needs`] need the stamina of the first attack.
+///
+/// # Parameters
+/// * `attacks` - Attack requests.
+/// * `blocks` - Block requests.
+/// * `started` - Notification writer for started attacks.
+/// * `exert` - Writer for the stamina costs.
+/// * `settings` - Default combo.
+/// * `combos` - Combo definitions.
+/// * `items` - Item definitions for weapon combos.
+/// * `combatants` - Combat state of the requesting characters.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn handle_combat_requests(
+    mut attacks: MessageReader<MeleeAttack>,
+    mut blocks: MessageReader<SetBlocking>,
+    mut started: MessageWriter<AttackStarted>,
+    mut exert: MessageWriter<ExertStamina>,
+    settings: Res<CombatSettings>,
+    combos: Combos,
+    items: Items,
+    mut combatants: Query<(&mut Combatant, Option<&Equipment>, Option<&Needs>), (Without<Dead>, Without<Staggered>)>,
+) {
+    for request in blocks.read() {
+        if let Ok((mut combatant, _, _)) = combatants.get_mut(request.entity) {
+            combatant.set_blocking(request.blocking);
+        }
+    }
+
+    for request in attacks.read() {
+        let Ok((mut combatant, equipment, needs)) = combatants.get_mut(request.entity) else {
+            continue;
+        };
+        // Chained attacks pay their stamina when their windup starts.
+        if let Some(current) = combatant.current.clone() {
+            combatant.request_attack(&current);
+            continue;
+        }
+
+        let combo_id = resolve_combo(&combatant, equipment, &items, &settings);
+        let Some(step) = combos.get(&combo_id).and_then(|combo| combo.attacks.first()) else {
+            debug!("Combo '{}' is not loaded", combo_id);
+            continue;
+        };
+        if needs.is_some_and(|needs| !needs.has_stamina(step.stamina_cost)) {
+            continue;
+        }
+
+        if combatant.request_attack(&combo_id) {
+            exert.write(ExertStamina { entity: request.entity, amount: step.stamina_cost });
+            started.write(AttackStarted {
+                entity: request.entity,
+                combo: combo_id,
+                step: 0,
+                animation: step.animation.clone(),
+            });
+        }
+    }
+}
+
+/// Advances the running attacks. The hitbox gets the shape of the attack and is
+/// enabled during the active phase, chained attacks cost their stamina on start.
+/// Characters with [`Needs`] which can't pay a chained attack end the combo instead.
+///
+/// # Parameters
+/// * `commands` - Enables and disables the hitboxes.
+/// * `time` - Global time.
+/// * `combos` - Combo definitions.
+/// * `combatants` - Combat state and stamina of all characters.
+/// * `hitboxes` - Hitbox sensors.
+/// * `started` - Notification writer for chained attacks.
+/// * `exert` - Writer for the stamina costs of chained attacks.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn advance_attacks(
+    mut commands: Commands,
+    time: Res<Time>,
+    combos: Combos,
+    mut combatants: Query<(Entity, &mut Combatant, Option<&Needs>), Without<Dead>>,
+    mut hitboxes: Query<(&mut Collider, &mut Transform), With<Hitbox>>,
+    mut started: MessageWriter<AttackStarted>,
+    mut exert: MessageWriter<ExertStamina>,
+) {
+    let delta = time.delta_secs();
+    for (entity, mut combatant) in combatants.iter_mut() {
+        let Some(combo) = combatant.current.as_deref().and_then(|id| combos.get(id)) else {
+            if combatant.blocking {
+                combatant.block_time += delta;
+            }
+            continue;
+        };
+
+        for phase in combatant.tick(combo, delta) {
+            let step = combo.attacks.get(combatant.step);
+            let Some(hitbox) = combatant.hitbox else {
+                continue;
+            };
+            match (phase, step) {
+                (AttackPhase::Active, Some(step)) => {
+                    if let Ok((mut collider, mut transform)) = hitboxes.get_mut(hitbox) {
+                        let [x, y, z] = step.hitbox.half_extents;
+                        *collider = Collider::cuboid(x, y, z);
+                        transform.translation = Vec3::from_array(step.hitbox.offset);
+                    }
+                    commands.entity(hitbox).remove::<ColliderDisabled>();
+                }
+                (AttackPhase::Windup, Some(step)) => {
+                    if needs.is_some_and(|needs| !needs.has_stamina(step.stamina_cost)) {
+                        combatant.interrupt();
+                        commands.entity(hitbox).insert(ColliderDisabled);
+                        break;
+                    }
+                    exert.write(ExertStamina { entity, amount: step.stamina_cost });
+                    started.write(AttackStarted {
+                        entity,
+                        combo: combo.id.clone(),
+                        step: combatant.step,
+                        animation: step.animation.clone(),
+                    });
+                }
+                _ => {
+                    commands.entity(hitbox).insert(ColliderDisabled);
+                }
+            }
+        }
+    }
+}
+
+/// Resolves hitbox contacts into hits. Every target is hit once per attack; blocking
+/// defenders which face the attacker take reduced damage, a block raised within the
+/// parry window staggers the attacker instead. Hits push the target away and trigger
+/// the hit-stop. Friends pass through the hitbox unless friendly fire is enabled.
+///
+/// # Parameters
+/// * `commands` - Staggers parried attackers and applies the knockback.
+/// * `collisions` - Rapier collision events.
+/// * `hitboxes` - Hitbox sensors and their owners.
+/// * `fighters` - Combat state, position and damage of attackers and targets.
+/// * `bodies` - Rigid bodies of the targets, only dynamic ones take impulses.
+/// * `factions` - Friendly fire rules.
+/// * `combos` - Combo definitions for the attack data.
+/// * `settings` - Block and parry tuning.
+/// * `hit_stop` - Hit-stop of the game.
+/// * `notifications` - Writers for damage, block stamina and hits.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn detect_melee_hits(
+    mut commands: Commands,
+    mut collisions: MessageReader<CollisionEvent>,
+    hitboxes: Query<&Hitbox>,
+    mut fighters: Query<(Option<&mut Combatant>, &GlobalTransform, Option<&Attributes>, Has<Health>, Has<Dead>)>,
+    bodies: Query<&RigidBody>,
+    factions: Factions,
+    combos: Combos,
+    settings: Res<CombatSettings>,
+    mut hit_stop: ResMut<HitStop>,
+    mut notifications: HitNotifications,
+) {
+    let contacts: Vec<(Entity, Entity)> = collisions
+        .read()
+        .filter_map(|collision| match collision {
+            CollisionEvent::Started(a, b, _) => Some((*a, *b)),
+            CollisionEvent::Stopped(..) => None,
+        })
+        .flat_map(|(a, b)| [(a, b), (b, a)])
+        .filter_map(|(sensor, target)| {
+            let hitbox = hitboxes.get(sensor).ok()?;
+            (hitbox.owner != target && factions.can_damage(hitbox.owner, target)).then_some((hitbox.owner, target))
+        })
+        .collect();
+
+    for (attacker, target) in contacts {
+        let Ok((Some(combatant), attacker_transform, attributes, _, false)) = fighters.get(attacker) else {
+            continue;
+        };
+        if combatant.phase != AttackPhase::Active || combatant.hit.contains(&target) {
+            continue;
+        }
+        let Some(step) = combatant.current
+            .as_deref()
+            .and_then(|id| combos.get(id))
+            .and_then(|combo| combo.attacks.get(combatant.step))
+        else {
+            continue;
+        };
+        let Ok((defender, target_transform, _, true, false)) = fighters.get(target) else {
+            continue;
+        };
+
+        let attacker_position = attacker_transform.translation();
+        let target_position = target_transform.translation();
+        let to_attacker = (attacker_position - target_position).normalize_or_zero();
+        let outcome = settings.resolve(defender, target_transform.forward().dot(to_attacker));
+
+        let base_damage = attributes.map_or(10.0, |attributes| attributes.damage.value) * step.damage_multiplier;
+        let (damage, knockback) = match outcome {
+            HitOutcome::Hit => (base_damage, step.knockback),
+            HitOutcome::Blocked => (base_damage * (1.0 - settings.block_reduction), step.knockback * settings.blocked_knockback),
+            HitOutcome::Parried => (0.0, 0.0),
+        };
+        let damage_type = step.damage_type;
+        let hit_stop_duration = step.hit_stop;
+        let combo = combatant.current.clone().unwrap_or_default();
+        let step = combatant.step;
+
+        if let Ok((Some(mut combatant), ..)) = fighters.get_mut(attacker) {
+            combatant.hit.push(target);
+            if outcome == HitOutcome::Parried {
+                combatant.interrupt();
+                if let Some(hitbox) = combatant.hitbox {
+                    commands.entity(hitbox).insert(ColliderDisabled);
+                }
+                commands.entity(attacker).insert(Staggered(Timer::from_seconds(settings.parry_stagger, TimerMode::Once)));
+            }
+        }
+
+        if damage > 0.0 {
+            notifications.damage.write(DamageEvent {
+                target,
+                source: Some(attacker),
+                damage_type,
+                amount: damage,
+            });
+        }
+        if outcome == HitOutcome::Blocked {
+            notifications.exert.write(ExertStamina { entity: target, amount: settings.block_stamina_cost });
+        }
+        if knockback > 0.0 {
+            let direction = Vec3::new(-to_attacker.x, 0.0, -to_attacker.z).normalize_or_zero();
+            if matches!(bodies.get(target), Ok(RigidBody::Dynamic)) {
+                commands.entity(target).insert(ExternalImpulse {
+                    impulse: direction * knockback,
+                    ..default()
+                });
+            } else {
+                commands.entity(target).insert(Knockback::new(direction * knockback, settings.knockback_duration));
+            }
+        }
+        hit_stop.trigger(hit_stop_duration);
+
+        debug!("{:?} hit {:?}: {:?} for {}", attacker, target, outcome, damage);
+        notifications.hits.write(MeleeHit { attacker, target, combo, step, outcome, damage });
+    }
+}
+
+/// Moves kinematic targets by their [`Knockback`] and removes it once it faded out.
+///
+/// # Parameters
+/// * `commands` - Removes finished knockbacks.
+/// * `time` - Global time.
+/// * `targets` - Pushed targets.
+#[coverage(off)]
+fn apply_knockback(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut targets: Query<(Entity, &mut Knockback, &mut Transform)>,
+) {
+    for (entity, mut knockback, mut transform) in targets.iter_mut() {
+        transform.translation += knockback.advance(time.delta_secs());
+        if knockback.is_finished() {
+            commands.entity(entity).remove::<Knockback>();
+        }
+    }
+}
+
+/// Slows the virtual time down while a hit-stop is running and restores it afterward.
+///
+/// # Parameters
+/// * `hit_stop` - Remaining hit-stop.
+/// * `settings` - Game speed during hit-stop.
+/// * `real_time` - Unscaled time which counts the hit-stop down.
+/// * `virtual_time` - Game time which is slowed down.
+#[coverage(off)]
+fn apply_hit_stop(
+    mut hit_stop: ResMut<HitStop>,
+    settings: Res<CombatSettings>,
+    real_time: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    if hit_stop.remaining > 0.0 {
+        hit_stop.remaining = (hit_stop.remaining - real_time.delta_secs()).max(0.0);
+        let speed = if hit_stop.remaining > 0.0 { settings.hit_stop_speed } else { 1.0 };
+        if virtual_time.relative_speed() != speed {
+            virtual_time.set_relative_speed(speed);
+        }
+    }
+}
+
+/// Engages combat when a hostile enemy targets the player within
+/// [`CombatSettings::engage_distance`] and disengages after
+/// [`CombatSettings::disengage_delay`] seconds without one. The [`CombatStatus`] is
+/// tracked in every simulating state, the in-game states switch between
+/// `InGameStates::Game` and `InGameStates::Combat` with it.
+///
+/// # Parameters
+/// * `time` - Global time for the disengage delay.
+/// * `settings` - Engage distance and disengage delay.
+/// * `state` - Current app state.
+/// * `next_state` - Receives the combat transitions.
+/// * `status` - Whether combat is engaged.
+/// * `players` - Position of the player.
+/// * `agents` - State and target of the AI.
+/// * `calm` - Seconds without engaged enemies.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn update_combat_state(
+    time: Res<Time>,
+    settings: Res<CombatSettings>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut status: ResMut<CombatStatus>,
+    players: Query<(Entity, &GlobalTransform), (With<Player>, Without<Dead>)>,
+    agents: Query<(&AiBrain, &Blackboard, &GlobalTransform), Without<Dead>>,
+    mut calm: Local<f32>,
+) {
+    // Another transition (e.g. game over) was requested this frame.
+    if matches!(*next_state, NextState::Pending(_)) {
+        return;
+    }
+    let Ok((player, player_transform)) = players.single() else {
+        return;
+    };
+
+    let engaged = agents.iter().any(|(brain, blackboard, transform)| {
+        brain.state.group() == AiStateGroup::Hostile
+            && blackboard.target == Some(player)
+            && transform.translation().distance(player_transform.translation()) <= settings.engage_distance
+    });
+
+    if engaged {
+        *calm = 0.0;
+        if !status.engaged {
+            info!("Enemies engaged, entering combat");
+            status.engaged = true;
+        }
+    } else if status.engaged {
+        *calm += time.delta_secs();
+        if *calm >= settings.disengage_delay {
+            info!("No enemies engaged, leaving combat");
+            *calm = 0.0;
+            status.engaged = false;
+        }
+    }
+
+    // The test scene has no in-game states and only tracks the status.
+    if let AppState::InGame(current) = state.get() {
+        let target = if status.engaged { InGameStates::Combat } else { InGameStates::Game };
+        if *current != target {
+            next_state.set(AppState::InGame(target));
+        }
+    }
+}
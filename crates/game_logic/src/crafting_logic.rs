@@ -0,0 +1,357 @@
+use bevy::prelude::*;
+use game_models::interaction::Interactable;
+use game_models::items::crafting::{CancelCrafting, CraftItem, CraftingCompleted, CraftingError, CraftingFailed, CraftingJob, CraftingProgressed, CraftingQueued, Recipes, Workstation, CRAFTING_RANGE};
+use game_models::items::equipment::Equipment;
+use game_models::items::inventory::{Inventory, ItemStack};
+use game_models::items::item::Items;
+use game_models::items::WorldItem;
+use game_models::states::is_state_simulating;
+
+pub struct CraftingLogicComponent;
+
+impl Plugin for CraftingLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            handle_craft_requests,
+                            handle_cancel_crafting,
+                            tick_workstations
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Adds `stacks` to the inventory and spawns whatever doesn't fit as [`WorldItem`] at
+/// `position`.
+pub(crate) fn give_or_drop(
+    commands: &mut Commands,
+    mut inventory: Option<&mut Inventory>,
+    stacks: &[ItemStack],
+    position: Vec3,
+    items: &Items,
+) {
+    for stack in stacks.iter() {
+        let remaining = match inventory.as_deref_mut() {
+            Some(inventory) => inventory.add(&stack.item, stack.count, |id| items.stack_size(id)),
+            None => stack.count,
+        };
+        if remaining > 0 {
+            commands.spawn((
+                Name::new(format!("Item: {}", stack.item)),
+                Transform::from_translation(position),
+                WorldItem(ItemStack::new(stack.item.clone(), remaining)),
+                Interactable::new("interact.pick_up", 1.5),
+            ));
+        }
+    }
+}
+
+/// Validates a single craft request, consumes the inputs and queues the job. The
+/// inventory is left untouched if any input can't be removed.
+fn queue_job(
+    request: &CraftItem,
+    recipes: &Recipes,
+    workstations: &mut Query<(&mut Workstation, &GlobalTransform)>,
+    crafters: &mut Query<(&mut Inventory, Option<&Equipment>, &GlobalTransform)>,
+) -> Result<(), CraftingError> {
+    let recipe = recipes.get(&request.recipe).ok_or_else(|| CraftingError::UnknownRecipe(request.recipe.clone()))?;
+    let (mut workstation, workstation_transform) = workstations.get_mut(request.workstation).map_err(|_| CraftingError::InvalidTarget)?;
+    let (mut inventory, equipment, crafter_transform) = crafters.get_mut(request.crafter).map_err(|_| CraftingError::InvalidTarget)?;
+
+    if crafter_transform.translation().distance(workstation_transform.translation()) > CRAFTING_RANGE {
+        return Err(CraftingError::TooFar);
+    }
+    recipe.validate(&workstation, &inventory, equipment)?;
+
+    let mut remaining = inventory.clone();
+    for input in recipe.total_inputs() {
+        remaining.remove(&input.item, input.count).map_err(|_| CraftingError::MissingInput {
+            required: input.count,
+            available: inventory.count(&input.item),
+            item: input.item,
+        })?;
+    }
+    *inventory = remaining;
+    workstation.queue.push_back(CraftingJob {
+        recipe: recipe.id.clone(),
+        crafter: request.crafter,
+        elapsed: 0.0,
+        duration: recipe.time.max(0.0),
+    });
+    Ok(())
+}
+
+/// Validates craft requests against the workstation, its distance and the inventory of
+/// the crafter, consumes the inputs and queues the job.
+///
+/// # Parameters
+/// * `requests` - Craft requests.
+/// * `queued` - Notification writer for queued jobs.
+/// * `failed` - Notification writer for rejected requests.
+/// * `recipes` - Recipe definitions.
+/// * `workstations` - Workstations, their queues and positions.
+/// * `crafters` - Inventory, equipment and position of the crafters.
+#[coverage(off)]
+fn handle_craft_requests(
+    mut requests: MessageReader<CraftItem>,
+    mut queued: MessageWriter<CraftingQueued>,
+    mut failed: MessageWriter<CraftingFailed>,
+    recipes: Recipes,
+    mut workstations: Query<(&mut Workstation, &GlobalTransform)>,
+    mut crafters: Query<(&mut Inventory, Option<&Equipment>, &GlobalTransform)>,
+) {
+    for request in requests.read() {
+        let result = queue_job(request, &recipes, &mut workstations, &mut crafters);
+
+        match result {
+            Ok(()) => {
+                queued.write(CraftingQueued {
+                    crafter: request.crafter,
+                    workstation: request.workstation,
+                    recipe: request.recipe.clone(),
+                });
+            }
+            Err(error) => {
+                debug!("Crafting '{}' failed for {:?}: {}", request.recipe, request.crafter, error);
+                failed.write(CraftingFailed {
+                    crafter: request.crafter,
+                    recipe: request.recipe.clone(),
+                    error,
+                });
+            }
+        }
+    }
+}
+
+/// Removes cancelled jobs from their queue and refunds the inputs to the crafter.
+///
+/// # Parameters
+/// * `requests` - Cancel requests.
+/// * `recipes` - Recipe definitions for the refunded inputs.
+/// * `items` - Item definitions for stack sizes.
+/// * `workstations` - Workstations and their queues.
+/// * `inventories` - Inventories of the crafters.
+#[coverage(off)]
+fn handle_cancel_crafting(
+    mut commands: Commands,
+    mut requests: MessageReader<CancelCrafting>,
+    recipes: Recipes,
+    items: Items,
+    mut workstations: Query<(&mut Workstation, &GlobalTransform)>,
+    mut inventories: Query<&mut Inventory>,
+) {
+    for request in requests.read() {
+        let Ok((mut workstation, transform)) = workstations.get_mut(request.workstation) else {
+            continue;
+        };
+        let Some(job) = workstation.queue.remove(request.index) else {
+            continue;
+        };
+        let Some(recipe) = recipes.get(&job.recipe) else {
+            continue;
+        };
+
+        give_or_drop(
+            &mut commands,
+            inventories.get_mut(job.crafter).ok().as_deref_mut(),
+            &recipe.inputs,
+            transform.translation(),
+            &items,
+        );
+    }
+}
+
+/// Works on the first job of every workstation, reports the progress in steps and hands
+/// the outputs of finished jobs to the crafter. Outputs which don't fit into the inventory
+/// are dropped at the workstation.
+///
+/// # Parameters
+/// * `time` - Global time.
+/// * `recipes` - Recipe definitions for the outputs.
+/// * `items` - Item definitions for stack sizes.
+/// * `workstations` - Workstations and their queues.
+/// * `inventories` - Inventories of the crafters.
+/// * `progressed` - Notification writer for the progress.
+/// * `completed` - Notification writer for finished jobs.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn tick_workstations(
+    mut commands: Commands,
+    time: Res<Time>,
+    recipes: Recipes,
+    items: Items,
+    mut workstations: Query<(Entity, &mut Workstation, &GlobalTransform)>,
+    mut inventories: Query<&mut Inventory>,
+    mut progressed: MessageWriter<CraftingProgressed>,
+    mut completed: MessageWriter<CraftingCompleted>,
+) {
+    let delta = time.delta_secs();
+    for (entity, mut workstation, transform) in workstations.iter_mut() {
+        let Some((step, started)) = workstation.queue.front().map(|job| (job.progress_step(), job.elapsed <= 0.0)) else {
+            continue;
+        };
+
+        let finished = workstation.tick(delta);
+        let next_started = finished.is_some();
+        if let Some(job) = finished {
+            let outputs = recipes.get(&job.recipe).map(|recipe| recipe.outputs.clone()).unwrap_or_default();
+            give_or_drop(
+                &mut commands,
+                inventories.get_mut(job.crafter).ok().as_deref_mut(),
+                &outputs,
+                transform.translation(),
+                &items,
+            );
+            completed.write(CraftingCompleted {
+                crafter: job.crafter,
+                workstation: entity,
+                recipe: job.recipe,
+                outputs,
+            });
+        }
+
+        if let Some(job) = workstation.queue.front()
+            && (started || next_started || job.progress_step() != step) {
+            progressed.write(CraftingProgressed {
+                workstation: entity,
+                recipe: job.recipe.clone(),
+                progress: job.progress(),
+                queued: workstation.queue.len() - 1,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use bevy::time::TimeUpdateStrategy;
+    use game_models::definitions::DefinitionLibrary;
+    use game_models::items::crafting::RecipeDefinition;
+    use game_models::items::item::ItemDefinition;
+
+    fn plank_recipe() -> RecipeDefinition {
+        RecipeDefinition {
+            id: "plank".to_string(),
+            inputs: vec![ItemStack::new("wood", 2), ItemStack::new("wood", 3)],
+            outputs: vec![ItemStack::new("plank", 1)],
+            time: 1.0,
+            workstation: Some("workbench".to_string()),
+            tool: None,
+            experience: 0,
+        }
+    }
+
+    /// Headless app which runs the crafting systems. Every update advances the time by
+    /// exactly one fixed step of 20 ms.
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(20)))
+            .init_resource::<Assets<RecipeDefinition>>()
+            .init_resource::<DefinitionLibrary<RecipeDefinition>>()
+            .init_resource::<Assets<ItemDefinition>>()
+            .init_resource::<DefinitionLibrary<ItemDefinition>>()
+            .add_message::<CraftItem>()
+            .add_message::<CancelCrafting>()
+            .add_message::<CraftingQueued>()
+            .add_message::<CraftingProgressed>()
+            .add_message::<CraftingCompleted>()
+            .add_message::<CraftingFailed>()
+            .add_systems(Update, (handle_craft_requests, handle_cancel_crafting, tick_workstations).chain());
+
+        let handle = app.world_mut().resource_mut::<Assets<RecipeDefinition>>().add(plank_recipe());
+        app.world_mut().resource_mut::<DefinitionLibrary<RecipeDefinition>>().insert("plank", handle);
+        app
+    }
+
+    /// Spawns a workbench at the origin and a crafter carrying `wood` at `distance`.
+    fn spawn_crafting(app: &mut App, wood: u32, distance: f32) -> (Entity, Entity) {
+        let mut inventory = Inventory::new(10);
+        inventory.add("wood", wood, |_| 1);
+        let workbench = app.world_mut().spawn((Workstation::new("workbench", 2), GlobalTransform::IDENTITY)).id();
+        let crafter = app.world_mut().spawn((inventory, GlobalTransform::from_translation(Vec3::X * distance))).id();
+        (workbench, crafter)
+    }
+
+    fn craft(app: &mut App, workstation: Entity, crafter: Entity) {
+        app.world_mut().write_message(CraftItem { crafter, workstation, recipe: "plank".to_string() });
+    }
+
+    fn count<M: Message>(app: &App) -> usize {
+        app.world().resource::<Messages<M>>().iter_current_update_messages().count()
+    }
+
+    fn failures(app: &App) -> Vec<CraftingError> {
+        app.world().resource::<Messages<CraftingFailed>>()
+            .iter_current_update_messages()
+            .map(|failed| failed.error.clone())
+            .collect()
+    }
+
+    #[test]
+    fn duplicate_inputs_are_summed() {
+        let mut app = test_app();
+        let (workbench, crafter) = spawn_crafting(&mut app, 4, 1.0);
+
+        craft(&mut app, workbench, crafter);
+        app.update();
+
+        assert_eq!(failures(&app), vec![CraftingError::MissingInput { item: "wood".to_string(), required: 5, available: 4 }]);
+        assert_eq!(app.world().get::<Inventory>(crafter).unwrap().count("wood"), 4);
+        assert!(app.world().get::<Workstation>(workbench).unwrap().queue.is_empty());
+    }
+
+    #[test]
+    fn crafters_out_of_range_are_rejected() {
+        let mut app = test_app();
+        let (workbench, crafter) = spawn_crafting(&mut app, 5, CRAFTING_RANGE + 1.0);
+
+        craft(&mut app, workbench, crafter);
+        app.update();
+
+        assert_eq!(failures(&app), vec![CraftingError::TooFar]);
+        assert_eq!(app.world().get::<Inventory>(crafter).unwrap().count("wood"), 5);
+    }
+
+    #[test]
+    fn queued_jobs_consume_inputs_and_complete() {
+        let mut app = test_app();
+        let (workbench, crafter) = spawn_crafting(&mut app, 6, 1.0);
+
+        craft(&mut app, workbench, crafter);
+        app.update();
+        assert_eq!(count::<CraftingQueued>(&app), 1);
+        assert_eq!(app.world().get::<Inventory>(crafter).unwrap().count("wood"), 1);
+
+        let mut completed = 0;
+        let mut updates = 1;
+        while completed == 0 && updates < 100 {
+            app.update();
+            completed += count::<CraftingCompleted>(&app);
+            updates += 1;
+        }
+
+        assert_eq!(completed, 1);
+        assert!((49..=52).contains(&updates), "completed after {} updates", updates);
+        assert_eq!(app.world().get::<Inventory>(crafter).unwrap().count("plank"), 1);
+        assert!(app.world().get::<Workstation>(workbench).unwrap().queue.is_empty());
+    }
+
+    #[test]
+    fn progress_is_reported_in_steps() {
+        let mut app = test_app();
+        let (workbench, crafter) = spawn_crafting(&mut app, 5, 1.0);
+
+        craft(&mut app, workbench, crafter);
+        let mut progressed = 0;
+        for _ in 0..60 {
+            app.update();
+            progressed += count::<CraftingProgressed>(&app);
+        }
+
+        assert!((9..=11).contains(&progressed), "{} progress messages", progressed);
+    }
+}
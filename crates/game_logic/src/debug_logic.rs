@@ -4,6 +4,7 @@ use bevy::render::renderer::RenderAdapterInfo;
 use sysinfo::{CpuRefreshKind, MemoryRefreshKind, Pid, ProcessesToUpdate, RefreshKind, System};
 use game_models::config::GlobalConfig;
 use game_models::debug::{BuildInfo, DebugOverlayState, DebugSnapshot, SysStats};
+use game_models::pacing::PacingDirector;
 use game_models::states::AppState;
 use game_models::v_ram_detection::{detect_v_ram_best_effort, fmt_bytes};
 
@@ -27,7 +28,8 @@ impl Plugin for DebugLogicComponent {
                             snap_perf,
                             snap_build,
                             snap_v_ram,
-                            snap_cpu_brand
+                            snap_cpu_brand,
+                            snap_pacing
                         )
                             .chain()
                             .run_if(in_state(AppState::Preload)));
@@ -193,6 +195,24 @@ fn snap_cpu_brand(stats: Res<SysStats>, mut snap: ResMut<DebugSnapshot>, debug_s
     snap.cpu_brand = brand;
 }
 
+/// Copies the state of the [`PacingDirector`] into the snapshot for tuning.
+///
+/// # Parameters
+/// * `pacing` - Current pacing state.
+/// * `snap` - Mutable snapshot receiving the pacing fields.
+#[coverage(off)]
+fn snap_pacing(pacing: Res<PacingDirector>, mut snap: ResMut<DebugSnapshot>, debug_state: Res<DebugOverlayState>) {
+    if !debug_state.0 {
+        return;
+    }
+
+    snap.pacing_phase = format!("{:?}", pacing.phase);
+    snap.pacing_intensity = pacing.intensity;
+    snap.pacing_spawn_rate = pacing.spawn_rate;
+    snap.pacing_aggression = pacing.aggression;
+    snap.music_intensity = pacing.music_intensity;
+}
+
 /// Attempts to detect available V-RAM and writes a formatted label into the snapshot.
 /// Falls back to `"n/a"` when detection fails.
 ///
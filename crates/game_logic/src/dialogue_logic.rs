@@ -0,0 +1,333 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use game_models::config::GlobalConfig;
+use game_models::definitions::DefinitionLibrary;
+use game_models::dialogue::{ChooseDialogueOption, ContinueDialogue, DialogueContext, DialogueDefinition, DialogueEffect, DialogueEnded, DialogueItemsChanged, DialogueOwner, DialogueSession, DialogueSpeaker, DialogueStarted, DialogueState, DialogueView, EndDialogue, StartDialogue, StoryFlags, MAX_DIALOGUE_CHOICES};
+use game_models::entities::EntityBaseInformation;
+use game_models::health::Dead;
+use game_models::interaction::Interacted;
+use game_models::items::inventory::Inventory;
+use game_models::items::item::Items;
+use game_models::localization::Localization;
+use game_models::quests::StartQuest;
+use crate::interaction_logic::trigger_interaction;
+
+pub struct DialogueLogicComponent;
+
+impl Plugin for DialogueLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, dialogue_input.before(trigger_interaction));
+        app.add_systems(Update,
+                        (
+                            start_dialogue_on_interact,
+                            run_dialogue,
+                            update_dialogue_view
+                        )
+                            .chain()
+                            .after(trigger_interaction));
+    }
+}
+
+const CHOICE_KEYS: [KeyCode; MAX_DIALOGUE_CHOICES] = [
+    KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+];
+
+/// World state read and changed by dialogue conditions and effects.
+#[derive(SystemParam)]
+struct DialogueWorld<'w, 's> {
+    flags: ResMut<'w, StoryFlags>,
+    items: Items<'w>,
+    inventories: Query<'w, 's, &'static mut Inventory>,
+    quests: MessageWriter<'w, StartQuest>,
+    items_changed: MessageWriter<'w, DialogueItemsChanged>,
+}
+
+impl DialogueWorld<'_, '_> {
+
+    fn context(&self, listener: Entity) -> DialogueContext<'_> {
+        DialogueContext {
+            flags: &self.flags,
+            inventory: self.inventories.get(listener).ok(),
+        }
+    }
+
+    fn apply(&mut self, effects: &[DialogueEffect], listener: Entity) {
+        for effect in effects {
+            match effect {
+                DialogueEffect::SetFlag { flag } => self.flags.set(flag.clone()),
+                DialogueEffect::ClearFlag { flag } => self.flags.clear(flag),
+                DialogueEffect::GiveItem { item, count } => {
+                    let Ok(mut inventory) = self.inventories.get_mut(listener) else {
+                        continue;
+                    };
+                    let remaining = inventory.add(item, *count, |id| self.items.stack_size(id));
+                    if remaining > 0 {
+                        warn!("Dialogue could not give {} '{}' to {:?}, inventory is full", remaining, item, listener);
+                    }
+                    if remaining < *count {
+                        self.items_changed.write(DialogueItemsChanged { listener, item: item.clone() });
+                    }
+                }
+                DialogueEffect::TakeItem { item, count } => {
+                    let Ok(mut inventory) = self.inventories.get_mut(listener) else {
+                        continue;
+                    };
+                    match inventory.remove(item, *count) {
+                        Ok(_) => { self.items_changed.write(DialogueItemsChanged { listener, item: item.clone() }); }
+                        Err(e) => warn!("Dialogue could not take {} '{}' from {:?}: {}", count, item, listener, e),
+                    }
+                }
+                DialogueEffect::StartQuest { quest } => {
+                    self.quests.write(StartQuest { entity: listener, quest: quest.clone() });
+                }
+            }
+        }
+    }
+
+    /// Enters the first node of `candidates` whose conditions are met and applies its effects.
+    ///
+    /// # Returns
+    /// Id of the entered node and the indices of its visible choices (at most
+    /// [`MAX_DIALOGUE_CHOICES`]), `None` if no node could be entered and the dialogue ends.
+    fn enter(&mut self, definition: &DialogueDefinition, candidates: &[String], listener: Entity) -> Option<(String, Vec<usize>)> {
+        let node = definition.resolve(candidates, &self.context(listener))?;
+        self.apply(&node.effects, listener);
+
+        let context = self.context(listener);
+        let choices = node.choices
+            .iter()
+            .enumerate()
+            .filter(|(_, choice)| context.all_met(&choice.conditions))
+            .map(|(index, _)| index)
+            .take(MAX_DIALOGUE_CHOICES)
+            .collect();
+        Some((node.id.clone(), choices))
+    }
+}
+
+/// Starts the dialogue of a [`DialogueOwner`] which was interacted with.
+///
+/// # Parameters
+/// * `interactions` - Interactions of any kind.
+/// * `owners` - Entities owning a dialogue.
+/// * `starts` - Writer for dialogue starts.
+#[coverage(off)]
+fn start_dialogue_on_interact(
+    mut interactions: MessageReader<Interacted>,
+    owners: Query<&DialogueOwner>,
+    mut starts: MessageWriter<StartDialogue>,
+) {
+    for interaction in interactions.read() {
+        if let Ok(owner) = owners.get(interaction.target) {
+            starts.write(StartDialogue {
+                dialogue: owner.0.clone(),
+                owner: interaction.target,
+                listener: interaction.interactor,
+            });
+        }
+    }
+}
+
+/// Player input of a running dialogue: the number keys pick a choice, the interact key
+/// continues lines without choices and escape leaves the dialogue. Consumed keys are
+/// cleared, so they don't trigger gameplay input in the same frame.
+///
+/// # Parameters
+/// * `keyboard` - Keyboard input.
+/// * `global_config` - Key bindings.
+/// * `state` - Running dialogue.
+/// * `continues` - Writer for continue requests.
+/// * `choices` - Writer for choice requests.
+/// * `ends` - Writer for end requests.
+#[coverage(off)]
+fn dialogue_input(
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    global_config: Res<GlobalConfig>,
+    state: Res<DialogueState>,
+    mut continues: MessageWriter<ContinueDialogue>,
+    mut choices: MessageWriter<ChooseDialogueOption>,
+    mut ends: MessageWriter<EndDialogue>,
+) {
+    let Some(session) = state.session.as_ref() else {
+        return;
+    };
+
+    if keyboard.clear_just_pressed(KeyCode::Escape) {
+        ends.write(EndDialogue);
+        return;
+    }
+
+    if session.choices.is_empty() {
+        if keyboard.clear_just_pressed(global_config.input_config.get_interact_key()) {
+            continues.write(ContinueDialogue);
+        }
+        return;
+    }
+
+    for (index, key) in CHOICE_KEYS.iter().enumerate().take(session.choices.len()) {
+        if keyboard.clear_just_pressed(*key) {
+            choices.write(ChooseDialogueOption { index });
+            return;
+        }
+    }
+}
+
+/// Runs the requests of the dialogue: starts a dialogue at its first entry node with met
+/// conditions, follows continues and choices and applies their effects. A dialogue ends
+/// when no next node can be entered, when it is ended explicitly or when the owner or
+/// the listener despawned or died.
+///
+/// # Parameters
+/// * `starts` - Start requests, ignored while another dialogue is running.
+/// * `continues` - Continue requests.
+/// * `choices` - Choice requests.
+/// * `ends` - End requests.
+/// * `started` - Notification writer for started dialogues.
+/// * `ended` - Notification writer for ended dialogues.
+/// * `library` - Dialogue definitions by id.
+/// * `definitions` - Dialogue definition assets.
+/// * `world` - Flags, inventories and quests changed by effects.
+/// * `living` - Living entities, the owner and listener have to be among them.
+/// * `state` - Running dialogue.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn run_dialogue(
+    mut starts: MessageReader<StartDialogue>,
+    mut continues: MessageReader<ContinueDialogue>,
+    mut choices: MessageReader<ChooseDialogueOption>,
+    mut ends: MessageReader<EndDialogue>,
+    mut started: MessageWriter<DialogueStarted>,
+    mut ended: MessageWriter<DialogueEnded>,
+    library: Res<DefinitionLibrary<DialogueDefinition>>,
+    definitions: Res<Assets<DialogueDefinition>>,
+    mut world: DialogueWorld,
+    living: Query<(), Without<Dead>>,
+    mut state: ResMut<DialogueState>,
+) {
+    for request in starts.read() {
+        if state.is_active() {
+            debug!("Dialogue '{}' ignored, another dialogue is running", request.dialogue);
+            continue;
+        }
+        let Some(definition) = library.get(&request.dialogue, &definitions) else {
+            warn!("Dialogue definition '{}' is not loaded", request.dialogue);
+            continue;
+        };
+        let Some((node, node_choices)) = world.enter(definition, &definition.start, request.listener) else {
+            debug!("Dialogue '{}' has no entry node with met conditions", request.dialogue);
+            continue;
+        };
+
+        state.session = Some(DialogueSession {
+            dialogue: request.dialogue.clone(),
+            node,
+            owner: request.owner,
+            listener: request.listener,
+            choices: node_choices,
+            view: DialogueView::default(),
+        });
+        started.write(DialogueStarted {
+            dialogue: request.dialogue.clone(),
+            owner: request.owner,
+            listener: request.listener,
+        });
+    }
+
+    let advance = continues.read().count() > 0;
+    let choice = choices.read().last().map(|request| request.index);
+    let end = ends.read().count() > 0;
+
+    let Some(session) = state.session.as_mut() else {
+        return;
+    };
+    let left = !living.contains(session.owner) || !living.contains(session.listener);
+    let mut finished = end || left;
+    if !finished {
+        let Some(definition) = library.get(&session.dialogue, &definitions) else {
+            return;
+        };
+        let Some(node) = definition.node(&session.node) else {
+            return;
+        };
+
+        let next = match choice.and_then(|index| session.choices.get(index)).and_then(|index| node.choices.get(*index)) {
+            Some(picked) => {
+                world.apply(&picked.effects, session.listener);
+                Some(picked.next.as_slice())
+            }
+            None if session.choices.is_empty() && advance => Some(node.next.as_slice()),
+            None => None,
+        };
+
+        if let Some(next) = next {
+            match world.enter(definition, next, session.listener) {
+                Some((node, node_choices)) => {
+                    session.node = node;
+                    session.choices = node_choices;
+                }
+                None => finished = true,
+            }
+        }
+    }
+
+    if !finished {
+        return;
+    }
+    if let Some(session) = state.session.take() {
+        ended.write(DialogueEnded {
+            dialogue: session.dialogue,
+            owner: session.owner,
+            listener: session.listener,
+        });
+    }
+}
+
+/// Resolves the localized speaker name, line and choices of the current node for the
+/// dialogue panel. Speaker names use [`EntityBaseInformation::localized_name`].
+///
+/// # Parameters
+/// * `library` - Dialogue definitions by id.
+/// * `definitions` - Dialogue definition assets.
+/// * `localization` - Texts of the active language.
+/// * `names` - Names of the owner and the listener.
+/// * `state` - Running dialogue.
+#[coverage(off)]
+fn update_dialogue_view(
+    library: Res<DefinitionLibrary<DialogueDefinition>>,
+    definitions: Res<Assets<DialogueDefinition>>,
+    localization: Localization,
+    names: Query<&EntityBaseInformation>,
+    mut state: ResMut<DialogueState>,
+) {
+    let Some(session) = state.bypass_change_detection().session.as_mut() else {
+        return;
+    };
+    let Some(node) = library
+        .get(&session.dialogue, &definitions)
+        .and_then(|definition| definition.node(&session.node)) else {
+        return;
+    };
+
+    let entity_name = |entity: Entity| names.get(entity).map_or_else(
+        |_| String::new(),
+        |info| if info.localized_name.is_empty() { info.display_name.clone() } else { localization.text(&info.localized_name) },
+    );
+    let view = DialogueView {
+        speaker: match &node.speaker {
+            DialogueSpeaker::Owner => entity_name(session.owner),
+            DialogueSpeaker::Listener => entity_name(session.listener),
+            DialogueSpeaker::Named(key) => localization.text(key),
+        },
+        text: localization.text(&node.text),
+        choices: session.choices
+            .iter()
+            .filter_map(|index| node.choices.get(*index))
+            .map(|choice| localization.text(&choice.text))
+            .collect(),
+    };
+
+    if session.view != view {
+        session.view = view;
+        state.set_changed();
+    }
+}
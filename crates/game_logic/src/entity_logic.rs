@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+use game_models::entities::definition::{DefinitionSpawner, EntityDefinition, EntityDefinitionRef};
+
+pub struct EntityLogicComponent;
+
+impl Plugin for EntityLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, hot_reload_entity_definitions);
+    }
+}
+
+/// Re-applies modified [`EntityDefinition`]s to every entity which was spawned from them,
+/// so tweaking a definition file on disk updates the running game. The shared model of
+/// the definition is rebuilt first.
+///
+/// # Parameters
+/// * `events` - Asset events of entity definitions.
+/// * `spawned` - All entities created through `spawn_from_definition`.
+/// * `spawner` - Spawner used to re-apply the definition.
+#[coverage(off)]
+fn hot_reload_entity_definitions(
+    mut events: MessageReader<AssetEvent<EntityDefinition>>,
+    spawned: Query<(Entity, &EntityDefinitionRef)>,
+    mut spawner: DefinitionSpawner,
+) {
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        spawner.invalidate_model(*id);
+
+        for (entity, definition_ref) in spawned.iter() {
+            if definition_ref.handle.id() == *id {
+                debug!("Hot reload definition '{}' for {:?}", definition_ref.id, entity);
+                spawner.apply_definition(entity, &definition_ref.handle);
+            }
+        }
+    }
+}
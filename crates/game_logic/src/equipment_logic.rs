@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+use game_models::attributes::AttributeModifiers;
+use game_models::items::equipment::{EquipItem, Equipment, EquipmentData, EquipmentSlot, EquipmentVisual, ItemEquipped, ItemUnequipped, UnequipItem};
+use game_models::items::inventory::Inventory;
+use game_models::items::item::Items;
+use game_models::states::is_state_simulating;
+
+pub struct EquipmentLogicComponent;
+
+impl Plugin for EquipmentLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            handle_equip,
+                            handle_unequip,
+                            attach_equipment_visuals
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Equips items from the inventory. Items which occupy the target slot (and the offhand for
+/// two-handed weapons) are moved back into the inventory. The request is rejected without
+/// changes if the replaced items don't fit into the inventory.
+///
+/// # Parameters
+/// * `requests` - Equip requests.
+/// * `equipped` - Notification writer for equipped items.
+/// * `unequipped` - Notification writer for replaced items.
+/// * `items` - Item definitions for equipment data and stack sizes.
+/// * `wearers` - Inventory, equipment and modifiers of the equipping entities.
+#[coverage(off)]
+fn handle_equip(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut requests: MessageReader<EquipItem>,
+    mut equipped: MessageWriter<ItemEquipped>,
+    mut unequipped: MessageWriter<ItemUnequipped>,
+    items: Items,
+    mut wearers: Query<(&mut Inventory, &mut Equipment, &mut AttributeModifiers)>,
+) {
+    for request in requests.read() {
+        let Ok((mut inventory, mut equipment, mut modifiers)) = wearers.get_mut(request.entity) else {
+            continue;
+        };
+        let Some(stack) = inventory.get(request.inventory_slot) else {
+            continue;
+        };
+        let item = stack.item.clone();
+        let Some(data) = items.get(&item).and_then(|i| i.equipment.clone()) else {
+            debug!("Item '{}' can't be equipped", item);
+            continue;
+        };
+
+        let blocked = blocked_slots(&data, &equipment, &items);
+
+        // Work on a copy, so a full inventory leaves everything untouched.
+        let mut next_inventory = inventory.clone();
+        if next_inventory.take_from_slot(request.inventory_slot, 1).is_err() {
+            continue;
+        }
+        let fits = blocked.iter()
+            .filter_map(|slot| equipment.get(*slot))
+            .all(|old| next_inventory.add(old, 1, |id| items.stack_size(id)) == 0);
+        if !fits {
+            debug!("No inventory space to swap equipment of {:?}", request.entity);
+            continue;
+        }
+        *inventory = next_inventory;
+
+        for slot in blocked {
+            if let Some(old) = remove_from_slot(&mut commands, &mut equipment, &mut modifiers, slot) {
+                unequipped.write(ItemUnequipped { entity: request.entity, slot, item: old });
+            }
+        }
+
+        for modifier in data.modifiers.iter() {
+            let mut modifier = modifier.clone();
+            modifier.source = data.slot.modifier_source();
+            modifiers.add(modifier);
+        }
+
+        if let Some(model) = data.model.as_ref() {
+            let visual = commands.spawn((
+                Name::new(format!("Equipment: {}", item)),
+                SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(model.clone()))),
+                Transform::default(),
+                EquipmentVisual {
+                    wearer: request.entity,
+                    slot: data.slot,
+                    bone: data.attach_to.clone().unwrap_or_else(|| data.slot.default_bone().to_string()),
+                    attached: false,
+                },
+                ChildOf(request.entity),
+            )).id();
+            equipment.visuals.insert(data.slot, visual);
+        }
+
+        equipment.items.insert(data.slot, item.clone());
+        equipped.write(ItemEquipped { entity: request.entity, slot: data.slot, item });
+    }
+}
+
+/// Moves equipped items back into the inventory if there is space left.
+///
+/// # Parameters
+/// * `requests` - Unequip requests.
+/// * `unequipped` - Notification writer for unequipped items.
+/// * `items` - Item definitions for stack sizes.
+/// * `wearers` - Inventory, equipment and modifiers of the entities.
+#[coverage(off)]
+fn handle_unequip(
+    mut commands: Commands,
+    mut requests: MessageReader<UnequipItem>,
+    mut unequipped: MessageWriter<ItemUnequipped>,
+    items: Items,
+    mut wearers: Query<(&mut Inventory, &mut Equipment, &mut AttributeModifiers)>,
+) {
+    for request in requests.read() {
+        let Ok((mut inventory, mut equipment, mut modifiers)) = wearers.get_mut(request.entity) else {
+            continue;
+        };
+        let Some(item) = equipment.get(request.slot).map(str::to_string) else {
+            continue;
+        };
+        if inventory.capacity_for(&item, |id| items.stack_size(id)) == 0 {
+            debug!("No inventory space to unequip '{}'", item);
+            continue;
+        }
+
+        remove_from_slot(&mut commands, &mut equipment, &mut modifiers, request.slot);
+        inventory.add(&item, 1, |id| items.stack_size(id));
+        unequipped.write(ItemUnequipped { entity: request.entity, slot: request.slot, item });
+    }
+}
+
+/// Re-parents equipment models to their bone once the wearer's scene contains a
+/// child with the matching [`Name`]. Models stay on the wearer's root until then.
+///
+/// # Parameters
+/// * `visuals` - Equipment models which are not attached yet.
+/// * `children` - Hierarchy used to search the wearer's bones.
+/// * `names` - Names of all entities.
+#[coverage(off)]
+fn attach_equipment_visuals(
+    mut commands: Commands,
+    mut visuals: Query<(Entity, &mut EquipmentVisual)>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+) {
+    for (entity, mut visual) in visuals.iter_mut().filter(|(_, v)| !v.attached) {
+        let bone = children
+            .iter_descendants(visual.wearer)
+            .find(|descendant| names.get(*descendant).is_ok_and(|name| name.as_str() == visual.bone));
+
+        if let Some(bone) = bone {
+            commands.entity(entity).insert(ChildOf(bone));
+            visual.attached = true;
+        }
+    }
+}
+
+/// Returns all slots which have to be free to equip an item with `data`.
+fn blocked_slots(data: &EquipmentData, equipment: &Equipment, items: &Items) -> Vec<EquipmentSlot> {
+    let mut slots = vec![data.slot];
+    if data.two_handed {
+        slots.push(EquipmentSlot::Offhand);
+    }
+    if data.slot == EquipmentSlot::Offhand {
+        let weapon_two_handed = equipment.get(EquipmentSlot::Weapon)
+            .and_then(|weapon| items.get(weapon))
+            .and_then(|weapon| weapon.equipment.as_ref())
+            .is_some_and(|weapon| weapon.two_handed);
+        if weapon_two_handed {
+            slots.push(EquipmentSlot::Weapon);
+        }
+    }
+    slots
+}
+
+/// Clears `slot`, removes its modifiers and despawns the model.
+///
+/// # Returns
+/// The id of the removed item.
+fn remove_from_slot(
+    commands: &mut Commands,
+    equipment: &mut Equipment,
+    modifiers: &mut AttributeModifiers,
+    slot: EquipmentSlot,
+) -> Option<String> {
+    let item = equipment.items.remove(&slot)?;
+    modifiers.remove_source(&slot.modifier_source());
+    if let Some(visual) = equipment.visuals.remove(&slot) {
+        commands.entity(visual).despawn();
+    }
+    Some(item)
+}
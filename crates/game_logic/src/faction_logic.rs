@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+use game_models::definitions::DefinitionLibrary;
+use game_models::entities::character::Character;
+use game_models::entities::player::Player;
+use game_models::factions::{kill_reputation, ChangeReputation, Faction, FactionDefinition, FactionSettings, Factions, ReputationChanged, ReputationSource, ThreatTable};
+use game_models::health::{DamageApplied, Dead, DeathEvent, Health};
+use game_models::states::is_state_simulating;
+
+pub struct FactionLogicComponent;
+
+impl Plugin for FactionLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            threat_from_damage,
+                            decay_threat,
+                            reputation_from_damage,
+                            reputation_from_kills,
+                            apply_reputation_changes
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Adds the health lost by AI to the threat of the attacker, so blocked, resisted and
+/// filtered damage raises no threat. Members of the same faction nearby share a part of
+/// the threat and come to help.
+///
+/// # Parameters
+/// * `damage` - Damage applied since the last frame.
+/// * `settings` - Threat per damage and assist range.
+/// * `tables` - Threat tables, positions and factions of the AI.
+#[coverage(off)]
+fn threat_from_damage(
+    mut damage: MessageReader<DamageApplied>,
+    settings: Res<FactionSettings>,
+    mut tables: Query<(Entity, &mut ThreatTable, &GlobalTransform, Option<&Faction>), Without<Dead>>,
+) {
+    for event in damage.read() {
+        let Some(source) = event.source.filter(|source| *source != event.target) else {
+            continue;
+        };
+        let Ok((_, _, transform, faction)) = tables.get(event.target) else {
+            continue;
+        };
+        let position = transform.translation();
+        let faction = faction.cloned();
+        let threat = event.amount * settings.damage_threat;
+
+        for (entity, mut table, transform, member) in tables.iter_mut() {
+            if entity == source {
+                continue;
+            }
+            if entity == event.target {
+                table.add(source, threat);
+            } else if faction.is_some() && member == faction.as_ref()
+                && transform.translation().distance(position) <= settings.assist_radius {
+                table.add(source, threat * settings.assist_threat);
+            }
+        }
+    }
+}
+
+/// Lowers the threat of all AI over time and forgets dead and despawned attackers.
+///
+/// # Parameters
+/// * `time` - Global time.
+/// * `settings` - Threat decay.
+/// * `tables` - Threat tables of the AI.
+/// * `living` - Living entities.
+#[coverage(off)]
+fn decay_threat(
+    time: Res<Time>,
+    settings: Res<FactionSettings>,
+    mut tables: Query<&mut ThreatTable>,
+    living: Query<(), (With<Health>, Without<Dead>)>,
+) {
+    let amount = settings.threat_decay * time.delta_secs();
+    for mut table in tables.iter_mut().filter(|table| !table.is_empty()) {
+        table.decay(amount, |entity| living.contains(entity));
+    }
+}
+
+/// Lowers the standing with a faction when the player damages one of its members.
+/// Damage filtered by friendly fire or invulnerability doesn't count.
+///
+/// # Parameters
+/// * `damage` - Damage applied since the last frame.
+/// * `players` - Player entities.
+/// * `factions` - Faction definitions and memberships.
+/// * `reputation` - Writer for the reputation changes.
+#[coverage(off)]
+fn reputation_from_damage(
+    mut damage: MessageReader<DamageApplied>,
+    players: Query<(), With<Player>>,
+    factions: Factions,
+    mut reputation: MessageWriter<ChangeReputation>,
+) {
+    for event in damage.read() {
+        if !event.source.is_some_and(|source| players.contains(source)) {
+            continue;
+        }
+        let Some(faction) = factions.faction_of(event.target).and_then(|faction| factions.get(faction)) else {
+            continue;
+        };
+        if let Some(rules) = &faction.reputation
+            && rules.attack != 0 {
+            reputation.write(ChangeReputation {
+                faction: faction.id.clone(),
+                amount: rules.attack,
+                source: ReputationSource::Attack(faction.id.clone()),
+            });
+        }
+    }
+}
+
+/// Changes the standing with the faction of an entity killed by the player and with
+/// all factions hostile to it.
+///
+/// # Parameters
+/// * `deaths` - Died entities.
+/// * `players` - Player entities.
+/// * `factions` - Faction definitions and memberships.
+/// * `reputation` - Writer for the reputation changes.
+#[coverage(off)]
+fn reputation_from_kills(
+    mut deaths: MessageReader<DeathEvent>,
+    players: Query<(), With<Player>>,
+    factions: Factions,
+    mut reputation: MessageWriter<ChangeReputation>,
+) {
+    for death in deaths.read() {
+        if !death.killer.is_some_and(|killer| players.contains(killer)) {
+            continue;
+        }
+        let Some(victim) = factions.faction_of(death.entity) else {
+            continue;
+        };
+        for (faction, amount) in kill_reputation(victim, factions.iter()) {
+            reputation.write(ChangeReputation {
+                faction: faction.to_string(),
+                amount,
+                source: ReputationSource::Kill(victim.to_string()),
+            });
+        }
+    }
+}
+
+/// Applies reputation changes to the standings of the [`Character`]. Factions without
+/// reputation rules ignore them.
+///
+/// # Parameters
+/// * `requests` - Reputation changes.
+/// * `library` - Loaded faction definitions.
+/// * `definitions` - Faction definition assets.
+/// * `characters` - The character.
+/// * `changed` - Notification writer for the new standings.
+#[coverage(off)]
+fn apply_reputation_changes(
+    mut requests: MessageReader<ChangeReputation>,
+    library: Res<DefinitionLibrary<FactionDefinition>>,
+    definitions: Res<Assets<FactionDefinition>>,
+    mut characters: Query<&mut Character>,
+    mut changed: MessageWriter<ReputationChanged>,
+) {
+    let Ok(mut character) = characters.single_mut() else {
+        return;
+    };
+
+    for request in requests.read() {
+        let Some(rules) = library.get(&request.faction, &definitions).and_then(|faction| faction.reputation.as_ref()) else {
+            warn!("Faction '{}' has no reputation", request.faction);
+            continue;
+        };
+        let from = rules.relation(character.reputation.standing(&request.faction, rules));
+        let standing = character.reputation.change(&request.faction, request.amount, rules);
+        let to = rules.relation(standing);
+        debug!("Reputation with '{}' changed by {} from {:?}", request.faction, request.amount, request.source);
+        if from != to {
+            info!("Faction '{}' is now {:?}", request.faction, to);
+        }
+        changed.write(ReputationChanged { faction: request.faction.clone(), standing, from, to });
+    }
+}
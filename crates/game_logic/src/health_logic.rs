@@ -0,0 +1,366 @@
+use std::collections::HashSet;
+use bevy::prelude::*;
+use game_models::attributes::Attributes;
+use game_models::entities::player::Player;
+use game_models::factions::Factions;
+use game_models::health::{armor_mitigation, DamageApplied, DamageEvent, DamageType, Dead, DeathBehaviour, DeathEvent, Health, InvulnerabilityFrames, Invulnerable, Resistances};
+use game_models::states::{is_state_simulating, AppState, InGameStates};
+
+pub struct HealthLogicComponent;
+
+impl Plugin for HealthLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            tick_invulnerability,
+                            apply_damage,
+                            handle_death,
+                            cleanup_dead
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Counts down [`Invulnerable`] timers and removes the component once they finished.
+///
+/// # Parameters
+/// * `time` - Global time used to tick the timers.
+/// * `invulnerable` - All currently invulnerable entities.
+#[coverage(off)]
+fn tick_invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut invulnerable: Query<(Entity, &mut Invulnerable)>,
+) {
+    for (entity, mut invulnerable) in invulnerable.iter_mut() {
+        if invulnerable.0.tick(time.delta()).is_finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+/// Applies all pending [`DamageEvent`]s. Damage is reduced by [`Resistances`] and the
+/// armor attribute (physical damage only), ignored for dead or invulnerable targets
+/// and grants [`InvulnerabilityFrames`] after a hit. The frames protect against the
+/// remaining damage of the same frame already, the [`Invulnerable`] component is only
+/// inserted at the end of the frame. Friends of the source take no damage while
+/// friendly fire is disabled, whatever produced the damage.
+/// Emits a [`DamageApplied`] with the health actually lost and a [`DeathEvent`] when a
+/// target's health reaches zero.
+///
+/// # Parameters
+/// * `damage_events` - Incoming damage requests.
+/// * `applied_events` - Writer for the damage which got through.
+/// * `death_events` - Writer for deaths caused this frame.
+/// * `factions` - Friendly fire rules.
+/// * `targets` - Damageable entities.
+#[coverage(off)]
+fn apply_damage(
+    mut commands: Commands,
+    mut damage_events: MessageReader<DamageEvent>,
+    mut applied_events: MessageWriter<DamageApplied>,
+    mut death_events: MessageWriter<DeathEvent>,
+    factions: Factions,
+    mut targets: Query<(
+        &mut Health,
+        Option<&Resistances>,
+        Option<&Attributes>,
+        Option<&InvulnerabilityFrames>,
+        Has<Invulnerable>,
+        Has<Dead>,
+    )>,
+) {
+    let mut protected = HashSet::new();
+    for event in damage_events.read() {
+        let Ok((mut health, resistances, attributes, frames, invulnerable, dead)) = targets.get_mut(event.target) else {
+            continue;
+        };
+
+        // Already dead this frame or protected by i-frames.
+        if dead || invulnerable || protected.contains(&event.target) || health.is_depleted() {
+            continue;
+        }
+        if let Some(source) = event.source
+            && !factions.can_damage(source, event.target) {
+            continue;
+        }
+
+        let mut amount = resistances
+            .map(|r| r.mitigate(event.damage_type, event.amount))
+            .unwrap_or(event.amount);
+        if event.damage_type == DamageType::Physical {
+            if let Some(attributes) = attributes {
+                amount = armor_mitigation(attributes.armor.value, amount);
+            }
+        }
+        let taken = health.take_damage(amount);
+        if taken > 0.0 {
+            applied_events.write(DamageApplied {
+                target: event.target,
+                source: event.source,
+                damage_type: event.damage_type,
+                amount: taken,
+            });
+        }
+
+        if health.is_depleted() {
+            death_events.write(DeathEvent {
+                entity: event.target,
+                killer: event.source,
+                damage_type: event.damage_type,
+            });
+            continue;
+        }
+
+        if let Some(frames) = frames {
+            protected.insert(event.target);
+            commands.entity(event.target).insert(Invulnerable(Timer::from_seconds(frames.duration, TimerMode::Once)));
+        }
+    }
+}
+
+/// Marks dying entities as [`Dead`]. The death of the [`Player`] switches to
+/// `InGameStates::GameOver`; other entities follow their [`DeathBehaviour`].
+///
+/// # Parameters
+/// * `death_events` - Deaths emitted by [`apply_damage`].
+/// * `players` - Used to detect the death of the player.
+/// * `behaviours` - Optional death behaviour of the dying entity.
+/// * `next_state` - Receives the game over transition.
+#[coverage(off)]
+fn handle_death(
+    mut commands: Commands,
+    mut death_events: MessageReader<DeathEvent>,
+    players: Query<(), With<Player>>,
+    behaviours: Query<Option<&DeathBehaviour>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in death_events.read() {
+        let Ok(behaviour) = behaviours.get(event.entity) else {
+            continue;
+        };
+
+        if players.contains(event.entity) {
+            info!("Player died, game over");
+            commands.entity(event.entity).insert(Dead { cleanup: None });
+            next_state.set(AppState::InGame(InGameStates::GameOver));
+            continue;
+        }
+
+        let cleanup = match behaviour.cloned().unwrap_or_default() {
+            DeathBehaviour::Despawn(delay) | DeathBehaviour::Ragdoll(delay) => {
+                Some(Timer::from_seconds(delay, TimerMode::Once))
+            }
+            DeathBehaviour::Keep => None,
+        };
+        commands.entity(event.entity).insert(Dead { cleanup });
+    }
+}
+
+/// Despawns dead entities once their cleanup timer finished.
+///
+/// # Parameters
+/// * `time` - Global time used to tick the cleanup timers.
+/// * `dead` - All dead entities.
+#[coverage(off)]
+fn cleanup_dead(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut dead: Query<(Entity, &mut Dead)>,
+) {
+    for (entity, mut dead) in dead.iter_mut() {
+        let Some(timer) = dead.cleanup.as_mut() else {
+            continue;
+        };
+        if timer.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use bevy::state::app::StatesPlugin;
+    use bevy::time::TimeUpdateStrategy;
+    use game_models::definitions::DefinitionLibrary;
+    use game_models::factions::{Faction, FactionDefinition, FactionSettings};
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .init_state::<AppState>()
+            .add_message::<DamageEvent>()
+            .add_message::<DamageApplied>()
+            .add_message::<DeathEvent>()
+            .init_resource::<Assets<FactionDefinition>>()
+            .init_resource::<DefinitionLibrary<FactionDefinition>>()
+            .init_resource::<FactionSettings>()
+            .add_systems(Update, (tick_invulnerability, apply_damage, handle_death).chain());
+        app
+    }
+
+    fn damage(app: &mut App, target: Entity, amount: f32) {
+        app.world_mut().write_message(DamageEvent {
+            target,
+            source: None,
+            damage_type: DamageType::Physical,
+            amount,
+        });
+    }
+
+    fn applied(app: &App) -> Vec<f32> {
+        app.world().resource::<Messages<DamageApplied>>()
+            .iter_current_update_messages()
+            .map(|applied| applied.amount)
+            .collect()
+    }
+
+    fn deaths(app: &App) -> Vec<Entity> {
+        app.world().resource::<Messages<DeathEvent>>()
+            .iter_current_update_messages()
+            .map(|death| death.entity)
+            .collect()
+    }
+
+    #[test]
+    fn resistances_reduce_damage() {
+        let mut app = test_app();
+        let mut resistances = Resistances::default();
+        resistances.values.insert(DamageType::Physical, 0.5);
+        let target = app.world_mut().spawn((Health::new(100.0), resistances)).id();
+
+        damage(&mut app, target, 40.0);
+        app.update();
+
+        assert_eq!(app.world().get::<Health>(target).unwrap().current, 80.0);
+    }
+
+    #[test]
+    fn invulnerability_frames_block_damage_of_the_same_frame() {
+        let mut app = test_app();
+        let target = app.world_mut().spawn((Health::new(100.0), InvulnerabilityFrames { duration: 0.5 })).id();
+
+        damage(&mut app, target, 10.0);
+        damage(&mut app, target, 10.0);
+        app.update();
+
+        assert_eq!(app.world().get::<Health>(target).unwrap().current, 90.0);
+        assert!(app.world().get::<Invulnerable>(target).is_some());
+    }
+
+    #[test]
+    fn invulnerability_ends_after_its_duration() {
+        let mut app = test_app();
+        let target = app.world_mut().spawn((Health::new(100.0), InvulnerabilityFrames { duration: 0.25 })).id();
+
+        damage(&mut app, target, 10.0);
+        app.update();
+        for _ in 0..4 {
+            app.update();
+        }
+        damage(&mut app, target, 10.0);
+        app.update();
+
+        assert_eq!(app.world().get::<Health>(target).unwrap().current, 80.0);
+    }
+
+    #[test]
+    fn lethal_damage_emits_one_death() {
+        let mut app = test_app();
+        let target = app.world_mut().spawn(Health::new(20.0)).id();
+
+        damage(&mut app, target, 15.0);
+        damage(&mut app, target, 15.0);
+        damage(&mut app, target, 15.0);
+        app.update();
+
+        assert!(app.world().get::<Health>(target).unwrap().is_depleted());
+        assert_eq!(deaths(&app), vec![target]);
+    }
+
+    #[test]
+    fn friends_only_damage_each_other_with_friendly_fire() {
+        let mut app = test_app();
+        let attacker = app.world_mut().spawn(Faction::player()).id();
+        let friend = app.world_mut().spawn((Health::new(100.0), Faction::player())).id();
+
+        app.world_mut().write_message(DamageEvent {
+            target: friend,
+            source: Some(attacker),
+            damage_type: DamageType::Physical,
+            amount: 10.0,
+        });
+        app.update();
+        assert_eq!(app.world().get::<Health>(friend).unwrap().current, 100.0);
+
+        app.world_mut().resource_mut::<FactionSettings>().friendly_fire = true;
+        app.world_mut().write_message(DamageEvent {
+            target: friend,
+            source: Some(attacker),
+            damage_type: DamageType::Physical,
+            amount: 10.0,
+        });
+        app.update();
+        assert_eq!(app.world().get::<Health>(friend).unwrap().current, 90.0);
+    }
+
+    #[test]
+    fn applied_damage_reports_the_health_lost() {
+        let mut app = test_app();
+        let mut resistances = Resistances::default();
+        resistances.values.insert(DamageType::Physical, 0.5);
+        let target = app.world_mut().spawn((Health::new(30.0), resistances, InvulnerabilityFrames { duration: 0.5 })).id();
+
+        damage(&mut app, target, 40.0);
+        damage(&mut app, target, 40.0);
+        app.update();
+
+        assert_eq!(applied(&app), vec![20.0]);
+    }
+
+    #[test]
+    fn lethal_damage_reports_only_the_remaining_health() {
+        let mut app = test_app();
+        let target = app.world_mut().spawn(Health::new(20.0)).id();
+
+        damage(&mut app, target, 50.0);
+        app.update();
+
+        assert_eq!(applied(&app), vec![20.0]);
+    }
+
+    #[test]
+    fn death_of_the_player_is_game_over() {
+        let mut app = test_app();
+        let player = app.world_mut().spawn((Health::new(10.0), Player)).id();
+
+        damage(&mut app, player, 10.0);
+        app.update();
+
+        assert!(matches!(
+            app.world().resource::<NextState<AppState>>(),
+            NextState::Pending(AppState::InGame(InGameStates::GameOver))
+        ));
+        assert!(app.world().get::<Dead>(player).unwrap().cleanup.is_none());
+    }
+
+    #[test]
+    fn dead_npcs_follow_their_death_behaviour() {
+        let mut app = test_app();
+        let despawned = app.world_mut().spawn((Health::new(10.0), DeathBehaviour::Despawn(2.0))).id();
+        let kept = app.world_mut().spawn((Health::new(10.0), DeathBehaviour::Keep)).id();
+
+        damage(&mut app, despawned, 10.0);
+        damage(&mut app, kept, 10.0);
+        app.update();
+
+        let cleanup = app.world().get::<Dead>(despawned).unwrap().cleanup.clone().unwrap();
+        assert_eq!(cleanup.duration(), Duration::from_secs(2));
+        assert!(app.world().get::<Dead>(kept).unwrap().cleanup.is_none());
+        assert!(matches!(app.world().resource::<NextState<AppState>>(), NextState::Unchanged));
+    }
+}
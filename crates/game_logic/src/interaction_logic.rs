@@ -0,0 +1,244 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{ActiveCollisionTypes, ActiveEvents, Collider, CollisionEvent, Sensor};
+use game_models::config::GlobalConfig;
+use game_models::dialogue::{is_dialogue_inactive, DialogueState};
+use game_models::entities::player::Player;
+use game_models::interaction::{Interactable, Interacted, InteractionPrompt, InteractionSensor, InteractionSettings, Interactor};
+use game_models::localization::Localization;
+use game_models::states::{is_state_simulating, AppState};
+
+pub struct InteractionLogicComponent;
+
+impl Plugin for InteractionLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Preload), spawn_interaction_prompt);
+        app.add_systems(Update,
+                        (
+                            attach_interaction_sensors,
+                            track_interaction_sensors,
+                            select_interaction_focus,
+                            trigger_interaction.run_if(is_state_simulating).run_if(is_dialogue_inactive),
+                            update_interaction_prompt
+                        )
+                            .chain());
+    }
+}
+
+/// Spawns the hidden prompt text at the bottom of the screen.
+#[coverage(off)]
+fn spawn_interaction_prompt(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Interaction Prompt"),
+        InteractionPrompt,
+        Text::new(""),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(Justify::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            bottom: Val::Percent(18.0),
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+}
+
+/// Attaches the proximity sensor to new [`Interactable`]s and resizes it when the range
+/// changes. Sensors of entities which lost their interactable are removed. The sensor
+/// reacts to kinematic and fixed bodies too, so static props and kinematic players are
+/// detected.
+///
+/// # Parameters
+/// * `commands` - Spawns and removes the sensor children.
+/// * `removed` - Entities which lost their interactable.
+/// * `interactables` - Added or changed interactables.
+/// * `owners` - Children of entities without an interactable.
+/// * `sensors` - Colliders of the existing sensors.
+#[coverage(off)]
+fn attach_interaction_sensors(
+    mut commands: Commands,
+    mut removed: RemovedComponents<Interactable>,
+    interactables: Query<(Entity, &Interactable, Option<&Children>), Changed<Interactable>>,
+    owners: Query<&Children, Without<Interactable>>,
+    mut sensors: Query<&mut Collider, With<InteractionSensor>>,
+) {
+    for entity in removed.read() {
+        let Ok(children) = owners.get(entity) else {
+            continue;
+        };
+        for child in children.iter().filter(|child| sensors.contains(*child)) {
+            commands.entity(child).despawn();
+        }
+    }
+
+    for (entity, interactable, children) in interactables.iter() {
+        let radius = interactable.range.max(0.01);
+        let sensor = children.and_then(|children| children.iter().find(|child| sensors.contains(*child)));
+        match sensor.and_then(|sensor| sensors.get_mut(sensor).ok()) {
+            Some(mut collider) => {
+                if collider.as_ball().is_none_or(|ball| ball.radius() != radius) {
+                    *collider = Collider::ball(radius);
+                }
+            }
+            None => {
+                commands.spawn((
+                    Name::new("Interaction Sensor"),
+                    InteractionSensor { owner: entity },
+                    Collider::ball(radius),
+                    Sensor,
+                    ActiveEvents::COLLISION_EVENTS,
+                    ActiveCollisionTypes::all(),
+                    Transform::default(),
+                    ChildOf(entity),
+                ));
+            }
+        }
+    }
+}
+
+/// Adds and removes [`Interactable`]s from the candidates of an [`Interactor`] when
+/// their sensors start or stop overlapping it.
+///
+/// # Parameters
+/// * `collisions` - Rapier collision events.
+/// * `sensors` - Interaction sensors and their owners.
+/// * `interactors` - Entities which can interact.
+#[coverage(off)]
+fn track_interaction_sensors(
+    mut collisions: MessageReader<CollisionEvent>,
+    sensors: Query<&InteractionSensor>,
+    mut interactors: Query<&mut Interactor>,
+) {
+    for collision in collisions.read() {
+        let (a, b, started) = match collision {
+            CollisionEvent::Started(a, b, _) => (*a, *b, true),
+            CollisionEvent::Stopped(a, b, _) => (*a, *b, false),
+        };
+
+        for (sensor, other) in [(a, b), (b, a)] {
+            let Ok(sensor) = sensors.get(sensor) else {
+                continue;
+            };
+            let Ok(mut interactor) = interactors.get_mut(other) else {
+                continue;
+            };
+            if started {
+                interactor.enter(sensor.owner);
+            } else {
+                interactor.leave(sensor.owner);
+            }
+        }
+    }
+}
+
+/// Picks the focused candidate of every [`Interactor`]. The highest priority wins,
+/// candidates of the same priority are rated by the view direction of the camera
+/// and the distance. Despawned candidates are dropped.
+///
+/// # Parameters
+/// * `settings` - Facing threshold and distance weight.
+/// * `cameras` - View direction, falls back to the forward of the interactor.
+/// * `interactors` - Entities which can interact.
+/// * `interactables` - Candidates.
+#[coverage(off)]
+fn select_interaction_focus(
+    settings: Res<InteractionSettings>,
+    cameras: Query<&GlobalTransform, With<Camera3d>>,
+    mut interactors: Query<(&mut Interactor, &GlobalTransform)>,
+    interactables: Query<(&Interactable, &GlobalTransform)>,
+) {
+    let camera_view = cameras.iter().next().map(|camera| camera.forward().as_vec3());
+
+    for (mut interactor, transform) in interactors.iter_mut() {
+        interactor.in_range.retain(|entity| interactables.contains(*entity));
+
+        let origin = transform.translation();
+        let view = camera_view.unwrap_or_else(|| transform.forward().as_vec3());
+        let focused = interactor.in_range
+            .iter()
+            .filter_map(|entity| {
+                let (interactable, target) = interactables.get(*entity).ok()?;
+                if !interactable.enabled {
+                    return None;
+                }
+                let score = settings.score(view, target.translation() - origin)?;
+                Some((*entity, interactable.priority, score))
+            })
+            .max_by(|a, b| a.1.cmp(&b.1).then(a.2.total_cmp(&b.2)))
+            .map(|(entity, _, _)| entity);
+
+        if interactor.focused != focused {
+            interactor.focused = focused;
+        }
+    }
+}
+
+/// Writes [`Interacted`] for the focused candidate of the player when the interact
+/// key is pressed. Paused while a dialogue is running.
+///
+/// # Parameters
+/// * `keyboard` - Keyboard input.
+/// * `global_config` - Key bindings.
+/// * `players` - Interactors controlled by the player.
+/// * `interacted` - Writer for interactions.
+#[coverage(off)]
+pub(crate) fn trigger_interaction(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    global_config: Res<GlobalConfig>,
+    players: Query<(Entity, &Interactor), With<Player>>,
+    mut interacted: MessageWriter<Interacted>,
+) {
+    if !keyboard.just_pressed(global_config.input_config.get_interact_key()) {
+        return;
+    }
+    for (entity, interactor) in players.iter() {
+        if let Some(target) = interactor.focused {
+            debug!("{:?} interacts with {:?}", entity, target);
+            interacted.write(Interacted { interactor: entity, target });
+        }
+    }
+}
+
+/// Shows the current interact key and the localized prompt of the player's focused
+/// candidate. Hidden while a dialogue is running.
+///
+/// # Parameters
+/// * `global_config` - Name of the interact key.
+/// * `localization` - Texts of the active language.
+/// * `dialogue` - Running dialogue.
+/// * `players` - Interactors controlled by the player.
+/// * `interactables` - Prompt texts.
+/// * `prompts` - On-screen prompt.
+#[coverage(off)]
+fn update_interaction_prompt(
+    global_config: Res<GlobalConfig>,
+    localization: Localization,
+    dialogue: Res<DialogueState>,
+    players: Query<&Interactor, With<Player>>,
+    interactables: Query<&Interactable>,
+    mut prompts: Query<(&mut Text, &mut Visibility), With<InteractionPrompt>>,
+) {
+    let prompt = players
+        .iter()
+        .filter(|_| !dialogue.is_active())
+        .find_map(|interactor| interactor.focused)
+        .and_then(|entity| interactables.get(entity).ok())
+        .map(|interactable| format!("[{}] {}", global_config.input_config.interact, localization.text(&interactable.prompt)));
+
+    for (mut text, mut visibility) in prompts.iter_mut() {
+        match &prompt {
+            Some(prompt) => {
+                if text.0 != *prompt {
+                    text.0 = prompt.clone();
+                }
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            None => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+}
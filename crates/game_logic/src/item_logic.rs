@@ -0,0 +1,217 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{Collider, RigidBody};
+use game_models::interaction::{Interactable, Interacted};
+use game_models::items::inventory::{Inventory, ItemStack};
+use game_models::items::item::Items;
+use game_models::items::{DropItem, ItemDropped, ItemPickedUp, ItemUsed, PickUpItem, UseItem, WorldItem};
+use game_models::states::is_state_simulating;
+
+pub struct ItemLogicComponent;
+
+impl Plugin for ItemLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, attach_world_item_models);
+        app.add_systems(Update,
+                        (
+                            pick_up_on_interact,
+                            handle_pick_up,
+                            handle_drop,
+                            handle_use
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Size of the box shown for items lying in the world.
+const WORLD_ITEM_HALF_EXTENT: f32 = 0.15;
+
+/// Gives new [`WorldItem`]s a box model and a physics body, so dropped, crafted and
+/// looted items are visible, fall to the ground and can be picked up. All items share
+/// one mesh and material.
+///
+/// # Parameters
+/// * `commands` - Adds the model and body.
+/// * `meshes` - Storage of the shared mesh.
+/// * `materials` - Storage of the shared material.
+/// * `model` - Shared mesh and material, created on first use.
+/// * `world_items` - Items spawned since the last frame.
+#[coverage(off)]
+fn attach_world_item_models(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut model: Local<Option<(Handle<Mesh>, Handle<StandardMaterial>)>>,
+    world_items: Query<Entity, Added<WorldItem>>,
+) {
+    if world_items.is_empty() {
+        return;
+    }
+    let (mesh, material) = model.get_or_insert_with(|| (
+        meshes.add(Cuboid::from_length(WORLD_ITEM_HALF_EXTENT * 2.0)),
+        materials.add(StandardMaterial {
+            base_color: Color::srgb(0.85, 0.7, 0.3),
+            ..default()
+        }),
+    )).clone();
+
+    for entity in world_items.iter() {
+        commands.entity(entity).insert((
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(material.clone()),
+            RigidBody::Dynamic,
+            Collider::cuboid(WORLD_ITEM_HALF_EXTENT, WORLD_ITEM_HALF_EXTENT, WORLD_ITEM_HALF_EXTENT),
+        ));
+    }
+}
+
+/// Turns interactions with a [`WorldItem`] into a [`PickUpItem`] request.
+///
+/// # Parameters
+/// * `interactions` - Interactions of any kind.
+/// * `world_items` - Items lying in the world.
+/// * `requests` - Writer for pick up requests.
+#[coverage(off)]
+fn pick_up_on_interact(
+    mut interactions: MessageReader<Interacted>,
+    world_items: Query<(), With<WorldItem>>,
+    mut requests: MessageWriter<PickUpItem>,
+) {
+    for interaction in interactions.read() {
+        if world_items.contains(interaction.target) {
+            requests.write(PickUpItem {
+                entity: interaction.interactor,
+                item_entity: interaction.target,
+            });
+        }
+    }
+}
+
+/// Moves [`WorldItem`]s into the inventory of the requesting entity. Items which
+/// don't fit stay in the world with the remaining amount. Fully taken items are
+/// emptied right away, so further requests of the same frame get nothing.
+///
+/// # Parameters
+/// * `requests` - Pick up requests.
+/// * `picked_up` - Notification writer for successful pick-ups.
+/// * `items` - Item definitions for stack sizes.
+/// * `inventories` - Inventories of the picking entities.
+/// * `world_items` - Items lying in the world.
+#[coverage(off)]
+fn handle_pick_up(
+    mut commands: Commands,
+    mut requests: MessageReader<PickUpItem>,
+    mut picked_up: MessageWriter<ItemPickedUp>,
+    items: Items,
+    mut inventories: Query<&mut Inventory>,
+    mut world_items: Query<&mut WorldItem>,
+) {
+    for request in requests.read() {
+        let Ok(mut inventory) = inventories.get_mut(request.entity) else {
+            continue;
+        };
+        let Ok(mut world_item) = world_items.get_mut(request.item_entity) else {
+            continue;
+        };
+
+        if world_item.0.count == 0 {
+            continue;
+        }
+
+        let stack = world_item.0.clone();
+        let remaining = inventory.add(&stack.item, stack.count, |id| items.stack_size(id));
+        let taken = stack.count - remaining;
+        if taken == 0 {
+            continue;
+        }
+
+        world_item.0.count = remaining;
+        if remaining == 0 {
+            commands.entity(request.item_entity).despawn();
+        }
+
+        picked_up.write(ItemPickedUp {
+            entity: request.entity,
+            stack: ItemStack::new(stack.item, taken),
+        });
+    }
+}
+
+/// Removes items from an inventory and spawns them as [`WorldItem`] in front of the entity.
+///
+/// # Parameters
+/// * `requests` - Drop requests.
+/// * `dropped` - Notification writer for dropped items.
+/// * `owners` - Inventory and position of the dropping entities.
+#[coverage(off)]
+fn handle_drop(
+    mut commands: Commands,
+    mut requests: MessageReader<DropItem>,
+    mut dropped: MessageWriter<ItemDropped>,
+    mut owners: Query<(&mut Inventory, &GlobalTransform)>,
+) {
+    for request in requests.read() {
+        let Ok((mut inventory, transform)) = owners.get_mut(request.entity) else {
+            continue;
+        };
+
+        let stack = match inventory.take_from_slot(request.slot, request.count) {
+            Ok(stack) if stack.count > 0 => stack,
+            Ok(_) => continue,
+            Err(e) => {
+                debug!("Drop failed for {:?}: {}", request.entity, e);
+                continue;
+            }
+        };
+
+        let position = transform.translation() + transform.forward() * 1.0;
+        let world_item = commands.spawn((
+            Name::new(format!("Item: {}", stack.item)),
+            Transform::from_translation(position),
+            WorldItem(stack.clone()),
+            Interactable::new("interact.pick_up", 1.5),
+        )).id();
+
+        dropped.write(ItemDropped {
+            entity: request.entity,
+            world_item,
+            stack,
+        });
+    }
+}
+
+/// Consumes one item of the requested slot if it is tagged as `consumable`.
+/// The effect of the item is applied by systems listening for [`ItemUsed`].
+///
+/// # Parameters
+/// * `requests` - Use requests.
+/// * `used` - Notification writer for used items.
+/// * `items` - Item definitions for tags.
+/// * `inventories` - Inventories of the using entities.
+#[coverage(off)]
+fn handle_use(
+    mut requests: MessageReader<UseItem>,
+    mut used: MessageWriter<ItemUsed>,
+    items: Items,
+    mut inventories: Query<&mut Inventory>,
+) {
+    for request in requests.read() {
+        let Ok(mut inventory) = inventories.get_mut(request.entity) else {
+            continue;
+        };
+        let Some(stack) = inventory.get(request.slot) else {
+            continue;
+        };
+        let consumable = items.get(&stack.item).is_some_and(|item| item.has_tag("consumable"));
+        if !consumable {
+            continue;
+        }
+
+        if let Ok(stack) = inventory.take_from_slot(request.slot, 1) {
+            used.write(ItemUsed {
+                entity: request.entity,
+                item: stack.item,
+            });
+        }
+    }
+}
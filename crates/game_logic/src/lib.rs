@@ -2,11 +2,63 @@
 
 mod debug_logic;
 mod camera_logic;
+mod entity_logic;
+mod health_logic;
+mod attribute_logic;
+mod item_logic;
+mod equipment_logic;
+mod ai_logic;
+mod navigation_logic;
+mod time_of_day_logic;
+mod spawning_logic;
+mod pacing_logic;
+mod weather_logic;
+mod interaction_logic;
+mod dialogue_logic;
+mod quest_logic;
+mod crafting_logic;
+mod building_logic;
+mod survival_logic;
+mod combat_logic;
+mod projectile_logic;
+mod status_effect_logic;
+mod loot_logic;
+mod progression_logic;
+mod animation_logic;
+mod ragdoll_logic;
+mod faction_logic;
+mod save_game_logic;
 
 use bevy::prelude::*;
 use game_models::states::AppState;
+use crate::ai_logic::AiLogicComponent;
+use crate::animation_logic::AnimationLogicComponent;
+use crate::attribute_logic::AttributeLogicComponent;
+use crate::building_logic::BuildingLogicComponent;
 use crate::camera_logic::{orbit_camera_controls, setup_test_scene};
+use crate::combat_logic::CombatLogicComponent;
+use crate::crafting_logic::CraftingLogicComponent;
 use crate::debug_logic::DebugLogicComponent;
+use crate::dialogue_logic::DialogueLogicComponent;
+use crate::entity_logic::EntityLogicComponent;
+use crate::equipment_logic::EquipmentLogicComponent;
+use crate::faction_logic::FactionLogicComponent;
+use crate::health_logic::HealthLogicComponent;
+use crate::interaction_logic::InteractionLogicComponent;
+use crate::item_logic::ItemLogicComponent;
+use crate::loot_logic::LootLogicComponent;
+use crate::navigation_logic::NavigationLogicComponent;
+use crate::pacing_logic::PacingLogicComponent;
+use crate::progression_logic::ProgressionLogicComponent;
+use crate::projectile_logic::ProjectileLogicComponent;
+use crate::quest_logic::QuestLogicComponent;
+use crate::ragdoll_logic::RagdollLogicComponent;
+use crate::save_game_logic::SaveGameLogicComponent;
+use crate::spawning_logic::SpawningLogicComponent;
+use crate::status_effect_logic::StatusEffectLogicComponent;
+use crate::survival_logic::SurvivalLogicComponent;
+use crate::time_of_day_logic::TimeOfDayLogicComponent;
+use crate::weather_logic::WeatherLogicComponent;
 
 pub struct GameLogicPlugin;
 
@@ -14,7 +66,37 @@ impl Plugin for GameLogicPlugin {
 
     #[coverage(off)]
     fn build(&self, app: &mut App) {
-        app.add_plugins(DebugLogicComponent);
+        app.add_plugins((
+            DebugLogicComponent,
+            EntityLogicComponent,
+            HealthLogicComponent,
+            AttributeLogicComponent,
+            ItemLogicComponent,
+            EquipmentLogicComponent,
+            AiLogicComponent,
+            NavigationLogicComponent,
+            TimeOfDayLogicComponent,
+            SpawningLogicComponent,
+            PacingLogicComponent,
+            WeatherLogicComponent,
+        ));
+        app.add_plugins((
+            InteractionLogicComponent,
+            DialogueLogicComponent,
+            QuestLogicComponent,
+            CraftingLogicComponent,
+            BuildingLogicComponent,
+            SurvivalLogicComponent,
+            CombatLogicComponent,
+            ProjectileLogicComponent,
+            StatusEffectLogicComponent,
+            LootLogicComponent,
+            ProgressionLogicComponent,
+            AnimationLogicComponent,
+            RagdollLogicComponent,
+            FactionLogicComponent,
+            SaveGameLogicComponent,
+        ));
         app.add_systems(OnEnter(AppState::Preload), setup_test_scene);
         app.add_systems(Update, orbit_camera_controls.run_if(in_state(AppState::Preload)));
     }
@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use game_models::entities::character::Character;
+use game_models::entities::definition::{DefinitionInteractable, LootReference};
+use game_models::health::DeathEvent;
+use game_models::interaction::{Interactable, Interacted};
+use game_models::items::inventory::{Inventory, ItemStack};
+use game_models::items::item::Items;
+use game_models::items::loot::{Biome, LootContainer, LootContext, LootDropped, LootRng, LootTables};
+use game_models::items::WorldItem;
+use game_models::spawning::NightDirector;
+use game_models::states::is_state_simulating;
+use crate::crafting_logic::give_or_drop;
+
+pub struct LootLogicComponent;
+
+impl Plugin for LootLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            drop_loot_on_death,
+                            open_loot_containers
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Radius around a dead entity in which its loot is scattered.
+const LOOT_SCATTER_RADIUS: f32 = 0.8;
+
+/// Facts of the loot conditions for a source in `biome`. Without a [`Character`] the
+/// player counts as level 1.
+fn loot_context(director: &NightDirector, biome: Option<&Biome>, character: Option<&Character>) -> LootContext {
+    LootContext {
+        night: director.night,
+        biome: biome.map(|biome| biome.0.clone()),
+        player_level: character.map_or(1, |character| character.level),
+    }
+}
+
+/// Rolls the loot table of dead entities and scatters the drops as [`WorldItem`]s
+/// around the body. Containers only drop loot when opened.
+///
+/// # Parameters
+/// * `commands` - Spawns the dropped items.
+/// * `deaths` - Died entities.
+/// * `tables` - Loot tables.
+/// * `director` - Current night for the conditions.
+/// * `characters` - Player level for the conditions.
+/// * `rng` - Random source of the session for the rolls and the scattering.
+/// * `sources` - Loot table, position and biome of the died entities.
+/// * `dropped` - Notification writer for the loot.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn drop_loot_on_death(
+    mut commands: Commands,
+    mut deaths: MessageReader<DeathEvent>,
+    tables: LootTables,
+    director: Res<NightDirector>,
+    characters: Query<&Character>,
+    mut rng: ResMut<LootRng>,
+    sources: Query<(&LootReference, &GlobalTransform, Option<&Biome>), Without<LootContainer>>,
+    mut dropped: MessageWriter<LootDropped>,
+) {
+    for death in deaths.read() {
+        let Ok((loot, transform, biome)) = sources.get(death.entity) else {
+            continue;
+        };
+        let items = tables.roll(&loot.0, &loot_context(&director, biome, characters.single().ok()), &mut rng.0);
+        if items.is_empty() {
+            continue;
+        }
+
+        let position = transform.translation();
+        for stack in items.iter() {
+            let offset = rng.0.point_in_circle(LOOT_SCATTER_RADIUS);
+            commands.spawn((
+                Name::new(format!("Item: {}", stack.item)),
+                Transform::from_translation(position + Vec3::new(offset.x, 0.0, offset.y)),
+                WorldItem(ItemStack::new(stack.item.clone(), stack.count)),
+                Interactable::new("interact.pick_up", 1.5),
+            ));
+        }
+        debug!("{:?} dropped {:?}", death.entity, items);
+        dropped.write(LootDropped { source: death.entity, receiver: None, table: loot.0.clone(), items });
+    }
+}
+
+/// Rolls the loot table of a [`LootContainer`] on its first interaction and gives the
+/// items to the interactor. Whatever doesn't fit is dropped in front of the container.
+///
+/// # Parameters
+/// * `commands` - Removes the prompt of looted containers.
+/// * `interactions` - Interactions with containers.
+/// * `tables` - Loot tables.
+/// * `items` - Item definitions for the stack sizes.
+/// * `director` - Current night for the conditions.
+/// * `characters` - Player level for the conditions.
+/// * `rng` - Random source of the session for the rolls.
+/// * `containers` - Loot table, state, position and biome of the containers.
+/// * `inventories` - Inventories of the interactors.
+/// * `dropped` - Notification writer for the loot.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn open_loot_containers(
+    mut commands: Commands,
+    mut interactions: MessageReader<Interacted>,
+    tables: LootTables,
+    items: Items,
+    director: Res<NightDirector>,
+    characters: Query<&Character>,
+    mut rng: ResMut<LootRng>,
+    mut containers: Query<(&LootReference, &mut LootContainer, &GlobalTransform, Option<&Biome>)>,
+    mut inventories: Query<&mut Inventory>,
+    mut dropped: MessageWriter<LootDropped>,
+) {
+    for interaction in interactions.read() {
+        let Ok((loot, mut container, transform, biome)) = containers.get_mut(interaction.target) else {
+            continue;
+        };
+        if container.looted {
+            continue;
+        }
+        container.looted = true;
+        commands.entity(interaction.target).remove::<(Interactable, DefinitionInteractable)>();
+
+        let loot_items = tables.roll(&loot.0, &loot_context(&director, biome, characters.single().ok()), &mut rng.0);
+        let mut inventory = inventories.get_mut(interaction.interactor).ok();
+        give_or_drop(&mut commands, inventory.as_deref_mut(), &loot_items, transform.translation() + Vec3::Y * 0.5, &items);
+        dropped.write(LootDropped {
+            source: interaction.target,
+            receiver: Some(interaction.interactor),
+            table: loot.0.clone(),
+            items: loot_items,
+        });
+    }
+}
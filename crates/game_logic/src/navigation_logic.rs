@@ -0,0 +1,453 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use bevy::prelude::*;
+use bevy::tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task};
+use bevy_rapier3d::prelude::{Collider, ColliderDisabled, DebugRenderContext, RayIntersection, RigidBody, Sensor};
+use game_models::ai::behaviour::AiMovement;
+use game_models::entities::player::Player;
+use game_models::health::Dead;
+use game_models::navigation::agent::{NavAgent, NavChunkBaked, NavPathStatus, RebakeNavChunk};
+use game_models::navigation::grid::{NavBakeQueue, NavChunk, NavMesh, NavNode, NavSettings};
+use game_models::states::is_state_simulating;
+use crate::ai_logic::{apply_ai_movement, run_state_actions};
+
+pub struct NavigationLogicComponent;
+
+impl Plugin for NavigationLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavBakeTasks>();
+        app.add_systems(Update,
+                        (
+                            queue_nav_chunks,
+                            bake_nav_chunks,
+                            invalidate_baked_paths
+                        )
+                            .chain());
+        app.add_systems(Update,
+                        (
+                            draw_nav_mesh,
+                            draw_nav_paths
+                        )
+                            .run_if(nav_gizmos_enabled));
+        app.add_systems(FixedUpdate,
+                        (
+                            request_paths,
+                            receive_paths,
+                            avoid_agents
+                        )
+                            .chain()
+                            .after(run_state_actions)
+                            .before(apply_ai_movement)
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Running path query of an agent.
+#[derive(Component)]
+struct NavPathTask(Task<Option<Vec<Vec3>>>);
+
+/// Chunks which are baking on the [`AsyncComputeTaskPool`].
+#[derive(Resource, Default)]
+struct NavBakeTasks(Vec<(IVec2, Task<NavChunk>)>);
+
+impl NavBakeTasks {
+
+    fn contains(&self, chunk: IVec2) -> bool {
+        self.0.iter().any(|(coord, _)| *coord == chunk)
+    }
+}
+
+/// Fixed colliders of the world with their translation and rotation. Copied from the
+/// ECS, so chunks can be baked on other threads while the physics world keeps running.
+#[derive(Default)]
+struct ColliderSnapshot(Vec<(Collider, Vec3, Quat)>);
+
+impl ColliderSnapshot {
+
+    /// Closest hit of a ray with any collider.
+    fn cast_ray_and_get_normal(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<RayIntersection> {
+        self.0
+            .iter()
+            .filter_map(|(collider, translation, rotation)| {
+                collider.cast_ray_and_get_normal(*translation, *rotation, origin, direction, max_distance, false)
+            })
+            .min_by(|a, b| a.time_of_impact.total_cmp(&b.time_of_impact))
+    }
+
+    /// `true` if a ray hits any collider.
+    fn intersects_ray(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> bool {
+        self.0.iter().any(|(collider, translation, rotation)| {
+            collider.cast_ray(*translation, *rotation, origin, direction, max_distance, false).is_some()
+        })
+    }
+}
+
+/// Queues the chunks around the player which are not baked yet and all chunks
+/// requested by [`RebakeNavChunk`].
+///
+/// # Parameters
+/// * `rebake` - Chunks which changed and need a new bake.
+/// * `players` - Positions the grid is baked around.
+/// * `nav_mesh` - Current navigation grid.
+/// * `settings` - Bake radius.
+/// * `queue` - Chunks waiting for a bake.
+/// * `tasks` - Chunks which are already baking.
+#[coverage(off)]
+fn queue_nav_chunks(
+    mut rebake: MessageReader<RebakeNavChunk>,
+    players: Query<&Transform, With<Player>>,
+    nav_mesh: Res<NavMesh>,
+    settings: Res<NavSettings>,
+    mut queue: ResMut<NavBakeQueue>,
+    tasks: Res<NavBakeTasks>,
+) {
+    for request in rebake.read() {
+        queue.push(request.chunk);
+    }
+
+    let grid = nav_mesh.grid();
+    for transform in players.iter() {
+        let center = grid.chunk_at(transform.translation);
+        for z in -settings.bake_radius..=settings.bake_radius {
+            for x in -settings.bake_radius..=settings.bake_radius {
+                let chunk = center + IVec2::new(x, z);
+                if !grid.contains_chunk(chunk) && !tasks.contains(chunk) {
+                    queue.push(chunk);
+                }
+            }
+        }
+    }
+}
+
+/// Bakes queued chunks from the fixed Rapier colliders of the world on the
+/// [`AsyncComputeTaskPool`] and stores the finished ones in the grid. Chunks without
+/// any collider fall back to a flat grid at [`NavSettings::fallback_height`].
+///
+/// Chunks which are already baking stay queued until their running bake finished, so
+/// changes made in the meantime are baked again.
+///
+/// # Parameters
+/// * `settings` - Bake settings.
+/// * `queue` - Chunks waiting for a bake.
+/// * `tasks` - Running bakes.
+/// * `colliders` - Colliders copied for the bakes.
+/// * `bodies` - Rigid bodies of colliders attached to a parent.
+/// * `nav_mesh` - Navigation grid receiving the baked chunks.
+/// * `baked` - Writer for finished chunks.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn bake_nav_chunks(
+    settings: Res<NavSettings>,
+    mut queue: ResMut<NavBakeQueue>,
+    mut tasks: ResMut<NavBakeTasks>,
+    colliders: Query<(&Collider, &GlobalTransform, Option<&RigidBody>, Option<&ChildOf>), (Without<Sensor>, Without<ColliderDisabled>)>,
+    bodies: Query<&RigidBody>,
+    mut nav_mesh: ResMut<NavMesh>,
+    mut baked: MessageWriter<NavChunkBaked>,
+) {
+    tasks.0.retain_mut(|(coord, task)| {
+        let Some(chunk) = block_on(future::poll_once(task)) else {
+            return true;
+        };
+        debug!("Baked navigation chunk {}", coord);
+        nav_mesh.grid_mut().insert_chunk(*coord, chunk);
+        baked.write(NavChunkBaked { chunk: *coord });
+        false
+    });
+
+    if queue.0.is_empty() {
+        return;
+    }
+
+    let snapshot = Arc::new(ColliderSnapshot(
+        colliders
+            .iter()
+            .filter(|(_, _, body, parent)| {
+                let body = body.or_else(|| parent.and_then(|parent| bodies.get(parent.parent()).ok()));
+                body.is_none_or(|body| matches!(body, RigidBody::Fixed))
+            })
+            .map(|(collider, transform, ..)| {
+                let (_, rotation, translation) = transform.to_scale_rotation_translation();
+                (collider.clone(), translation, rotation)
+            })
+            .collect(),
+    ));
+
+    let pool = AsyncComputeTaskPool::get();
+    let mut waiting = Vec::new();
+    let mut started = 0;
+    while started < settings.bakes_per_frame {
+        let Some(coord) = queue.0.pop_front() else {
+            break;
+        };
+        if tasks.contains(coord) {
+            waiting.push(coord);
+            continue;
+        }
+
+        let settings = settings.clone();
+        let colliders = Arc::clone(&snapshot);
+        let task = pool.spawn(async move { bake_chunk(coord, &settings, &colliders) });
+        tasks.0.push((coord, task));
+        started += 1;
+    }
+    for coord in waiting.into_iter().rev() {
+        queue.0.push_front(coord);
+    }
+}
+
+/// Bakes the chunk at `coord`, falling back to a flat chunk if nothing is walkable.
+fn bake_chunk(coord: IVec2, settings: &NavSettings, colliders: &ColliderSnapshot) -> NavChunk {
+    let chunk = NavChunk::bake(coord, settings, |xz| sample_column(colliders, settings, xz));
+    match settings.fallback_height {
+        Some(height) if chunk.is_empty() => NavChunk::flat(settings.chunk_cells, height),
+        _ => chunk,
+    }
+}
+
+/// Returns the heights of all walkable surfaces in the column at `xz`, top to bottom.
+///
+/// The ray walks down through the column and keeps every hit which is flat enough
+/// and has [`NavSettings::agent_height`] free space above it. Undersides of colliders
+/// are rejected by the head room check as well.
+fn sample_column(colliders: &ColliderSnapshot, settings: &NavSettings, xz: Vec2) -> Vec<f32> {
+    let min_normal_y = settings.max_slope.to_radians().cos();
+    let mut layers = Vec::new();
+    let mut origin = Vec3::new(xz.x, settings.bake_top, xz.y);
+
+    while layers.len() < settings.max_layers {
+        let max_distance = origin.y - settings.bake_bottom;
+        if max_distance <= 0.0 {
+            break;
+        }
+        let Some(hit) = colliders.cast_ray_and_get_normal(origin, Vec3::NEG_Y, max_distance) else {
+            break;
+        };
+
+        if hit.normal.y >= min_normal_y {
+            let head = Vec3::new(xz.x, hit.point.y + 0.05, xz.y);
+            if !colliders.intersects_ray(head, Vec3::Y, settings.agent_height) {
+                layers.push(hit.point.y);
+            }
+        }
+        origin = Vec3::new(xz.x, hit.point.y - 0.05, xz.y);
+    }
+    layers
+}
+
+/// Drops the paths which cross a chunk baked this frame, so they are requested again
+/// on the new grid. Running and failed queries searched the old grid and are
+/// requested again as well.
+///
+/// # Parameters
+/// * `baked` - Chunks baked this frame.
+/// * `nav_mesh` - Navigation grid.
+/// * `agents` - Navigating entities.
+#[coverage(off)]
+fn invalidate_baked_paths(
+    mut baked: MessageReader<NavChunkBaked>,
+    nav_mesh: Res<NavMesh>,
+    mut agents: Query<(&mut NavAgent, &Transform)>,
+) {
+    let chunks: HashSet<IVec2> = baked.read().map(|event| event.chunk).collect();
+    if chunks.is_empty() {
+        return;
+    }
+
+    let grid = nav_mesh.grid();
+    for (mut agent, transform) in agents.iter_mut() {
+        let invalid = match agent.status {
+            NavPathStatus::Idle => false,
+            NavPathStatus::Following => {
+                let path: Vec<Vec3> = std::iter::once(transform.translation)
+                    .chain(agent.remaining_path().iter().copied())
+                    .collect();
+                grid.path_crosses_chunks(&path, &chunks)
+            }
+            NavPathStatus::Pending | NavPathStatus::Unreachable => true,
+        };
+        if invalid {
+            agent.clear_path();
+        }
+    }
+}
+
+/// Starts an async path query for every agent whose goal changed.
+///
+/// # Parameters
+/// * `commands` - Used to attach the running [`NavPathTask`].
+/// * `agents` - Navigating entities without a running query.
+/// * `nav_mesh` - Grid snapshot handed to the query.
+/// * `settings` - Search limits.
+#[coverage(off)]
+fn request_paths(
+    mut commands: Commands,
+    mut agents: Query<(Entity, &mut NavAgent, &Transform), (Without<NavPathTask>, Without<Dead>)>,
+    nav_mesh: Res<NavMesh>,
+    settings: Res<NavSettings>,
+) {
+    let pool = AsyncComputeTaskPool::get();
+    for (entity, mut agent, transform) in agents.iter_mut() {
+        if !agent.needs_path() {
+            continue;
+        }
+        let Some(goal) = agent.goal else {
+            continue;
+        };
+
+        agent.start_query();
+        let grid = nav_mesh.grid();
+        let start = transform.translation;
+        let max_nodes = settings.max_search_nodes;
+        let task = pool.spawn(async move { grid.find_path(start, goal, max_nodes) });
+        commands.entity(entity).insert(NavPathTask(task));
+    }
+}
+
+/// Stores finished path queries in their agents.
+///
+/// # Parameters
+/// * `commands` - Used to remove finished tasks.
+/// * `agents` - Navigating entities with a running query.
+#[coverage(off)]
+fn receive_paths(
+    mut commands: Commands,
+    mut agents: Query<(Entity, &mut NavAgent, &mut NavPathTask)>,
+) {
+    for (entity, mut agent, mut task) in agents.iter_mut() {
+        let Some(path) = block_on(future::poll_once(&mut task.0)) else {
+            continue;
+        };
+        commands.entity(entity).remove::<NavPathTask>();
+
+        if agent.status != NavPathStatus::Pending || agent.goal.is_none() {
+            continue;
+        }
+        agent.set_path(path);
+    }
+}
+
+/// Local avoidance: steers moving agents away from agents they are about to overlap
+/// with, keeping their speed.
+///
+/// # Parameters
+/// * `agents` - Moving AI entities.
+/// * `settings` - Avoidance margin and weight.
+#[coverage(off)]
+fn avoid_agents(
+    mut agents: Query<(Entity, &NavAgent, &Transform, &mut AiMovement), Without<Dead>>,
+    settings: Res<NavSettings>,
+) {
+    let others: Vec<(Entity, Vec3, f32)> = agents
+        .iter()
+        .map(|(entity, agent, transform, _)| (entity, transform.translation, agent.radius))
+        .collect();
+
+    for (entity, agent, transform, mut movement) in agents.iter_mut() {
+        let speed = movement.desired_velocity.length();
+        if speed <= f32::EPSILON {
+            continue;
+        }
+
+        let mut push = Vec3::ZERO;
+        for (other, position, radius) in others.iter() {
+            if *other == entity {
+                continue;
+            }
+            let offset = Vec3::new(transform.translation.x - position.x, 0.0, transform.translation.z - position.z);
+            let distance = offset.length();
+            let min_distance = agent.radius + radius + settings.avoidance_margin;
+            if distance >= min_distance || distance <= f32::EPSILON {
+                continue;
+            }
+            push += offset / distance * (1.0 - distance / min_distance);
+        }
+
+        if push != Vec3::ZERO {
+            let direction = movement.desired_velocity / speed + push * settings.avoidance_weight;
+            movement.desired_velocity = direction.normalize_or_zero() * speed;
+        }
+    }
+}
+
+/// Navigation gizmos are shown together with the physics debug render (F9 by default).
+#[coverage(off)]
+fn nav_gizmos_enabled(context: Option<Res<DebugRenderContext>>) -> bool {
+    context.is_some_and(|c| c.enabled)
+}
+
+/// Draws the walkable connections of the grid around the player.
+///
+/// # Parameters
+/// * `gizmos` - Gizmo buffer.
+/// * `players` - Center of the drawn area.
+/// * `nav_mesh` - Navigation grid.
+/// * `settings` - Drawn radius.
+#[coverage(off)]
+fn draw_nav_mesh(
+    mut gizmos: Gizmos,
+    players: Query<&Transform, With<Player>>,
+    nav_mesh: Res<NavMesh>,
+    settings: Res<NavSettings>,
+) {
+    let Ok(player) = players.single() else {
+        return;
+    };
+    let grid = nav_mesh.grid();
+    let center = grid.cell_of(player.translation);
+    let radius = (settings.gizmo_radius / grid.cell_size).ceil() as i32;
+    let color = Color::srgba(0.2, 0.8, 1.0, 0.35);
+    let lift = Vec3::Y * 0.05;
+
+    for z in -radius..=radius {
+        for x in -radius..=radius {
+            let cell = center + IVec2::new(x, z);
+            for (layer, height) in grid.layers(cell).iter().enumerate() {
+                let Some(from) = grid.node_position(NavNode { cell, layer: layer as u8 }) else {
+                    continue;
+                };
+                for offset in [IVec2::X, IVec2::Y] {
+                    let Some(next) = grid.connecting_node(cell + offset, *height) else {
+                        continue;
+                    };
+                    if let Some(to) = grid.node_position(next) {
+                        gizmos.line(from + lift, to + lift, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Draws the remaining path and goal of every navigating agent.
+/// The goal is green while following, yellow while computing and red if unreachable.
+///
+/// # Parameters
+/// * `gizmos` - Gizmo buffer.
+/// * `agents` - Navigating entities.
+#[coverage(off)]
+fn draw_nav_paths(
+    mut gizmos: Gizmos,
+    agents: Query<(&NavAgent, &Transform)>,
+) {
+    let lift = Vec3::Y * 0.1;
+    for (agent, transform) in agents.iter() {
+        let Some(goal) = agent.goal else {
+            continue;
+        };
+
+        let path = agent.remaining_path();
+        if !path.is_empty() {
+            let points = std::iter::once(transform.translation).chain(path.iter().map(|p| *p + lift));
+            gizmos.linestrip(points, Color::srgb(1.0, 0.6, 0.1));
+        }
+
+        let color = match agent.status {
+            NavPathStatus::Following => Color::srgb(0.2, 1.0, 0.3),
+            NavPathStatus::Pending => Color::srgb(1.0, 0.9, 0.2),
+            NavPathStatus::Unreachable => Color::srgb(1.0, 0.2, 0.2),
+            NavPathStatus::Idle => Color::WHITE,
+        };
+        gizmos.sphere(Isometry3d::from_translation(goal), 0.25, color);
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use game_models::ai::behaviour::{AiBrain, AiStateGroup};
+use game_models::entities::player::Player;
+use game_models::health::{DamageApplied, Dead, Health};
+use game_models::pacing::{PacingDirector, PacingPhaseChanged, PacingSettings};
+use game_models::states::is_state_simulating;
+use game_models::time_of_day::TimeOfDay;
+
+pub struct PacingLogicComponent;
+
+impl Plugin for PacingLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            update_intensity,
+                            update_pacing_phase
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Raises the intensity from damage taken, nearby hostile enemies, low health and
+/// darkness and lets it decay over time.
+///
+/// # Parameters
+/// * `time` - Global time.
+/// * `damage` - Applied damage, only health lost by the player counts.
+/// * `players` - The player and its health.
+/// * `enemies` - AI controlled entities.
+/// * `time_of_day` - Sun height for the darkness.
+/// * `settings` - Intensity weights.
+/// * `director` - Pacing state.
+#[coverage(off)]
+fn update_intensity(
+    time: Res<Time>,
+    mut damage: MessageReader<DamageApplied>,
+    players: Query<(Entity, &Transform, &Health), (With<Player>, Without<Dead>)>,
+    enemies: Query<(&AiBrain, &Transform), Without<Dead>>,
+    time_of_day: Res<TimeOfDay>,
+    settings: Res<PacingSettings>,
+    mut director: ResMut<PacingDirector>,
+) {
+    let Ok((player, player_transform, health)) = players.single() else {
+        damage.clear();
+        return;
+    };
+    let delta = time.delta_secs();
+    let mut intensity = director.intensity;
+
+    for hit in damage.read().filter(|hit| hit.target == player) {
+        intensity += hit.amount / health.max.max(1.0) * settings.damage_weight;
+    }
+
+    let nearby = enemies
+        .iter()
+        .filter(|(brain, _)| brain.state.group() == AiStateGroup::Hostile)
+        .filter(|(_, transform)| transform.translation.distance(player_transform.translation) <= settings.nearby_radius)
+        .count();
+    intensity += nearby as f32 * settings.nearby_enemy_weight * delta;
+
+    let threshold = settings.low_health_threshold;
+    if threshold > 0.0 && health.fraction() < threshold {
+        intensity += settings.low_health_weight * (1.0 - health.fraction() / threshold) * delta;
+    }
+
+    let darkness = (-time_of_day.sun_elevation()).clamp(0.0, 1.0);
+    intensity += settings.darkness_weight * darkness * delta;
+
+    intensity -= settings.decay * delta;
+    director.intensity = intensity.clamp(0.0, 1.0);
+}
+
+/// Switches between build-up, peak and relax and applies the multipliers of the
+/// active phase. The music intensity eases towards the target of the phase.
+///
+/// # Parameters
+/// * `time` - Global time.
+/// * `settings` - Phase thresholds and profiles.
+/// * `director` - Pacing state.
+/// * `changed` - Writer for phase changes.
+#[coverage(off)]
+fn update_pacing_phase(
+    time: Res<Time>,
+    settings: Res<PacingSettings>,
+    mut director: ResMut<PacingDirector>,
+    mut changed: MessageWriter<PacingPhaseChanged>,
+) {
+    let delta = time.delta_secs();
+    director.time_in_phase += delta;
+
+    if let Some(next) = director.next_phase(&settings) {
+        debug!("Pacing changed from {:?} to {:?} at intensity {:.2}", director.phase, next, director.intensity);
+        changed.write(PacingPhaseChanged { from: director.phase, to: next });
+        director.phase = next;
+        director.time_in_phase = 0.0;
+    }
+
+    let profile = settings.profile(director.phase);
+    director.spawn_rate = profile.spawn_rate;
+    director.aggression = profile.aggression;
+
+    let t = 1.0 - (-settings.music_smoothing * delta).exp();
+    director.music_intensity = director.music_intensity.lerp(profile.music_intensity, t);
+}
@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+use game_models::attributes::AttributeModifiers;
+use game_models::entities::character::Character;
+use game_models::entities::definition::{EntityDefinition, EntityDefinitionRef};
+use game_models::entities::player::Player;
+use game_models::health::DeathEvent;
+use game_models::items::crafting::{CraftingCompleted, Recipes};
+use game_models::progression::{apply_skill_modifiers, ExperienceSource, GainExperience, LevelUp, Progressions, SkillUnlockFailed, SkillUnlocked, UnlockSkill};
+use game_models::states::is_state_simulating;
+
+pub struct ProgressionLogicComponent;
+
+impl Plugin for ProgressionLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            experience_from_kills,
+                            experience_from_crafting,
+                            apply_experience,
+                            handle_skill_unlocks,
+                            sync_skill_modifiers
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Grants the experience of the entity definition when the player kills an entity.
+///
+/// # Parameters
+/// * `deaths` - Died entities.
+/// * `players` - Player entities.
+/// * `definition_refs` - Definitions of the died entities.
+/// * `definitions` - Entity definition assets.
+/// * `experience` - Writer for the gained experience.
+#[coverage(off)]
+fn experience_from_kills(
+    mut deaths: MessageReader<DeathEvent>,
+    players: Query<(), With<Player>>,
+    definition_refs: Query<&EntityDefinitionRef>,
+    definitions: Res<Assets<EntityDefinition>>,
+    mut experience: MessageWriter<GainExperience>,
+) {
+    for death in deaths.read() {
+        if !death.killer.is_some_and(|killer| players.contains(killer)) {
+            continue;
+        }
+        let Some(definition) = definition_refs.get(death.entity).ok()
+            .and_then(|reference| definitions.get(&reference.handle)) else {
+            continue;
+        };
+        if definition.experience > 0 {
+            experience.write(GainExperience {
+                amount: definition.experience,
+                source: ExperienceSource::Kill(definition.id.clone()),
+            });
+        }
+    }
+}
+
+/// Grants the experience of the recipe when the player finishes a crafting job.
+///
+/// # Parameters
+/// * `completed` - Finished crafting jobs.
+/// * `players` - Player entities.
+/// * `recipes` - Recipe definitions.
+/// * `experience` - Writer for the gained experience.
+#[coverage(off)]
+fn experience_from_crafting(
+    mut completed: MessageReader<CraftingCompleted>,
+    players: Query<(), With<Player>>,
+    recipes: Recipes,
+    mut experience: MessageWriter<GainExperience>,
+) {
+    for job in completed.read() {
+        if !players.contains(job.crafter) {
+            continue;
+        }
+        let Some(recipe) = recipes.get(&job.recipe) else {
+            continue;
+        };
+        if recipe.experience > 0 {
+            experience.write(GainExperience {
+                amount: recipe.experience,
+                source: ExperienceSource::Crafting(recipe.id.clone()),
+            });
+        }
+    }
+}
+
+/// Adds gained experience to the [`Character`] and raises its level along the curve of
+/// its progression definition.
+///
+/// # Parameters
+/// * `requests` - Gained experience.
+/// * `progressions` - Progression definitions.
+/// * `characters` - The character.
+/// * `level_ups` - Notification writer for reached levels.
+#[coverage(off)]
+fn apply_experience(
+    mut requests: MessageReader<GainExperience>,
+    progressions: Progressions,
+    mut characters: Query<&mut Character>,
+    mut level_ups: MessageWriter<LevelUp>,
+) {
+    let Ok(mut character) = characters.single_mut() else {
+        return;
+    };
+
+    for request in requests.read() {
+        let Some(definition) = progressions.get(&character.progression) else {
+            warn!("Progression '{}' is not loaded", character.progression);
+            continue;
+        };
+        let gained = character.add_experience(request.amount, definition);
+        debug!("Gained {} experience from {:?}", request.amount, request.source);
+        for level in (character.level - gained + 1)..=character.level {
+            info!("Reached level {}", level);
+            level_ups.write(LevelUp { level, skill_points: character.skill_points });
+        }
+    }
+}
+
+/// Spends skill points of the [`Character`] on requested skill nodes.
+///
+/// # Parameters
+/// * `requests` - Skills to unlock.
+/// * `progressions` - Progression and skill tree definitions.
+/// * `characters` - The character.
+/// * `unlocked` - Notification writer for unlocked skills.
+/// * `failed` - Notification writer for rejected requests.
+#[coverage(off)]
+fn handle_skill_unlocks(
+    mut requests: MessageReader<UnlockSkill>,
+    progressions: Progressions,
+    mut characters: Query<&mut Character>,
+    mut unlocked: MessageWriter<SkillUnlocked>,
+    mut failed: MessageWriter<SkillUnlockFailed>,
+) {
+    let Ok(mut character) = characters.single_mut() else {
+        return;
+    };
+
+    for request in requests.read() {
+        let Some(tree) = progressions.skill_tree_of(&character) else {
+            warn!("Skill tree of progression '{}' is not loaded", character.progression);
+            continue;
+        };
+        match tree.check_unlock(&character, &request.skill) {
+            Ok(node) => {
+                character.unlock(node);
+                debug!("Skill '{}' unlocked", node.id);
+                unlocked.write(SkillUnlocked { skill: node.id.clone() });
+            }
+            Err(error) => {
+                debug!("Skill '{}' can't be unlocked: {}", request.skill, error);
+                failed.write(SkillUnlockFailed { skill: request.skill.clone(), error });
+            }
+        }
+    }
+}
+
+/// Applies the attribute modifiers of the unlocked skills to the player whenever the
+/// [`Character`] changes or a player spawns, e.g. after loading a save.
+///
+/// # Parameters
+/// * `progressions` - Progression and skill tree definitions.
+/// * `characters` - The character.
+/// * `spawned` - Players spawned this frame.
+/// * `players` - Modifiers of the players.
+#[coverage(off)]
+fn sync_skill_modifiers(
+    progressions: Progressions,
+    characters: Query<Ref<Character>>,
+    spawned: Query<(), Added<Player>>,
+    mut players: Query<&mut AttributeModifiers, With<Player>>,
+) {
+    let Ok(character) = characters.single() else {
+        return;
+    };
+    if !character.is_changed() && spawned.is_empty() {
+        return;
+    }
+    let Some(tree) = progressions.skill_tree_of(&character) else {
+        return;
+    };
+
+    for mut modifiers in players.iter_mut() {
+        apply_skill_modifiers(&mut modifiers, tree, &character);
+    }
+}
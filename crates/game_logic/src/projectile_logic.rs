@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use bevy::ecs::system::SystemParam;
+use bevy::light::NotShadowCaster;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_rapier3d::prelude::{QueryFilter, ReadRapierContext};
+use game_models::building::Structure;
+use game_models::config::GlobalConfig;
+use game_models::dialogue::is_dialogue_inactive;
+use game_models::entities::player::Player;
+use game_models::factions::Factions;
+use game_models::health::{DamageEvent, Dead, Health};
+use game_models::items::equipment::{Equipment, EquipmentSlot};
+use game_models::items::inventory::Inventory;
+use game_models::items::item::Items;
+use game_models::projectiles::{spread_direction, FireWeapon, OutOfAmmo, PooledProjectile, Projectile, ProjectileDefinition, ProjectileFired, ProjectileHit, ProjectilePool, ProjectileRng, ProjectileSettings, Projectiles, RangedCooldown};
+use game_models::states::is_state_simulating;
+
+pub struct ProjectileLogicComponent;
+
+impl Plugin for ProjectileLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            fill_projectile_pool,
+                            player_fire_input.run_if(is_dialogue_inactive),
+                            handle_fire_requests,
+                            simulate_projectiles
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Maximum number of projectile entities added to the pool per frame.
+const POOL_SPAWNS_PER_FRAME: usize = 64;
+/// Distance the aim ray of the player reaches.
+const AIM_DISTANCE: f32 = 100.0;
+
+/// Writers for the results of [`handle_fire_requests`].
+#[derive(SystemParam)]
+struct FireNotifications<'w> {
+    fired: MessageWriter<'w, ProjectileFired>,
+    out_of_ammo: MessageWriter<'w, OutOfAmmo>,
+}
+
+/// Spawns hidden projectile entities until the pool has its initial size and enough
+/// free entities. The entities share one unit cube mesh which is scaled per projectile.
+///
+/// # Parameters
+/// * `commands` - Spawns the pooled entities.
+/// * `mesh` - Shared mesh, created on first use.
+/// * `settings` - Pool sizes.
+/// * `pool` - Projectile pool.
+/// * `meshes` - Mesh assets.
+#[coverage(off)]
+fn fill_projectile_pool(
+    mut commands: Commands,
+    mut mesh: Local<Option<Handle<Mesh>>>,
+    settings: Res<ProjectileSettings>,
+    mut pool: ResMut<ProjectilePool>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let missing = pool.missing(&settings).min(POOL_SPAWNS_PER_FRAME);
+    if missing == 0 {
+        return;
+    }
+    let mesh = mesh.get_or_insert_with(|| meshes.add(Mesh::from(Cuboid::new(1.0, 1.0, 1.0)))).clone();
+    for _ in 0..missing {
+        let entity = commands.spawn((
+            Name::new("Projectile"),
+            PooledProjectile,
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d::<StandardMaterial>::default(),
+            Transform::default(),
+            Visibility::Hidden,
+            NotShadowCaster,
+        )).id();
+        pool.add(entity);
+    }
+    debug!("Projectile pool grew to {} entities", pool.total());
+}
+
+/// Fires the ranged weapon of the player at the point under the cursor while the fire
+/// key is held. The fire rate is limited by the weapon cooldown.
+///
+/// # Parameters
+/// * `keyboard` - Keyboard input.
+/// * `global_config` - Key bindings.
+/// * `windows` - Cursor position.
+/// * `cameras` - Cursor ray.
+/// * `players` - The shooting player.
+/// * `rapier` - Aim ray cast.
+/// * `fire` - Writer for shots.
+#[coverage(off)]
+fn player_fire_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    global_config: Res<GlobalConfig>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    players: Query<Entity, (With<Player>, Without<Dead>)>,
+    rapier: ReadRapierContext,
+    mut fire: MessageWriter<FireWeapon>,
+) {
+    if !keyboard.pressed(global_config.input_config.get_fire_key()) {
+        return;
+    }
+    let Ok(player) = players.single() else {
+        return;
+    };
+
+    let ray = windows
+        .single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+        .and_then(|cursor| cameras
+            .iter()
+            .find_map(|(camera, transform)| camera.viewport_to_world(transform, cursor).ok()));
+    let target = ray.map(|ray| {
+        let filter = QueryFilter::default().exclude_sensors().exclude_collider(player);
+        let distance = rapier
+            .single()
+            .ok()
+            .and_then(|context| context.cast_ray(ray.origin, *ray.direction, AIM_DISTANCE, true, filter))
+            .map_or(AIM_DISTANCE, |(_, distance)| distance);
+        ray.get_point(distance)
+    });
+
+    fire.write(FireWeapon { entity: player, target });
+}
+
+/// Fires the equipped ranged weapon of the requesting entities. Consumes the ammo from
+/// the inventory, scatters the projectiles of the shot within the spread cone and takes
+/// the entities from the pool. Shots are dropped while the pool is empty.
+///
+/// # Parameters
+/// * `commands` - Activates the pooled entities.
+/// * `requests` - Shots to fire.
+/// * `time` - Elapsed time for the cooldowns.
+/// * `settings` - Gravity and muzzle offset.
+/// * `items` - Item definitions for the weapon data.
+/// * `projectiles` - Projectile definitions.
+/// * `pool` - Projectile pool.
+/// * `rng` - Session-seeded random source for the spread.
+/// * `materials` - Material per projectile definition, created on first use.
+/// * `reloads` - Asset events of projectile definitions, reloads reset the materials.
+/// * `material_assets` - Material assets.
+/// * `shooters` - Equipment, ammo and cooldown of the shooters.
+/// * `notifications` - Writers for shots and missing ammo.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn handle_fire_requests(
+    mut commands: Commands,
+    mut requests: MessageReader<FireWeapon>,
+    time: Res<Time>,
+    settings: Res<ProjectileSettings>,
+    items: Items,
+    projectiles: Projectiles,
+    mut pool: ResMut<ProjectilePool>,
+    mut rng: ResMut<ProjectileRng>,
+    mut materials: Local<HashMap<String, Handle<StandardMaterial>>>,
+    mut reloads: MessageReader<AssetEvent<ProjectileDefinition>>,
+    mut material_assets: ResMut<Assets<StandardMaterial>>,
+    mut shooters: Query<(&GlobalTransform, &Equipment, Option<&mut Inventory>, Option<&mut RangedCooldown>), Without<Dead>>,
+    mut notifications: FireNotifications,
+) {
+    if reloads.read().any(|event| matches!(event, AssetEvent::Modified { .. } | AssetEvent::Removed { .. })) {
+        materials.clear();
+    }
+
+    let now = time.elapsed_secs();
+    for request in requests.read() {
+        let Ok((transform, equipment, inventory, cooldown)) = shooters.get_mut(request.entity) else {
+            continue;
+        };
+        let Some(ranged) = equipment
+            .get(EquipmentSlot::Weapon)
+            .and_then(|weapon| items.get(weapon))
+            .and_then(|item| item.equipment.as_ref())
+            .and_then(|data| data.ranged.as_ref())
+        else {
+            continue;
+        };
+        if cooldown.as_ref().is_some_and(|cooldown| now < cooldown.ready_at) {
+            continue;
+        }
+        let Some(definition) = projectiles.get(&ranged.projectile) else {
+            debug!("Projectile '{}' is not loaded", ranged.projectile);
+            continue;
+        };
+
+        // Dry fire waits for the cooldown as well, so a held key reports missing ammo once per shot.
+        match cooldown {
+            Some(mut cooldown) => cooldown.ready_at = now + ranged.cooldown,
+            None => {
+                commands.entity(request.entity).insert(RangedCooldown { ready_at: now + ranged.cooldown });
+            }
+        }
+
+        // Entities are taken before the ammo, so an exhausted pool never eats a shot.
+        let pooled: Vec<Entity> = (0..ranged.projectiles_per_shot).map_while(|_| pool.acquire()).collect();
+        if pooled.is_empty() {
+            warn!("Projectile pool is exhausted, shot of {:?} dropped", request.entity);
+            continue;
+        }
+
+        if let Some(ammo) = &ranged.ammo {
+            let consumed = inventory.is_some_and(|mut inventory| inventory.remove(ammo, ranged.ammo_per_shot).is_ok());
+            if !consumed {
+                for entity in pooled {
+                    pool.release(entity);
+                }
+                notifications.out_of_ammo.write(OutOfAmmo { entity: request.entity, ammo: ammo.clone() });
+                continue;
+            }
+        }
+
+        let muzzle = transform.transform_point(settings.muzzle_offset);
+        let aim = request.target
+            .map(|target| (target - muzzle).normalize_or_zero())
+            .filter(|direction| *direction != Vec3::ZERO)
+            .unwrap_or_else(|| transform.forward().as_vec3());
+        let material = materials
+            .entry(definition.id.clone())
+            .or_insert_with(|| material_assets.add(StandardMaterial {
+                base_color: Color::srgb(definition.color[0], definition.color[1], definition.color[2]),
+                ..default()
+            }))
+            .clone();
+
+        for entity in pooled {
+            let direction = spread_direction(aim, ranged.spread, &mut rng.0);
+            let velocity = direction * definition.speed * ranged.speed_multiplier;
+            commands.entity(entity).insert((
+                Projectile::new(definition, Some(request.entity), velocity, settings.gravity),
+                Transform::from_translation(muzzle)
+                    .looking_to(direction, Vec3::Y)
+                    .with_scale(Vec3::new(definition.thickness, definition.thickness, definition.length)),
+                MeshMaterial3d(material.clone()),
+                Visibility::Visible,
+            ));
+        }
+        notifications.fired.write(ProjectileFired { shooter: request.entity, projectile: definition.id.clone() });
+    }
+}
+
+/// Moves the projectiles in flight and sweeps every step with ray casts. Damageable
+/// targets take the damage of the projectile, which passes through them while it has
+/// penetration left, structures always stop it. Friends of the shooter stop the
+/// projectile without damage unless friendly fire is enabled. Other colliders stop the
+/// projectile, which stays stuck for [`ProjectileSettings::stuck_time`]. Expired
+/// projectiles return to the pool.
+///
+/// # Parameters
+/// * `commands` - Returns the entities to the pool.
+/// * `time` - Global time.
+/// * `settings` - Stuck time.
+/// * `rapier` - Sweep ray casts.
+/// * `pool` - Projectile pool.
+/// * `projectiles` - Projectiles in flight.
+/// * `targets` - Damageable entities and whether they are structures.
+/// * `factions` - Friendly fire rules.
+/// * `damage` - Writer for the damage of hits.
+/// * `hits` - Notification writer for hits.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn simulate_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<ProjectileSettings>,
+    rapier: ReadRapierContext,
+    mut pool: ResMut<ProjectilePool>,
+    mut projectiles: Query<(Entity, &mut Projectile, &mut Transform)>,
+    targets: Query<(Has<Dead>, Has<Structure>), With<Health>>,
+    factions: Factions,
+    mut damage: MessageWriter<DamageEvent>,
+    mut hits: MessageWriter<ProjectileHit>,
+) {
+    let context = rapier.single().ok();
+    let delta = time.delta_secs();
+
+    for (entity, mut projectile, mut transform) in projectiles.iter_mut() {
+        let start = transform.translation;
+        let end = projectile.integrate(start, delta);
+        if projectile.is_expired() {
+            commands.entity(entity).remove::<Projectile>().insert(Visibility::Hidden);
+            pool.release(entity);
+            continue;
+        }
+        if projectile.stuck {
+            continue;
+        }
+
+        let Some((direction, length)) = Dir3::new_and_length(end - start).ok() else {
+            continue;
+        };
+        let mut origin = start;
+        let mut remaining = length;
+        let mut stop = None;
+
+        if let Some(context) = &context {
+            loop {
+                let cast = {
+                    let shooter = projectile.shooter;
+                    let hit = &projectile.hit;
+                    let predicate = |candidate: Entity| Some(candidate) != shooter
+                        && !hit.contains(&candidate)
+                        && !targets.get(candidate).is_ok_and(|(dead, _)| dead);
+                    let filter = QueryFilter::default().exclude_sensors().predicate(&predicate);
+                    context.cast_ray(origin, *direction, remaining, true, filter)
+                };
+                let Some((target, distance)) = cast else {
+                    break;
+                };
+                let point = origin + *direction * distance;
+
+                if targets.contains(target) && projectile.shooter.is_some_and(|shooter| !factions.can_damage(shooter, target)) {
+                    stop = Some((point, false));
+                } else if let Ok((_, structure)) = targets.get(target) {
+                    let (amount, passes) = projectile.penetrate(target);
+                    damage.write(DamageEvent {
+                        target,
+                        source: projectile.shooter,
+                        damage_type: projectile.damage_type,
+                        amount,
+                    });
+                    hits.write(ProjectileHit {
+                        projectile: projectile.definition.clone(),
+                        shooter: projectile.shooter,
+                        target,
+                        damage: amount,
+                    });
+                    if passes && !structure {
+                        origin = point;
+                        remaining -= distance;
+                        continue;
+                    }
+                    stop = Some((point, false));
+                } else {
+                    stop = Some((point, true));
+                }
+                break;
+            }
+        }
+
+        match stop {
+            Some((point, true)) => {
+                transform.translation = point;
+                projectile.stick(settings.stuck_time);
+            }
+            Some((_, false)) => {
+                commands.entity(entity).remove::<Projectile>().insert(Visibility::Hidden);
+                pool.release(entity);
+            }
+            None => {
+                transform.translation = end;
+                transform.look_to(direction, Vec3::Y);
+            }
+        }
+    }
+}
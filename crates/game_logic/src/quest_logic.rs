@@ -0,0 +1,381 @@
+use std::collections::HashSet;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use game_models::definitions::DefinitionLibrary;
+use game_models::dialogue::{DialogueStarted, StoryFlags};
+use game_models::entities::definition::EntityDefinitionRef;
+use game_models::entities::player::Player;
+use game_models::factions::{ChangeReputation, ReputationSource};
+use game_models::health::DeathEvent;
+use game_models::items::inventory::Inventory;
+use game_models::items::item::Items;
+use game_models::localization::Localization;
+use game_models::progression::{ExperienceSource, GainExperience};
+use game_models::quests::{AreaReached, JournalEntry, JournalObjective, ObjectiveKind, QuestArea, QuestCompleted, QuestDefinition, QuestEvent, QuestEventKind, QuestJournal, QuestLog, QuestObjectiveProgressed, QuestProgress, QuestReward, QuestStageCompleted, QuestStarted, QuestStatus, StageAdvance, StartQuest};
+use game_models::states::is_state_simulating;
+use game_models::time_of_day::{DayPhase, DayPhaseChanged};
+
+pub struct QuestLogicComponent;
+
+impl Plugin for QuestLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            handle_start_quest,
+                            detect_quest_areas,
+                            translate_quest_events,
+                            apply_quest_events,
+                            update_quest_journal
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Writers for the progress notifications, experience and reputation rewards of
+/// [`apply_quest_events`].
+#[derive(SystemParam)]
+struct QuestNotifications<'w> {
+    progressed: MessageWriter<'w, QuestObjectiveProgressed>,
+    stage_completed: MessageWriter<'w, QuestStageCompleted>,
+    completed: MessageWriter<'w, QuestCompleted>,
+    experience: MessageWriter<'w, GainExperience>,
+    reputation: MessageWriter<'w, ChangeReputation>,
+}
+
+/// Items of the collect objectives in the active stages of `log`.
+fn collected_items(log: &QuestLog, library: &DefinitionLibrary<QuestDefinition>, definitions: &Assets<QuestDefinition>) -> HashSet<String> {
+    log.quests
+        .iter()
+        .filter(|progress| progress.status == QuestStatus::Active)
+        .filter_map(|progress| progress.current_stage(library.get(&progress.quest, definitions)?))
+        .flat_map(|stage| stage.objectives.iter())
+        .filter_map(|objective| match &objective.kind {
+            ObjectiveKind::Collect { item, .. } => Some(item.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Sets the progress of the collect objectives of the active stage to the amount
+/// already carried, so items collected before the stage count too.
+fn sync_carried_items(progress: &mut QuestProgress, definition: &QuestDefinition, inventory: Option<&Inventory>) {
+    let Some(inventory) = inventory else {
+        return;
+    };
+    let Some(stage) = progress.current_stage(definition) else {
+        return;
+    };
+    let carried: Vec<QuestEventKind> = stage.objectives
+        .iter()
+        .filter_map(|objective| match &objective.kind {
+            ObjectiveKind::Collect { item, .. } => Some(QuestEventKind::Carried { item: item.clone(), count: inventory.count(item) }),
+            _ => None,
+        })
+        .collect();
+    for event in carried.iter() {
+        progress.record(definition, event);
+    }
+}
+
+/// Starts requested quests in the [`QuestLog`] of the requesting entity.
+///
+/// # Parameters
+/// * `requests` - Start requests, e.g. from dialogue effects.
+/// * `started` - Notification writer for started quests.
+/// * `library` - Quest definitions by id.
+/// * `definitions` - Quest definition assets.
+/// * `logs` - Quest logs.
+/// * `inventories` - Carried items for collect objectives.
+#[coverage(off)]
+fn handle_start_quest(
+    mut requests: MessageReader<StartQuest>,
+    mut started: MessageWriter<QuestStarted>,
+    library: Res<DefinitionLibrary<QuestDefinition>>,
+    definitions: Res<Assets<QuestDefinition>>,
+    mut logs: Query<&mut QuestLog>,
+    inventories: Query<&Inventory>,
+) {
+    for request in requests.read() {
+        let Some(definition) = library.get(&request.quest, &definitions) else {
+            warn!("Quest definition '{}' is not loaded", request.quest);
+            continue;
+        };
+        let Ok(mut log) = logs.get_mut(request.entity) else {
+            continue;
+        };
+        if !log.start(definition) {
+            continue;
+        }
+        if let Some(progress) = log.quests.last_mut() {
+            sync_carried_items(progress, definition, inventories.get(request.entity).ok());
+        }
+
+        info!("Quest '{}' started", request.quest);
+        started.write(QuestStarted {
+            entity: request.entity,
+            quest: request.quest.clone(),
+        });
+    }
+}
+
+/// Writes [`AreaReached`] when an entity with a [`QuestLog`] enters a [`QuestArea`].
+///
+/// # Parameters
+/// * `inside` - Pairs of quest log owner and area which overlapped last frame.
+/// * `holders` - Entities with a quest log.
+/// * `areas` - Quest areas.
+/// * `reached` - Writer for entered areas.
+#[coverage(off)]
+fn detect_quest_areas(
+    mut inside: Local<HashSet<(Entity, Entity)>>,
+    holders: Query<(Entity, &GlobalTransform), With<QuestLog>>,
+    areas: Query<(Entity, &QuestArea, &GlobalTransform)>,
+    mut reached: MessageWriter<AreaReached>,
+) {
+    let mut now = HashSet::new();
+    for (holder, holder_transform) in holders.iter() {
+        for (area_entity, area, area_transform) in areas.iter() {
+            if holder_transform.translation().distance(area_transform.translation()) > area.radius {
+                continue;
+            }
+            now.insert((holder, area_entity));
+            if !inside.contains(&(holder, area_entity)) {
+                reached.write(AreaReached { entity: holder, area: area.id.clone() });
+            }
+        }
+    }
+    *inside = now;
+}
+
+/// Translates gameplay messages into [`QuestEvent`]s: kills, changes of carried items,
+/// entered areas, started dialogues and the dawn.
+///
+/// # Parameters
+/// * `deaths` - Deaths, counted for the killer.
+/// * `areas` - Entered quest areas.
+/// * `dialogues` - Started dialogues.
+/// * `phases` - Day phase changes.
+/// * `definition_refs` - Entity definition ids of killed and talked to entities.
+/// * `library` - Quest definitions by id.
+/// * `definitions` - Quest definition assets.
+/// * `carriers` - Quest holders whose inventory changed, whatever changed it.
+/// * `events` - Writer for quest events.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn translate_quest_events(
+    mut deaths: MessageReader<DeathEvent>,
+    mut areas: MessageReader<AreaReached>,
+    mut dialogues: MessageReader<DialogueStarted>,
+    mut phases: MessageReader<DayPhaseChanged>,
+    definition_refs: Query<&EntityDefinitionRef>,
+    library: Res<DefinitionLibrary<QuestDefinition>>,
+    definitions: Res<Assets<QuestDefinition>>,
+    carriers: Query<(Entity, &Inventory, &QuestLog), Changed<Inventory>>,
+    mut events: MessageWriter<QuestEvent>,
+) {
+    for death in deaths.read() {
+        let (Some(killer), Ok(definition)) = (death.killer, definition_refs.get(death.entity)) else {
+            continue;
+        };
+        events.write(QuestEvent {
+            entity: Some(killer),
+            kind: QuestEventKind::Killed { entity: definition.id.clone() },
+        });
+    }
+
+    for (entity, inventory, log) in carriers.iter() {
+        for item in collected_items(log, &library, &definitions) {
+            let count = inventory.count(&item);
+            events.write(QuestEvent {
+                entity: Some(entity),
+                kind: QuestEventKind::Carried { item, count },
+            });
+        }
+    }
+
+    for area in areas.read() {
+        events.write(QuestEvent {
+            entity: Some(area.entity),
+            kind: QuestEventKind::AreaReached { area: area.area.clone() },
+        });
+    }
+
+    for dialogue in dialogues.read() {
+        let Ok(definition) = definition_refs.get(dialogue.owner) else {
+            continue;
+        };
+        events.write(QuestEvent {
+            entity: Some(dialogue.listener),
+            kind: QuestEventKind::TalkedTo { entity: definition.id.clone() },
+        });
+    }
+
+    for phase in phases.read() {
+        if phase.to == DayPhase::Dawn {
+            events.write(QuestEvent { entity: None, kind: QuestEventKind::Dawn });
+        }
+    }
+}
+
+/// Applies [`QuestEvent`]s to the active quests, completes stages whose objectives
+/// are done and grants the rewards of completed quests.
+///
+/// # Parameters
+/// * `events` - Quest events.
+/// * `notifications` - Writers for progress, stage and quest completion, experience and reputation rewards.
+/// * `library` - Quest definitions by id.
+/// * `definitions` - Quest definition assets.
+/// * `items` - Item definitions for stack sizes of rewards.
+/// * `flags` - Story flags set by rewards.
+/// * `logs` - Quest logs.
+/// * `inventories` - Inventories receiving item rewards.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn apply_quest_events(
+    mut events: MessageReader<QuestEvent>,
+    mut notifications: QuestNotifications,
+    library: Res<DefinitionLibrary<QuestDefinition>>,
+    definitions: Res<Assets<QuestDefinition>>,
+    items: Items,
+    mut flags: ResMut<StoryFlags>,
+    mut logs: Query<(Entity, &mut QuestLog)>,
+    mut inventories: Query<&mut Inventory>,
+) {
+    for event in events.read() {
+        for (entity, mut log) in logs.iter_mut() {
+            if event.entity.is_some_and(|target| target != entity) {
+                continue;
+            }
+
+            for progress in log.quests.iter_mut().filter(|progress| progress.status == QuestStatus::Active) {
+                let Some(definition) = library.get(&progress.quest, &definitions) else {
+                    continue;
+                };
+
+                for objective in progress.record(definition, &event.kind) {
+                    let required = progress
+                        .current_stage(definition)
+                        .and_then(|stage| stage.objectives.get(objective))
+                        .map_or(1, |objective| objective.kind.required());
+                    notifications.progressed.write(QuestObjectiveProgressed {
+                        entity,
+                        quest: progress.quest.clone(),
+                        stage: progress.stage,
+                        objective,
+                        progress: progress.objectives[objective],
+                        required,
+                    });
+                }
+
+                loop {
+                    match progress.advance(definition) {
+                        StageAdvance::Open => break,
+                        StageAdvance::NextStage(stage) => {
+                            debug!("Quest '{}' stage {} completed", progress.quest, stage);
+                            notifications.stage_completed.write(QuestStageCompleted {
+                                entity,
+                                quest: progress.quest.clone(),
+                                stage,
+                            });
+                            sync_carried_items(progress, definition, inventories.get(entity).ok());
+                        }
+                        StageAdvance::Completed => {
+                            info!("Quest '{}' completed", progress.quest);
+                            notifications.stage_completed.write(QuestStageCompleted {
+                                entity,
+                                quest: progress.quest.clone(),
+                                stage: progress.stage - 1,
+                            });
+                            for reward in definition.rewards.iter() {
+                                match reward {
+                                    QuestReward::Item { item, count } => {
+                                        let Ok(mut inventory) = inventories.get_mut(entity) else {
+                                            continue;
+                                        };
+                                        let remaining = inventory.add(item, *count, |id| items.stack_size(id));
+                                        if remaining > 0 {
+                                            warn!("Quest reward {} '{}' does not fit into the inventory", remaining, item);
+                                        }
+                                    }
+                                    QuestReward::Flag { flag } => flags.set(flag.clone()),
+                                    QuestReward::Experience { amount } => {
+                                        notifications.experience.write(GainExperience {
+                                            amount: *amount,
+                                            source: ExperienceSource::Quest(progress.quest.clone()),
+                                        });
+                                    }
+                                    QuestReward::Reputation { faction, amount } => {
+                                        notifications.reputation.write(ChangeReputation {
+                                            faction: faction.clone(),
+                                            amount: *amount,
+                                            source: ReputationSource::Quest(progress.quest.clone()),
+                                        });
+                                    }
+                                }
+                            }
+                            notifications.completed.write(QuestCompleted {
+                                entity,
+                                quest: progress.quest.clone(),
+                            });
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Rebuilds the localized [`QuestJournal`] whenever the quest log of the player or the
+/// quest definitions change.
+///
+/// # Parameters
+/// * `players` - Quest log of the player.
+/// * `library` - Quest definitions by id.
+/// * `definitions` - Quest definition assets.
+/// * `localization` - Texts of the active language.
+/// * `journal` - Journal data for the UI.
+#[coverage(off)]
+fn update_quest_journal(
+    players: Query<Ref<QuestLog>, With<Player>>,
+    library: Res<DefinitionLibrary<QuestDefinition>>,
+    definitions: Res<Assets<QuestDefinition>>,
+    localization: Localization,
+    mut journal: ResMut<QuestJournal>,
+) {
+    let Ok(log) = players.single() else {
+        return;
+    };
+    if !log.is_changed() && !definitions.is_changed() {
+        return;
+    }
+
+    let mut entries: Vec<JournalEntry> = log.quests
+        .iter()
+        .filter_map(|progress| {
+            let definition = library.get(&progress.quest, &definitions)?;
+            let stage = progress.current_stage(definition);
+            Some(JournalEntry {
+                quest: progress.quest.clone(),
+                name: localization.text(&definition.name),
+                description: localization.text(&definition.description),
+                completed: progress.status == QuestStatus::Completed,
+                stage: stage.map(|stage| localization.text(&stage.description)).unwrap_or_default(),
+                objectives: stage
+                    .map(|stage| stage.objectives
+                        .iter()
+                        .zip(progress.objectives.iter())
+                        .map(|(objective, progress)| JournalObjective {
+                            text: localization.text(&objective.description),
+                            progress: *progress,
+                            required: objective.kind.required(),
+                        })
+                        .collect())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect();
+    entries.sort_by_key(|entry| entry.completed);
+    journal.entries = entries;
+}
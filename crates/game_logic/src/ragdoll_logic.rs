@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use bevy::ecs::system::SystemParam;
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use bevy::transform::TransformSystems;
+use bevy_rapier3d::prelude::{ColliderDisabled, ColliderMassProperties, Damping, ImpulseJoint, LockedAxes, PhysicsSet, RigidBody, Velocity};
+use game_models::animation::CharacterAnimation;
+use game_models::entities::definition::RagdollReference;
+use game_models::health::{DeathBehaviour, DeathEvent};
+use game_models::ragdoll::{blow_velocity, ActiveRagdolls, Ragdoll, RagdollBody, RagdollLink, RagdollSettings, Ragdolls};
+use game_models::states::is_state_simulating;
+
+pub struct RagdollLogicComponent;
+
+impl Plugin for RagdollLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            start_ragdolls,
+                            cleanup_ragdolls
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+        app.add_systems(PostUpdate,
+                        drive_ragdoll_bones
+                            .after(PhysicsSet::Writeback)
+                            .before(TransformSystems::Propagate)
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Named nodes below the scene of a character.
+#[derive(SystemParam)]
+struct Skeletons<'w, 's> {
+    children: Query<'w, 's, &'static Children>,
+    nodes: Query<'w, 's, (&'static Name, &'static GlobalTransform)>,
+}
+
+impl Skeletons<'_, '_> {
+
+    /// Bone `name` below `root` with its world transform.
+    fn find(&self, root: Entity, name: &str) -> Option<(Entity, &GlobalTransform)> {
+        self.children.iter_descendants(root).find_map(|node| {
+            self.nodes.get(node).ok()
+                .filter(|(node_name, _)| node_name.as_str() == name)
+                .map(|(_, transform)| (node, transform))
+        })
+    }
+}
+
+/// Switches characters with [`DeathBehaviour::Ragdoll`] from animation to physics when
+/// they die. Characters with a ragdoll definition get one jointed body per bone, all
+/// others tip over as a whole. Fixed bodies, e.g. structures, don't ragdoll. The oldest
+/// ragdolls freeze when the cap of [`RagdollSettings`] is exceeded.
+///
+/// # Parameters
+/// * `commands` - Spawns the bodies and switches the characters to physics.
+/// * `deaths` - Died entities.
+/// * `settings` - Cap, killing blow and damping.
+/// * `ragdolls` - Ragdoll definitions.
+/// * `active` - Ragdolls in the order they started.
+/// * `characters` - Death behaviour, ragdoll, velocity and body of the died entities.
+/// * `killers` - Positions of the killers.
+/// * `skeletons` - Bones of the character scenes.
+/// * `animation_players` - Stopped when the ragdoll starts.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn start_ragdolls(
+    mut commands: Commands,
+    mut deaths: MessageReader<DeathEvent>,
+    settings: Res<RagdollSettings>,
+    ragdolls: Ragdolls,
+    mut active: ResMut<ActiveRagdolls>,
+    characters: Query<(&DeathBehaviour, &GlobalTransform, &RigidBody, Option<&RagdollReference>, Option<&CharacterAnimation>, Option<&Velocity>), Without<Ragdoll>>,
+    killers: Query<&GlobalTransform>,
+    skeletons: Skeletons,
+    mut animation_players: Query<&mut AnimationPlayer>,
+) {
+    for death in deaths.read() {
+        let Ok((behaviour, transform, body, reference, animation, velocity)) = characters.get(death.entity) else {
+            continue;
+        };
+        if !matches!(behaviour, DeathBehaviour::Ragdoll(_)) || *body == RigidBody::Fixed || settings.max_ragdolls == 0 {
+            continue;
+        }
+
+        let inherited = velocity.map(|velocity| velocity.linvel)
+            .or(animation.map(|animation| animation.velocity))
+            .unwrap_or(Vec3::ZERO);
+        let killer = death.killer.and_then(|killer| killers.get(killer).ok()).map(GlobalTransform::translation);
+        let linvel = blow_velocity(transform.translation(), killer, inherited, settings.blow_speed);
+        let damping = Damping { linear_damping: settings.linear_damping, angular_damping: settings.angular_damping };
+
+        let mut links = Vec::new();
+        if let Some(definition) = reference.and_then(|reference| ragdolls.get(&reference.0)) {
+            let mut bodies: HashMap<&str, (Entity, Transform)> = HashMap::new();
+            for bone in definition.bones.iter() {
+                let Some((bone_entity, global)) = skeletons.find(death.entity, &bone.bone) else {
+                    warn!("Bone '{}' of ragdoll '{}' is missing", bone.bone, definition.id);
+                    continue;
+                };
+                let (scale, rotation, translation) = global.to_scale_rotation_translation();
+                let body_transform = Transform::from_translation(translation).with_rotation(rotation);
+
+                let mut body = commands.spawn((
+                    Name::new(format!("Ragdoll: {}", bone.bone)),
+                    RagdollBody { character: death.entity },
+                    body_transform,
+                    RigidBody::Dynamic,
+                    bone.collider(),
+                    ColliderMassProperties::Density(bone.density),
+                    Velocity::linear(linvel),
+                    damping,
+                ));
+                if let Some((parent_body, parent_transform)) = bone.parent.as_deref().and_then(|parent| bodies.get(parent)) {
+                    body.insert(ImpulseJoint::new(*parent_body, bone.joint(parent_transform, &body_transform)));
+                }
+                let body = body.id();
+                bodies.insert(bone.bone.as_str(), (body, body_transform));
+                links.push(RagdollLink { bone: bone_entity, body, scale });
+            }
+        }
+
+        if let Some(player) = animation.and_then(|animation| animation.player)
+            && let Ok(mut animation_player) = animation_players.get_mut(player) {
+            animation_player.stop_all();
+        }
+
+        if links.is_empty() {
+            let fall = Vec3::new(linvel.x, 0.0, linvel.z).try_normalize().unwrap_or(transform.back().as_vec3());
+            commands.entity(death.entity)
+                .remove::<LockedAxes>()
+                .insert((
+                    Ragdoll::default(),
+                    RigidBody::Dynamic,
+                    Velocity { linvel, angvel: Vec3::Y.cross(fall) * settings.topple_speed },
+                    damping,
+                ));
+        } else {
+            commands.entity(death.entity).insert((Ragdoll { links, frozen: false }, RigidBody::Fixed, ColliderDisabled));
+        }
+        debug!("{:?} ragdolls", death.entity);
+
+        for frozen in active.activate(death.entity, settings.max_ragdolls) {
+            if let Ok(mut entity_commands) = commands.get_entity(frozen) {
+                entity_commands
+                    .insert(RigidBody::Fixed)
+                    .entry::<Ragdoll>()
+                    .and_modify(|mut ragdoll| ragdoll.frozen = true);
+            }
+        }
+    }
+}
+
+/// Despawns the bodies of frozen ragdolls and of despawned characters.
+///
+/// # Parameters
+/// * `commands` - Despawns the bodies.
+/// * `active` - Ragdolls in the order they started.
+/// * `bodies` - All ragdoll bodies.
+/// * `ragdolls` - Ragdolls of the characters.
+#[coverage(off)]
+fn cleanup_ragdolls(
+    mut commands: Commands,
+    mut active: ResMut<ActiveRagdolls>,
+    bodies: Query<(Entity, &RagdollBody)>,
+    ragdolls: Query<&Ragdoll>,
+) {
+    let simulated = |entity: Entity| ragdolls.get(entity).is_ok_and(|ragdoll| !ragdoll.frozen);
+    active.retain(simulated);
+    for (entity, body) in bodies.iter() {
+        if !simulated(body.character) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Moves the bones of ragdolls to their bodies. Runs after the physics wrote the body
+/// transforms and before the transforms are propagated.
+///
+/// # Parameters
+/// * `ragdolls` - Ragdolling characters, frozen ones keep their pose.
+/// * `bodies` - Transforms of the ragdoll bodies.
+/// * `bones` - Local transforms of the scene nodes.
+/// * `parents` - Hierarchy of the scenes.
+/// * `globals` - World transforms of the characters.
+#[coverage(off)]
+fn drive_ragdoll_bones(
+    ragdolls: Query<(Entity, &Ragdoll)>,
+    bodies: Query<&Transform, With<RagdollBody>>,
+    mut bones: Query<&mut Transform, Without<RagdollBody>>,
+    parents: Query<&ChildOf>,
+    globals: Query<&GlobalTransform>,
+) {
+    for (character, ragdoll) in ragdolls.iter().filter(|(_, ragdoll)| !ragdoll.frozen) {
+        let mut worlds: HashMap<Entity, Affine3A> = HashMap::new();
+        for link in ragdoll.links.iter() {
+            let (Ok(body), Ok(parent)) = (bodies.get(link.body), parents.get(link.bone)) else {
+                continue;
+            };
+            let Some(parent_world) = resolve_world(parent.parent(), character, &worlds, &parents, &bones, &globals) else {
+                continue;
+            };
+            let world = Affine3A::from_scale_rotation_translation(link.scale, body.rotation, body.translation);
+            if let Ok(mut transform) = bones.get_mut(link.bone) {
+                *transform = Transform::from_matrix(Mat4::from(parent_world.inverse() * world));
+            }
+            worlds.insert(link.bone, world);
+        }
+    }
+}
+
+/// World transform of the scene node `entity` in this frame. Bones already moved by the
+/// ragdoll are taken from `worlds`, all others are composed from their local transforms
+/// up to the `character`.
+fn resolve_world(
+    entity: Entity,
+    character: Entity,
+    worlds: &HashMap<Entity, Affine3A>,
+    parents: &Query<&ChildOf>,
+    transforms: &Query<&mut Transform, Without<RagdollBody>>,
+    globals: &Query<&GlobalTransform>,
+) -> Option<Affine3A> {
+    if let Some(world) = worlds.get(&entity) {
+        return Some(*world);
+    }
+    if entity == character {
+        return globals.get(entity).ok().map(GlobalTransform::affine);
+    }
+    let local = transforms.get(entity).ok()?.compute_affine();
+    let parent = parents.get(entity).ok()?.parent();
+    Some(resolve_world(parent, character, worlds, parents, transforms, globals)? * local)
+}
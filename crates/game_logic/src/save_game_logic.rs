@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+use game_models::config::GlobalConfig;
+use game_models::dialogue::StoryFlags;
+use game_models::entities::character::Character;
+use game_models::entities::player::Player;
+use game_models::quests::QuestLog;
+use game_models::save_game::{GameSave, LoadGame, SaveGame, SaveGameSettings};
+use game_models::states::is_state_simulating;
+use game_models::weather::{Weather, WeatherSettings};
+
+pub struct SaveGameLogicComponent;
+
+impl Plugin for SaveGameLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            quick_save_input,
+                            save_game,
+                            load_game
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Requests a quick save or a quick load when their keys are pressed.
+///
+/// # Parameters
+/// * `keyboard` - Keyboard input.
+/// * `global_config` - Key bindings.
+/// * `saves` - Writer for save requests.
+/// * `loads` - Writer for load requests.
+#[coverage(off)]
+fn quick_save_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    global_config: Res<GlobalConfig>,
+    mut saves: MessageWriter<SaveGame>,
+    mut loads: MessageWriter<LoadGame>,
+) {
+    let input = &global_config.input_config;
+    if keyboard.just_pressed(input.get_quick_save_key()) {
+        saves.write(SaveGame);
+    } else if keyboard.just_pressed(input.get_quick_load_key()) {
+        loads.write(LoadGame);
+    }
+}
+
+/// Writes the character, the quest log of the player, the story flags and the weather
+/// to the quick save.
+///
+/// # Parameters
+/// * `requests` - Save requests, several in one frame save once.
+/// * `settings` - Path of the quick save.
+/// * `characters` - Progression and reputation of the player.
+/// * `players` - Quest log of the player.
+/// * `flags` - Story flags.
+/// * `weather` - Current weather and forecast.
+#[coverage(off)]
+fn save_game(
+    mut requests: MessageReader<SaveGame>,
+    settings: Res<SaveGameSettings>,
+    characters: Query<&Character>,
+    players: Query<&QuestLog, With<Player>>,
+    flags: Res<StoryFlags>,
+    weather: Res<Weather>,
+) {
+    if requests.read().count() == 0 {
+        return;
+    }
+
+    let save = GameSave {
+        character: characters.single().cloned().unwrap_or_default(),
+        quests: players.single().map(|log| log.to_save()).unwrap_or_default(),
+        story_flags: flags.clone(),
+        weather: weather.to_save(),
+    };
+    match save.write(&settings.quick_save_path) {
+        Ok(()) => info!("Game saved to '{}'", settings.quick_save_path),
+        Err(e) => error!("Could not save the game to '{}': {}", settings.quick_save_path, e),
+    }
+}
+
+/// Restores the character, the quest log of the player, the story flags and the
+/// weather from the quick save. A missing or broken save leaves the game untouched.
+///
+/// # Parameters
+/// * `requests` - Load requests, several in one frame load once.
+/// * `settings` - Path of the quick save.
+/// * `characters` - Progression and reputation of the player.
+/// * `players` - Quest log of the player.
+/// * `flags` - Story flags.
+/// * `weather` - Current weather and forecast.
+/// * `weather_settings` - Profiles of the loaded weather.
+#[coverage(off)]
+fn load_game(
+    mut requests: MessageReader<LoadGame>,
+    settings: Res<SaveGameSettings>,
+    mut characters: Query<&mut Character>,
+    mut players: Query<&mut QuestLog, With<Player>>,
+    mut flags: ResMut<StoryFlags>,
+    mut weather: ResMut<Weather>,
+    weather_settings: Res<WeatherSettings>,
+) {
+    if requests.read().count() == 0 {
+        return;
+    }
+
+    let save = match GameSave::read(&settings.quick_save_path) {
+        Ok(save) => save,
+        Err(e) => {
+            error!("Could not load the game from '{}': {}", settings.quick_save_path, e);
+            return;
+        }
+    };
+    if let Ok(mut character) = characters.single_mut() {
+        *character = save.character;
+    }
+    if let Ok(mut log) = players.single_mut() {
+        log.load(save.quests);
+    }
+    *flags = save.story_flags;
+    weather.load(save.weather, &weather_settings);
+    info!("Game loaded from '{}'", settings.quick_save_path);
+}
@@ -0,0 +1,253 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{QueryFilter, RapierContext, ReadRapierContext};
+use game_models::attributes::{AttributeKind, AttributeModifier, AttributeModifiers, ModifierKind};
+use game_models::definitions::DefinitionLibrary;
+use game_models::entities::definition::{DefinitionSpawner, EntityDefinition};
+use game_models::entities::player::Player;
+use game_models::health::{Dead, Health};
+use game_models::navigation::grid::NavMesh;
+use game_models::pacing::PacingDirector;
+use game_models::spawning::{NightDirector, NightSpawnDefinition, NightSpawned};
+use game_models::states::is_state_simulating;
+use game_models::time_of_day::{DayPhase, DayPhaseChanged, TimeOfDay};
+
+pub struct SpawningLogicComponent;
+
+impl Plugin for SpawningLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            track_nights,
+                            spawn_night_enemies,
+                            despawn_night_enemies
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Height above the player from which the ground below a spawn point is searched.
+const GROUND_SEARCH_HEIGHT: f32 = 10.0;
+
+/// Starts the [`NightDirector`] when the night phase begins and stops it at dawn.
+///
+/// # Parameters
+/// * `phase_changed` - Day phase changes.
+/// * `time_of_day` - Current day, used as night number.
+/// * `director` - Night spawning state.
+/// * `library` - Loaded spawn definitions.
+/// * `definitions` - Spawn definition assets.
+#[coverage(off)]
+fn track_nights(
+    mut phase_changed: MessageReader<DayPhaseChanged>,
+    time_of_day: Res<TimeOfDay>,
+    mut director: ResMut<NightDirector>,
+    library: Res<DefinitionLibrary<NightSpawnDefinition>>,
+    definitions: Res<Assets<NightSpawnDefinition>>,
+) {
+    for change in phase_changed.read() {
+        match change.to {
+            DayPhase::Night => {
+                let Some(definition) = library.get(&director.definition, &definitions) else {
+                    warn!("Night spawn definition '{}' is not loaded", director.definition);
+                    continue;
+                };
+                let night = time_of_day.day;
+                director.start_night(night, definition);
+                info!("Night {} begins with a spawn budget of {}", night, director.remaining_budget);
+            }
+            _ if director.active => {
+                info!("Night {} is over", director.night);
+                director.active = false;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Spawns one enemy per spawn interval while the night lasts, the budget is not used
+/// up and less than the allowed number of enemies is alive. The interval is scaled by
+/// the spawn rate of the [`PacingDirector`], which pauses spawning while the player
+/// relaxes. Spawn points are rolled in
+/// the rings around the player, snapped onto the navigation grid and rejected if any
+/// camera could see them.
+///
+/// # Parameters
+/// * `time` - Global time for the spawn interval.
+/// * `director` - Night spawning state.
+/// * `pacing` - Spawn rate multiplier.
+/// * `library` - Loaded spawn definitions.
+/// * `definitions` - Spawn definition assets.
+/// * `entity_definitions` - Entity definition assets for the base health.
+/// * `entity_library` - Loaded entity definitions.
+/// * `players` - Spawn rings are placed around the player.
+/// * `alive` - Living night enemies.
+/// * `cameras` - Cameras which must not see a spawn.
+/// * `nav_mesh` - Ground for the spawn points.
+/// * `rapier` - Occlusion checks.
+/// * `spawner` - Creates the enemies.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn spawn_night_enemies(
+    time: Res<Time>,
+    mut director: ResMut<NightDirector>,
+    pacing: Res<PacingDirector>,
+    library: Res<DefinitionLibrary<NightSpawnDefinition>>,
+    definitions: Res<Assets<NightSpawnDefinition>>,
+    entity_definitions: Res<Assets<EntityDefinition>>,
+    entity_library: Res<DefinitionLibrary<EntityDefinition>>,
+    players: Query<&Transform, (With<Player>, Without<Dead>)>,
+    alive: Query<(), (With<NightSpawned>, Without<Dead>)>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    nav_mesh: Res<NavMesh>,
+    rapier: ReadRapierContext,
+    mut spawner: DefinitionSpawner,
+) {
+    if !director.active || director.remaining_budget == 0 || pacing.spawn_rate <= 0.0 {
+        return;
+    }
+    director.cooldown -= time.delta_secs();
+    if director.cooldown > 0.0 {
+        return;
+    }
+
+    let Some(definition) = library.get(&director.definition, &definitions) else {
+        return;
+    };
+    let Ok(player) = players.single() else {
+        return;
+    };
+    let night = director.night;
+    if alive.iter().count() as u32 >= definition.max_alive(night) {
+        return;
+    }
+    director.cooldown = definition.spawn_interval / pacing.spawn_rate;
+
+    let budget = director.remaining_budget;
+    let Some(entry) = definition.pick_entry(night, budget, &mut director.rng) else {
+        return;
+    };
+
+    let grid = nav_mesh.grid();
+    let context = rapier.single().ok();
+    let mut position = None;
+    for _ in 0..definition.attempts {
+        let Some(offset) = definition.pick_offset(&mut director.rng) else {
+            break;
+        };
+        let probe = player.translation + Vec3::new(offset.x, GROUND_SEARCH_HEIGHT, offset.y);
+        let Some(ground) = grid.node_below(probe).and_then(|node| grid.node_position(node)) else {
+            continue;
+        };
+        let candidate = ground + Vec3::Y;
+        if !is_visible(candidate, &cameras, context.as_ref()) {
+            position = Some(candidate);
+            break;
+        }
+    }
+    let Some(position) = position else {
+        return;
+    };
+
+    let Some(entity) = spawner.spawn_from_definition(&entry.entity, Transform::from_translation(position)) else {
+        return;
+    };
+    director.remaining_budget -= entry.cost;
+
+    let health_bonus = definition.health_bonus(night);
+    let damage_bonus = definition.damage_bonus(night);
+    let base_health = entity_library
+        .get(&entry.entity, &entity_definitions)
+        .map_or(0.0, |d| d.stats.max_health);
+    let source = format!("night:{}", night);
+    spawner.commands().entity(entity).insert((
+        NightSpawned { night, cost: entry.cost },
+        AttributeModifiers {
+            modifiers: vec![
+                AttributeModifier::new(AttributeKind::MaxHealth, ModifierKind::Percent, health_bonus, source.clone()),
+                AttributeModifier::new(AttributeKind::Damage, ModifierKind::Percent, damage_bonus, source),
+            ],
+        },
+        Health::new(base_health * (1.0 + health_bonus)),
+    ));
+    debug!("Night {} spawned '{}' at {}", night, entry.entity, position);
+}
+
+/// Removes night enemies which are too far away from the player (their cost goes
+/// back into the budget) and, once the night is over, every enemy no camera can see.
+///
+/// # Parameters
+/// * `director` - Night spawning state.
+/// * `library` - Loaded spawn definitions.
+/// * `definitions` - Spawn definition assets.
+/// * `players` - Distance reference.
+/// * `enemies` - Living night enemies.
+/// * `cameras` - Enemies in view are kept until they leave it.
+/// * `rapier` - Occlusion checks.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn despawn_night_enemies(
+    mut commands: Commands,
+    mut director: ResMut<NightDirector>,
+    library: Res<DefinitionLibrary<NightSpawnDefinition>>,
+    definitions: Res<Assets<NightSpawnDefinition>>,
+    players: Query<&Transform, With<Player>>,
+    enemies: Query<(Entity, &NightSpawned, &Transform), Without<Dead>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    rapier: ReadRapierContext,
+) {
+    let Some(definition) = library.get(&director.definition, &definitions) else {
+        return;
+    };
+    let Ok(player) = players.single() else {
+        return;
+    };
+    let context = rapier.single().ok();
+
+    for (entity, spawned, transform) in enemies.iter() {
+        let too_far = transform.translation.distance(player.translation) > definition.despawn_distance;
+        let night_over = !director.active || spawned.night != director.night;
+        if !too_far && !night_over {
+            continue;
+        }
+        if is_visible(transform.translation, &cameras, context.as_ref()) {
+            continue;
+        }
+
+        if too_far && !night_over {
+            director.remaining_budget += spawned.cost;
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+/// `true` if `point` is inside the view of any camera and not hidden behind the world.
+fn is_visible(
+    point: Vec3,
+    cameras: &Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    context: Option<&RapierContext>,
+) -> bool {
+    cameras.iter().any(|(camera, camera_transform)| {
+        let Some(ndc) = camera.world_to_ndc(camera_transform, point) else {
+            return false;
+        };
+        // Small margin, so enemies don't appear right at the screen border.
+        if ndc.x.abs() > 1.1 || ndc.y.abs() > 1.1 || ndc.z <= 0.0 || ndc.z > 1.0 {
+            return false;
+        }
+
+        let Some(context) = context else {
+            return true;
+        };
+        let origin = camera_transform.translation();
+        let to_point = point - origin;
+        let distance = to_point.length();
+        if distance <= 0.1 {
+            return true;
+        }
+        let occluded = context
+            .cast_ray(origin, to_point / distance, distance - 0.1, true, QueryFilter::only_fixed())
+            .is_some();
+        !occluded
+    })
+}
@@ -0,0 +1,316 @@
+use bevy::prelude::*;
+use game_models::attributes::{AttributeModifiers, Attributes};
+use game_models::building::Structure;
+use game_models::combat::{Combos, HitOutcome, MeleeHit};
+use game_models::health::{DamageEvent, Dead};
+use game_models::items::item::Items;
+use game_models::items::ItemUsed;
+use game_models::projectiles::{ProjectileHit, Projectiles};
+use game_models::rng::SeededRng;
+use game_models::states::is_state_simulating;
+use game_models::status_effects::{apply_effect_modifiers, modifier_source, ApplyResult, ApplyStatusEffect, EffectApplication, Effects, RemoveStatusEffect, StatusAura, StatusEffectApplied, StatusEffectRemoved, StatusEffectRng, StatusEffectSettings, StatusEffects, StatusVisual};
+use game_models::weather::Weather;
+
+pub struct StatusEffectLogicComponent;
+
+impl Plugin for StatusEffectLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            apply_hit_effects,
+                            apply_item_effects,
+                            apply_environment_effects,
+                            handle_status_requests,
+                            tick_status_effects,
+                            play_status_hooks
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Writes an [`ApplyStatusEffect`] for every application which passes its chance roll.
+fn roll_effects(
+    applications: &[EffectApplication],
+    target: Entity,
+    source: Option<Entity>,
+    rng: &mut SeededRng,
+    requests: &mut MessageWriter<ApplyStatusEffect>,
+) {
+    for application in applications.iter() {
+        if rng.chance(application.chance) {
+            requests.write(ApplyStatusEffect { target, effect: application.effect.clone(), source });
+        }
+    }
+}
+
+/// Applies the effects of melee attacks and projectiles to hit targets. Blocked and
+/// parried attacks apply nothing.
+///
+/// # Parameters
+/// * `melee_hits` - Melee hits.
+/// * `projectile_hits` - Projectile hits.
+/// * `combos` - Combo definitions with the attack effects.
+/// * `projectiles` - Projectile definitions with their effects.
+/// * `rng` - Session-seeded random source for the chance rolls.
+/// * `requests` - Writer for the effects.
+#[coverage(off)]
+fn apply_hit_effects(
+    mut melee_hits: MessageReader<MeleeHit>,
+    mut projectile_hits: MessageReader<ProjectileHit>,
+    combos: Combos,
+    projectiles: Projectiles,
+    mut rng: ResMut<StatusEffectRng>,
+    mut requests: MessageWriter<ApplyStatusEffect>,
+) {
+    for hit in melee_hits.read() {
+        if hit.outcome != HitOutcome::Hit {
+            continue;
+        }
+        let Some(step) = combos.get(&hit.combo).and_then(|combo| combo.attacks.get(hit.step)) else {
+            continue;
+        };
+        roll_effects(&step.effects, hit.target, Some(hit.attacker), &mut rng.0, &mut requests);
+    }
+
+    for hit in projectile_hits.read() {
+        let Some(projectile) = projectiles.get(&hit.projectile) else {
+            continue;
+        };
+        roll_effects(&projectile.effects, hit.target, hit.shooter, &mut rng.0, &mut requests);
+    }
+}
+
+/// Applies and cures the status effects listed in the definition of used items.
+///
+/// # Parameters
+/// * `used` - Used items.
+/// * `items` - Item definitions.
+/// * `rng` - Session-seeded random source for the chance rolls.
+/// * `requests` - Writer for applied effects.
+/// * `removals` - Writer for cured effects.
+#[coverage(off)]
+fn apply_item_effects(
+    mut used: MessageReader<ItemUsed>,
+    items: Items,
+    mut rng: ResMut<StatusEffectRng>,
+    mut requests: MessageWriter<ApplyStatusEffect>,
+    mut removals: MessageWriter<RemoveStatusEffect>,
+) {
+    for event in used.read() {
+        let Some(item) = items.get(&event.item) else {
+            continue;
+        };
+        for effect in item.cures.iter() {
+            removals.write(RemoveStatusEffect { target: event.entity, effect: effect.clone() });
+        }
+        roll_effects(&item.applies, event.entity, None, &mut rng.0, &mut requests);
+    }
+}
+
+/// Applies the effects of [`StatusAura`]s to characters within their radius and the
+/// rain effect to all characters while it rains, every
+/// [`StatusEffectSettings::environment_interval`] seconds.
+///
+/// # Parameters
+/// * `time` - Global time for the interval.
+/// * `settings` - Interval and rain effect.
+/// * `weather` - Current precipitation.
+/// * `auras` - Effect sources, e.g. fires.
+/// * `characters` - Characters which can be affected, structures are left out.
+/// * `requests` - Writer for the effects.
+/// * `timer` - Seconds until the next application.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn apply_environment_effects(
+    time: Res<Time>,
+    settings: Res<StatusEffectSettings>,
+    weather: Res<Weather>,
+    auras: Query<(Entity, &StatusAura, &GlobalTransform), Without<Dead>>,
+    characters: Query<(Entity, &GlobalTransform), (With<StatusEffects>, With<Attributes>, Without<Structure>, Without<Dead>)>,
+    mut requests: MessageWriter<ApplyStatusEffect>,
+    mut timer: Local<f32>,
+) {
+    *timer -= time.delta_secs();
+    if *timer > 0.0 {
+        return;
+    }
+    *timer = settings.environment_interval;
+
+    for (source, aura, aura_transform) in auras.iter() {
+        let center = aura_transform.translation();
+        for (target, transform) in characters.iter() {
+            if target != source && transform.translation().distance(center) <= aura.radius {
+                requests.write(ApplyStatusEffect { target, effect: aura.effect.clone(), source: Some(source) });
+            }
+        }
+    }
+
+    if let Some(effect) = &settings.rain_effect
+        && weather.current.precipitation >= settings.rain_threshold {
+        for (target, _) in characters.iter() {
+            requests.write(ApplyStatusEffect { target, effect: effect.clone(), source: None });
+        }
+    }
+}
+
+/// Applies and removes status effects. Applied effects remove the effects they cancel
+/// and (re)apply their attribute modifiers once per stack.
+///
+/// # Parameters
+/// * `requests` - Effects to apply.
+/// * `removals` - Effects to remove.
+/// * `effects` - Effect definitions.
+/// * `characters` - Status effects and modifiers of living characters.
+/// * `applied` - Notification writer for applied effects.
+/// * `removed` - Notification writer for removed effects.
+#[coverage(off)]
+fn handle_status_requests(
+    mut requests: MessageReader<ApplyStatusEffect>,
+    mut removals: MessageReader<RemoveStatusEffect>,
+    effects: Effects,
+    mut characters: Query<(&mut StatusEffects, Option<&mut AttributeModifiers>), Without<Dead>>,
+    mut applied: MessageWriter<StatusEffectApplied>,
+    mut removed: MessageWriter<StatusEffectRemoved>,
+) {
+    for request in removals.read() {
+        let Ok((mut status, modifiers)) = characters.get_mut(request.target) else {
+            continue;
+        };
+        if status.remove(&request.effect) {
+            if let Some(mut modifiers) = modifiers {
+                modifiers.remove_source(&modifier_source(&request.effect));
+            }
+            removed.write(StatusEffectRemoved { target: request.target, effect: request.effect.clone() });
+        }
+    }
+
+    for request in requests.read() {
+        let Some(definition) = effects.get(&request.effect) else {
+            debug!("Status effect '{}' is not loaded", request.effect);
+            continue;
+        };
+        let Ok((mut status, mut modifiers)) = characters.get_mut(request.target) else {
+            continue;
+        };
+
+        let result = status.apply(definition, request.source);
+        if result == ApplyResult::Immune {
+            continue;
+        }
+        for cancelled in definition.cancels.iter() {
+            if status.remove(cancelled) {
+                if let Some(modifiers) = modifiers.as_mut() {
+                    modifiers.remove_source(&modifier_source(cancelled));
+                }
+                removed.write(StatusEffectRemoved { target: request.target, effect: cancelled.clone() });
+            }
+        }
+        if !result.changes_stacks() {
+            continue;
+        }
+
+        let stacks = status.stacks(&definition.id);
+        if let Some(modifiers) = modifiers.as_mut() {
+            apply_effect_modifiers(modifiers, definition, stacks);
+        }
+        debug!("{:?} is affected by '{}' ({} stacks)", request.target, definition.id, stacks);
+        applied.write(StatusEffectApplied { target: request.target, effect: definition.id.clone(), stacks });
+    }
+}
+
+/// Counts down the active effects, deals their tick damage per stack and removes
+/// expired effects with their modifiers.
+///
+/// # Parameters
+/// * `time` - Global time.
+/// * `effects` - Effect definitions.
+/// * `characters` - Status effects and modifiers of living characters.
+/// * `damage` - Writer for the tick damage.
+/// * `removed` - Notification writer for expired effects.
+#[coverage(off)]
+fn tick_status_effects(
+    time: Res<Time>,
+    effects: Effects,
+    mut characters: Query<(Entity, &mut StatusEffects, Option<&mut AttributeModifiers>), Without<Dead>>,
+    mut damage: MessageWriter<DamageEvent>,
+    mut removed: MessageWriter<StatusEffectRemoved>,
+) {
+    let delta = time.delta_secs();
+    for (entity, mut status, mut modifiers) in characters.iter_mut() {
+        if status.iter().next().is_none() {
+            continue;
+        }
+
+        for active in status.iter_mut() {
+            let Some(definition) = effects.get(&active.id) else {
+                continue;
+            };
+            let ticks = active.tick(definition, delta);
+            if ticks > 0 && definition.damage_per_tick > 0.0 {
+                damage.write(DamageEvent {
+                    target: entity,
+                    source: active.source,
+                    damage_type: definition.damage_type,
+                    amount: definition.damage_per_tick * (ticks * active.stacks) as f32,
+                });
+            }
+        }
+
+        for effect in status.take_expired() {
+            if let Some(modifiers) = modifiers.as_mut() {
+                modifiers.remove_source(&modifier_source(&effect));
+            }
+            removed.write(StatusEffectRemoved { target: entity, effect });
+        }
+    }
+}
+
+/// Plays the hooks of the effect definitions: the sound on every application and the
+/// visual as child of the affected character while the effect is active.
+///
+/// # Parameters
+/// * `commands` - Spawns sounds and visuals.
+/// * `asset_server` - Loads the hook assets.
+/// * `applied` - Applied effects.
+/// * `removed` - Removed effects.
+/// * `effects` - Effect definitions.
+/// * `visuals` - Spawned visuals.
+#[coverage(off)]
+fn play_status_hooks(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut applied: MessageReader<StatusEffectApplied>,
+    mut removed: MessageReader<StatusEffectRemoved>,
+    effects: Effects,
+    visuals: Query<(Entity, &StatusVisual, &ChildOf)>,
+) {
+    for event in removed.read() {
+        for (visual, status_visual, parent) in visuals.iter() {
+            if parent.parent() == event.target && status_visual.effect == event.effect {
+                commands.entity(visual).despawn();
+            }
+        }
+    }
+
+    for event in applied.read() {
+        let Some(definition) = effects.get(&event.effect) else {
+            continue;
+        };
+        if let Some(sound) = &definition.sound {
+            commands.spawn((
+                AudioPlayer::new(asset_server.load(sound.clone())),
+                PlaybackSettings::DESPAWN,
+            ));
+        }
+        if let Some(visual) = &definition.visual
+            && event.stacks == 1 {
+            commands.spawn((
+                StatusVisual { effect: definition.id.clone() },
+                SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(visual.clone()))),
+                Transform::default(),
+                ChildOf(event.target),
+            ));
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use bevy::prelude::*;
+use game_models::attributes::{AttributeModifiers, Attributes};
+use game_models::config::GlobalConfig;
+use game_models::dialogue::is_dialogue_inactive;
+use game_models::entities::player::Player;
+use game_models::health::{DamageEvent, Dead};
+use game_models::items::item::Items;
+use game_models::items::ItemUsed;
+use game_models::states::is_state_simulating;
+use game_models::survival::{ExertStamina, HeatSource, NeedDepleted, NeedEnvironment, NeedKind, NeedRecovered, Needs, NeedsSettings};
+use game_models::time_of_day::{DayPhase, TimeOfDay, TimeOfDaySettings};
+use game_models::weather::Weather;
+
+pub struct SurvivalLogicComponent;
+
+impl Plugin for SurvivalLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            player_exertion.run_if(is_dialogue_inactive),
+                            spend_stamina,
+                            restore_needs_on_use,
+                            tick_needs,
+                            apply_need_penalties
+                        )
+                            .chain()
+                            .run_if(is_state_simulating));
+    }
+}
+
+/// Spends the stamina of the player for sprinting (sprint key held while moving) and
+/// jumping. Sprinting keeps draining while exhausted, so stamina only regenerates
+/// once the player rests.
+///
+/// # Parameters
+/// * `time` - Global time for the sprint cost.
+/// * `keyboard` - Keyboard input.
+/// * `global_config` - Key bindings.
+/// * `settings` - Costs of the active difficulty.
+/// * `players` - Needs of the player.
+/// * `exert` - Writer for stamina costs.
+#[coverage(off)]
+fn player_exertion(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    global_config: Res<GlobalConfig>,
+    settings: Res<NeedsSettings>,
+    players: Query<(Entity, &Needs), (With<Player>, Without<Dead>)>,
+    mut exert: MessageWriter<ExertStamina>,
+) {
+    let input = &global_config.input_config;
+    let moving = keyboard.any_pressed([
+        input.get_move_up_key(),
+        input.get_move_down_key(),
+        input.get_move_left_key(),
+        input.get_move_right_key(),
+    ]);
+    let sprinting = moving && keyboard.pressed(input.get_sprint_key());
+    let jumping = keyboard.just_pressed(input.get_jump_key());
+    let rates = settings.rates();
+
+    for (entity, needs) in players.iter() {
+        if sprinting {
+            exert.write(ExertStamina { entity, amount: rates.sprint_cost * time.delta_secs() });
+        }
+        if jumping && needs.has_stamina(rates.jump_cost) {
+            exert.write(ExertStamina { entity, amount: rates.jump_cost });
+        }
+    }
+}
+
+/// Spends requested stamina. Requests for more stamina than left drain it to zero,
+/// every request restarts the regeneration delay.
+///
+/// # Parameters
+/// * `requests` - Stamina costs.
+/// * `settings` - Regeneration delay of the active difficulty.
+/// * `needs` - Needs of the exerting entities.
+#[coverage(off)]
+fn spend_stamina(
+    mut requests: MessageReader<ExertStamina>,
+    settings: Res<NeedsSettings>,
+    mut needs: Query<&mut Needs, Without<Dead>>,
+) {
+    let delay = settings.rates().stamina_regen_delay;
+    for request in requests.read() {
+        let Ok(mut needs) = needs.get_mut(request.entity) else {
+            continue;
+        };
+        needs.drain_stamina(request.amount, delay);
+    }
+}
+
+/// Restores the needs listed in the definition of used items, e.g. hunger for food.
+///
+/// # Parameters
+/// * `used` - Used items.
+/// * `items` - Item definitions.
+/// * `needs` - Needs of the using entities.
+#[coverage(off)]
+fn restore_needs_on_use(
+    mut used: MessageReader<ItemUsed>,
+    items: Items,
+    mut needs: Query<&mut Needs, Without<Dead>>,
+) {
+    for event in used.read() {
+        let Some(item) = items.get(&event.item) else {
+            continue;
+        };
+        let Ok(mut needs) = needs.get_mut(event.entity) else {
+            continue;
+        };
+        for restore in item.restores.iter() {
+            needs.get_mut(restore.need).change(restore.amount);
+        }
+    }
+}
+
+/// Decays hunger and thirst, regenerates stamina and changes the warmth by the time of
+/// day, the precipitation of the weather and nearby [`HeatSource`]s. The max stamina
+/// follows the `max_stamina` attribute.
+///
+/// # Parameters
+/// * `time` - Global time.
+/// * `settings` - Rates of the active difficulty.
+/// * `time_of_day` - Current hour for the night check.
+/// * `day_settings` - Day phase boundaries.
+/// * `weather` - Current precipitation.
+/// * `heat_sources` - Warming entities.
+/// * `needs` - Needs of living characters.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn tick_needs(
+    time: Res<Time>,
+    settings: Res<NeedsSettings>,
+    time_of_day: Res<TimeOfDay>,
+    day_settings: Res<TimeOfDaySettings>,
+    weather: Res<Weather>,
+    heat_sources: Query<(&HeatSource, &GlobalTransform), Without<Dead>>,
+    mut needs: Query<(&mut Needs, &GlobalTransform, Option<&Attributes>), Without<Dead>>,
+) {
+    let delta = time.delta_secs();
+    let rates = settings.rates();
+    let night = day_settings.phase_at(time_of_day.hour) == DayPhase::Night;
+
+    for (mut needs, transform, attributes) in needs.iter_mut() {
+        if let Some(attributes) = attributes
+            && needs.stamina.max != attributes.max_stamina.value {
+            needs.stamina.set_max(attributes.max_stamina.value);
+        }
+
+        let position = transform.translation();
+        let heat = heat_sources
+            .iter()
+            .map(|(source, source_transform)| source.warmth_at(source_transform.translation().distance(position)))
+            .sum();
+        let environment = NeedEnvironment {
+            night,
+            precipitation: weather.current.precipitation,
+            heat,
+        };
+        needs.tick(rates, &environment, delta);
+    }
+}
+
+/// Applies the [`NeedPenalty`](game_models::survival::NeedPenalty) modifiers of
+/// depleted needs and removes them once the need recovers. Depleted needs deal
+/// damage every [`NeedsSettings::damage_interval`] seconds.
+///
+/// # Parameters
+/// * `time` - Global time for the damage interval.
+/// * `settings` - Penalties of the needs.
+/// * `characters` - Needs and modifiers of living characters.
+/// * `damage` - Writer for starvation, dehydration and cold damage.
+/// * `depleted` - Notification writer for depleted needs.
+/// * `recovered` - Notification writer for recovered needs.
+#[coverage(off)]
+fn apply_need_penalties(
+    time: Res<Time>,
+    settings: Res<NeedsSettings>,
+    mut characters: Query<(Entity, &mut Needs, &mut AttributeModifiers), Without<Dead>>,
+    mut damage: MessageWriter<DamageEvent>,
+    mut depleted: MessageWriter<NeedDepleted>,
+    mut recovered: MessageWriter<NeedRecovered>,
+) {
+    for (entity, mut needs, mut modifiers) in characters.iter_mut() {
+        needs.damage_timer -= time.delta_secs();
+        let damage_tick = needs.damage_timer <= 0.0;
+        if damage_tick {
+            needs.damage_timer = settings.damage_interval;
+        }
+
+        for kind in NeedKind::ALL {
+            let penalty = settings.penalty(kind);
+            let source = kind.modifier_source();
+            let is_depleted = needs.get(kind).is_depleted();
+            let has_penalty = needs.depleted.contains(&kind);
+
+            if is_depleted && !has_penalty {
+                needs.depleted.push(kind);
+                for modifier in penalty.modifiers.iter() {
+                    let mut modifier = modifier.clone();
+                    modifier.source = source.clone();
+                    modifiers.add(modifier);
+                }
+                debug!("{:?} of {:?} is depleted", kind, entity);
+                depleted.write(NeedDepleted { entity, need: kind });
+            } else if !is_depleted && has_penalty {
+                needs.depleted.retain(|need| *need != kind);
+                modifiers.remove_source(&source);
+                recovered.write(NeedRecovered { entity, need: kind });
+            }
+
+            if is_depleted && damage_tick && penalty.damage_per_second > 0.0 {
+                damage.write(DamageEvent {
+                    target: entity,
+                    source: None,
+                    damage_type: penalty.damage_type,
+                    amount: penalty.damage_per_second * settings.damage_interval,
+                });
+            }
+        }
+    }
+}
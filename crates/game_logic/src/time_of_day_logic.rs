@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+use game_models::config::GlobalConfig;
+use game_models::states::{is_state_simulating, AppState};
+use game_models::time_of_day::{DayPhase, DayPhaseChanged, MidnightReached, Moon, Sun, TimeOfDay, TimeOfDaySettings, HOURS_PER_DAY};
+use game_models::weather::Weather;
+
+pub struct TimeOfDayLogicComponent;
+
+impl Plugin for TimeOfDayLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Preload), spawn_sky_lights);
+        app.add_systems(Update,
+                        (
+                            debug_time_controls,
+                            advance_time_of_day.run_if(is_state_simulating),
+                            emit_time_messages,
+                            update_sky_lighting
+                        )
+                            .chain());
+    }
+}
+
+/// Spawns the sun and moon lights. Their rotation and intensity is driven by
+/// [`update_sky_lighting`].
+#[coverage(off)]
+fn spawn_sky_lights(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Sun"),
+        Sun,
+        DirectionalLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::default(),
+    ));
+    commands.spawn((
+        Name::new("Moon"),
+        Moon,
+        DirectionalLight {
+            shadows_enabled: false,
+            ..default()
+        },
+        Transform::default(),
+    ));
+}
+
+/// Advances the time of day by the real time passed, scaled by the day length.
+///
+/// # Parameters
+/// * `time` - Global time.
+/// * `settings` - Day length.
+/// * `time_of_day` - Current in game time.
+#[coverage(off)]
+fn advance_time_of_day(
+    time: Res<Time>,
+    settings: Res<TimeOfDaySettings>,
+    mut time_of_day: ResMut<TimeOfDay>,
+) {
+    if time_of_day.frozen || settings.day_length <= 0.0 {
+        return;
+    }
+    let hours = time.delta_secs() * HOURS_PER_DAY / settings.day_length * time_of_day.speed;
+    time_of_day.advance(hours);
+}
+
+/// Debug keys: freeze the time (F6 by default) and scrub it back (F7) or forward (F8).
+///
+/// # Parameters
+/// * `time` - Global time used for the scrub speed.
+/// * `keyboard` - Keyboard input.
+/// * `global_config` - Key bindings.
+/// * `settings` - Scrub speed.
+/// * `time_of_day` - Current in game time.
+#[coverage(off)]
+fn debug_time_controls(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    global_config: Res<GlobalConfig>,
+    settings: Res<TimeOfDaySettings>,
+    mut time_of_day: ResMut<TimeOfDay>,
+) {
+    let input = &global_config.input_config;
+    if keyboard.just_pressed(input.get_time_freeze_key()) {
+        time_of_day.frozen = !time_of_day.frozen;
+        info!("Time of day {}", if time_of_day.frozen { "frozen" } else { "running" });
+    }
+
+    let mut direction = 0.0;
+    if keyboard.pressed(input.get_time_rewind_key()) {
+        direction -= 1.0;
+    }
+    if keyboard.pressed(input.get_time_forward_key()) {
+        direction += 1.0;
+    }
+    if direction != 0.0 {
+        time_of_day.advance(direction * settings.scrub_speed * time.delta_secs());
+    }
+}
+
+/// Writes [`DayPhaseChanged`] and [`MidnightReached`] whenever the time crossed a
+/// phase boundary or midnight since the last frame.
+///
+/// # Parameters
+/// * `last` - Phase and day of the last frame.
+/// * `settings` - Phase boundaries.
+/// * `time_of_day` - Current in game time.
+/// * `phase_changed` - Writer for phase changes.
+/// * `midnight` - Writer for new days.
+#[coverage(off)]
+fn emit_time_messages(
+    mut last: Local<Option<(DayPhase, u32)>>,
+    settings: Res<TimeOfDaySettings>,
+    time_of_day: Res<TimeOfDay>,
+    mut phase_changed: MessageWriter<DayPhaseChanged>,
+    mut midnight: MessageWriter<MidnightReached>,
+) {
+    let phase = settings.phase_at(time_of_day.hour);
+    let day = time_of_day.day;
+
+    if let Some((last_phase, last_day)) = *last {
+        for new_day in last_day + 1..=day {
+            midnight.write(MidnightReached { day: new_day });
+        }
+        if last_phase != phase {
+            debug!("Day {} phase changed from {:?} to {:?}", day, last_phase, phase);
+            phase_changed.write(DayPhaseChanged { from: last_phase, to: phase, day });
+        }
+    }
+    *last = Some((phase, day));
+}
+
+/// Rotates the sun and moon over the sky and applies the light curves of
+/// [`TimeOfDaySettings`] to them and to the ambient light, dimmed by the [`Weather`].
+///
+/// # Parameters
+/// * `settings` - Light curves.
+/// * `time_of_day` - Current in game time.
+/// * `weather` - Light multiplier of the weather.
+/// * `ambient` - Global ambient light.
+/// * `suns` - Sun lights.
+/// * `moons` - Moon lights.
+#[coverage(off)]
+fn update_sky_lighting(
+    settings: Res<TimeOfDaySettings>,
+    time_of_day: Res<TimeOfDay>,
+    weather: Res<Weather>,
+    mut ambient: ResMut<AmbientLight>,
+    mut suns: Query<(&mut DirectionalLight, &mut Transform), (With<Sun>, Without<Moon>)>,
+    mut moons: Query<(&mut DirectionalLight, &mut Transform), (With<Moon>, Without<Sun>)>,
+) {
+    let hour = time_of_day.hour;
+    let angle = time_of_day.sun_angle();
+    let elevation = time_of_day.sun_elevation();
+    let light = weather.current.light;
+
+    for (mut sun, mut transform) in suns.iter_mut() {
+        transform.rotation = Quat::from_rotation_y(0.4) * Quat::from_rotation_x(-angle);
+        sun.illuminance = settings.sun_illuminance.sample(hour) * light;
+        sun.color = settings.sun_color.sample(hour).into();
+    }
+
+    for (mut moon, mut transform) in moons.iter_mut() {
+        transform.rotation = Quat::from_rotation_y(0.4) * Quat::from_rotation_x(-angle - std::f32::consts::PI);
+        moon.illuminance = settings.moon_illuminance * (-elevation).max(0.0) * light;
+        moon.color = settings.moon_color.into();
+    }
+
+    ambient.brightness = settings.ambient_brightness.sample(hour) * light;
+    ambient.color = settings.ambient_color.sample(hour).into();
+}
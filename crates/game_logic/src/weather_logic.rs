@@ -0,0 +1,199 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::camera::visibility::NoFrustumCulling;
+use bevy::light::NotShadowCaster;
+use bevy::mesh::PrimitiveTopology;
+use bevy::pbr::{DistanceFog, FogFalloff};
+use bevy::prelude::*;
+use game_models::rng::SeededRng;
+use game_models::states::is_state_simulating;
+use game_models::time_of_day::TimeOfDay;
+use game_models::weather::{Precipitation, RainDrop, Weather, WeatherChanged, WeatherSettings};
+
+pub struct WeatherLogicComponent;
+
+impl Plugin for WeatherLogicComponent {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+                        (
+                            advance_weather.run_if(is_state_simulating),
+                            apply_weather_fog,
+                            update_precipitation
+                        )
+                            .chain());
+    }
+}
+
+/// Half size of the box around the camera which is filled with rain.
+const PRECIPITATION_EXTENT: Vec3 = Vec3::new(20.0, 12.0, 20.0);
+/// Maximum number of rain drops spawned per frame while the rain gets heavier.
+const PRECIPITATION_SPAWNS_PER_FRAME: usize = 100;
+/// Length of the streak drawn for one rain drop.
+const RAIN_DROP_LENGTH: f32 = 0.35;
+
+/// Counts down the current weather, starts the next one of the forecast and blends
+/// the profiles and the wind direction over the transition time. A weather which starts
+/// during a transition blends from the current profile.
+///
+/// # Parameters
+/// * `time` - Global time.
+/// * `settings` - Weather states and transition time.
+/// * `weather` - Current weather.
+/// * `changed` - Writer for weather changes.
+#[coverage(off)]
+fn advance_weather(
+    time: Res<Time>,
+    settings: Res<WeatherSettings>,
+    mut weather: ResMut<Weather>,
+    mut changed: MessageWriter<WeatherChanged>,
+) {
+    let delta = time.delta_secs();
+    weather.remaining -= delta;
+    if weather.remaining <= 0.0 {
+        let (from, to) = weather.advance(&settings);
+        info!("Weather changes from {:?} to {:?}", from, to);
+        changed.write(WeatherChanged { from, to });
+    }
+
+    if weather.blend < 1.0 {
+        let step = if settings.transition_duration > 0.0 { delta / settings.transition_duration } else { 1.0 };
+        weather.blend = (weather.blend + step).min(1.0);
+    }
+
+    let t = 1.0 - (-0.1 * delta).exp();
+    let target = weather.wind_target;
+    weather.wind_direction = weather.wind_direction.lerp(target, t).normalize_or(target);
+    weather.update_profile(&settings);
+}
+
+/// Applies the fog of the weather to every 3D camera. The fog color darkens at night,
+/// so it does not glow in the dark.
+///
+/// # Parameters
+/// * `commands` - Adds the fog to cameras without one.
+/// * `weather` - Current weather.
+/// * `time_of_day` - Sun height for the fog brightness.
+/// * `cameras` - All 3D cameras.
+#[coverage(off)]
+fn apply_weather_fog(
+    mut commands: Commands,
+    weather: Res<Weather>,
+    time_of_day: Res<TimeOfDay>,
+    mut cameras: Query<(Entity, Option<&mut DistanceFog>), With<Camera3d>>,
+) {
+    let profile = &weather.current;
+    let daylight = (time_of_day.sun_elevation() * 2.0 + 0.3).clamp(0.08, 1.0);
+    let color: Color = (profile.fog_color * daylight).into();
+    let falloff = FogFalloff::Linear { start: profile.fog_start, end: profile.fog_end };
+
+    for (entity, fog) in cameras.iter_mut() {
+        match fog {
+            Some(mut fog) => {
+                fog.color = color;
+                fog.falloff = falloff.clone();
+            }
+            None => {
+                commands.entity(entity).insert(DistanceFog {
+                    color,
+                    falloff: falloff.clone(),
+                    ..default()
+                });
+            }
+        }
+    }
+}
+
+/// Keeps the number of rain drops around the camera at the precipitation of the weather.
+/// Drops fall with the wind and are recycled at the top of the box once they leave it.
+/// All drops are drawn by a single line mesh, which is spawned on first use.
+///
+/// # Parameters
+/// * `commands` - Spawns the rain mesh.
+/// * `time` - Global time.
+/// * `rng` - Random source for drop positions.
+/// * `weather` - Precipitation and wind.
+/// * `cameras` - Center of the rain box.
+/// * `rain` - The rain mesh and its drops.
+/// * `meshes` - Mesh assets.
+/// * `materials` - Material assets.
+#[coverage(off)]
+#[allow(clippy::too_many_arguments)]
+fn update_precipitation(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rng: Local<SeededRng>,
+    weather: Res<Weather>,
+    cameras: Query<&GlobalTransform, With<Camera3d>>,
+    mut rain: Query<(&mut Precipitation, &Mesh3d, &mut Visibility)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let Some(camera) = cameras.iter().next() else {
+        return;
+    };
+    let Ok((mut precipitation, mesh, mut visibility)) = rain.single_mut() else {
+        commands.spawn((
+            Precipitation::default(),
+            Mesh3d(meshes.add(Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::default()))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::srgba(0.7, 0.75, 0.85, 0.4),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })),
+            Transform::default(),
+            Visibility::Hidden,
+            NotShadowCaster,
+            NoFrustumCulling,
+        ));
+        return;
+    };
+
+    let target = weather.current.precipitation.max(0.0) as usize;
+    if target == 0 && precipitation.drops.is_empty() {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+
+    let center = camera.translation();
+    let random_position = |rng: &mut SeededRng| {
+        center + Vec3::new(
+            rng.range_f32(-PRECIPITATION_EXTENT.x, PRECIPITATION_EXTENT.x),
+            rng.range_f32(-PRECIPITATION_EXTENT.y, PRECIPITATION_EXTENT.y),
+            rng.range_f32(-PRECIPITATION_EXTENT.z, PRECIPITATION_EXTENT.z),
+        )
+    };
+
+    let drops = &mut precipitation.drops;
+    drops.truncate(target);
+    for _ in 0..target.saturating_sub(drops.len()).min(PRECIPITATION_SPAWNS_PER_FRAME) {
+        drops.push(RainDrop {
+            position: random_position(&mut *rng),
+            fall_speed: rng.range_f32(14.0, 20.0),
+        });
+    }
+
+    let wind = weather.wind_direction * weather.current.wind_strength;
+    let delta = time.delta_secs();
+    let mut positions = Vec::with_capacity(drops.len() * 2);
+    for drop in drops.iter_mut() {
+        let velocity = Vec3::new(wind.x, -drop.fall_speed, wind.y);
+        drop.position += velocity * delta;
+
+        let offset = drop.position - center;
+        if offset.y < -PRECIPITATION_EXTENT.y
+            || offset.x.abs() > PRECIPITATION_EXTENT.x
+            || offset.z.abs() > PRECIPITATION_EXTENT.z {
+            let position = random_position(&mut *rng);
+            drop.position = Vec3::new(position.x, center.y + PRECIPITATION_EXTENT.y, position.z);
+        }
+
+        let streak = velocity.normalize_or(Vec3::NEG_Y) * RAIN_DROP_LENGTH;
+        positions.push(drop.position.to_array());
+        positions.push((drop.position - streak).to_array());
+    }
+
+    visibility.set_if_neq(if positions.is_empty() { Visibility::Hidden } else { Visibility::Inherited });
+    if let Some(mesh) = meshes.get_mut(&mesh.0) {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    }
+}
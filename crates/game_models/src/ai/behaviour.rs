@@ -0,0 +1,275 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::definitions::Definition;
+use crate::factions::ThreatTable;
+use crate::navigation::agent::NavAgent;
+use crate::rng::SeededRng;
+
+/// States of the AI state machine. Every state has a fixed action which is executed
+/// by the AI logic while it is active; the transitions between them are data driven.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiState {
+    /// Stand still.
+    #[default]
+    Idle,
+    /// Walk to random points around home.
+    Wander,
+    /// Walk to the last heard noise.
+    Investigate,
+    /// Follow the current target.
+    Chase,
+    /// Damage the current target when in range.
+    Attack,
+    /// Run away from the current target.
+    Flee,
+    /// Walk back to the home position.
+    ReturnHome,
+}
+
+impl AiState {
+
+    /// Parent group of the state. Transitions authored for a group apply to all of its states.
+    pub fn group(&self) -> AiStateGroup {
+        match self {
+            AiState::Idle | AiState::Wander | AiState::ReturnHome => AiStateGroup::Calm,
+            AiState::Investigate => AiStateGroup::Alert,
+            AiState::Chase | AiState::Attack | AiState::Flee => AiStateGroup::Hostile,
+        }
+    }
+}
+
+/// Parent states of the hierarchical state machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiStateGroup {
+    Calm,
+    Alert,
+    Hostile,
+}
+
+/// Where a transition can be taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum AiTransitionFrom {
+    State(AiState),
+    Group(AiStateGroup),
+}
+
+impl AiTransitionFrom {
+    pub fn matches(&self, state: AiState) -> bool {
+        match self {
+            AiTransitionFrom::State(from) => *from == state,
+            AiTransitionFrom::Group(group) => state.group() == *group,
+        }
+    }
+}
+
+/// Condition of an [`AiTransition`], evaluated against the [`AiContext`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiCondition {
+    /// A target is perceived.
+    TargetVisible,
+    /// A target is perceived and closer than the given distance.
+    TargetInRange(f32),
+    /// No target is perceived.
+    TargetLost,
+    /// A noise was heard and not investigated yet.
+    HeardNoise,
+    /// Health fraction is below the given value (`0.0..=1.0`).
+    HealthBelow(f32),
+    /// Distance to home is greater than the given value.
+    FarFromHome(f32),
+    /// Closer than 1 unit to the current move destination.
+    ArrivedAtDestination,
+    /// The current state is active for longer than the given seconds.
+    TimeInState(f32),
+    /// Random check per evaluation with the given probability.
+    Chance(f32),
+    /// A status effect with the `fear` tag is active.
+    Frightened,
+    Not(Box<AiCondition>),
+    All(Vec<AiCondition>),
+    Any(Vec<AiCondition>),
+}
+
+/// Facts about the world used to evaluate [`AiCondition`]s.
+#[derive(Debug, Clone, Default)]
+pub struct AiContext {
+    pub target_distance: Option<f32>,
+    pub heard_noise: bool,
+    pub health_fraction: f32,
+    pub distance_to_home: f32,
+    pub distance_to_destination: Option<f32>,
+    pub time_in_state: f32,
+    pub frightened: bool,
+}
+
+impl AiCondition {
+
+    pub fn evaluate(&self, context: &AiContext, rng: &mut SeededRng) -> bool {
+        match self {
+            AiCondition::TargetVisible => context.target_distance.is_some(),
+            AiCondition::TargetInRange(range) => context.target_distance.is_some_and(|d| d <= *range),
+            AiCondition::TargetLost => context.target_distance.is_none(),
+            AiCondition::HeardNoise => context.heard_noise,
+            AiCondition::HealthBelow(fraction) => context.health_fraction < *fraction,
+            AiCondition::FarFromHome(distance) => context.distance_to_home > *distance,
+            AiCondition::ArrivedAtDestination => context.distance_to_destination.is_none_or(|d| d < 1.0),
+            AiCondition::TimeInState(seconds) => context.time_in_state >= *seconds,
+            AiCondition::Chance(probability) => rng.chance(*probability),
+            AiCondition::Frightened => context.frightened,
+            AiCondition::Not(condition) => !condition.evaluate(context, rng),
+            AiCondition::All(conditions) => conditions.iter().all(|c| c.evaluate(context, rng)),
+            AiCondition::Any(conditions) => conditions.iter().any(|c| c.evaluate(context, rng)),
+        }
+    }
+}
+
+/// Edge of the state machine. Transitions are checked in file order, the first match wins.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AiTransition {
+    pub from: AiTransitionFrom,
+    pub to: AiState,
+    pub when: AiCondition,
+}
+
+/// Tuning values of an AI profile.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AiParameters {
+    /// Maximum distance at which targets are perceived.
+    pub sight_range: f32,
+    /// Maximum distance at which noises are heard. Noises never carry further than their
+    /// own radius.
+    pub hearing_range: f32,
+    /// Radius around home used for wandering.
+    pub wander_radius: f32,
+    /// Distance at which attacks hit.
+    pub attack_range: f32,
+    /// Seconds between two attacks.
+    pub attack_cooldown: f32,
+    /// Move speed multiplier per state group.
+    pub calm_speed: f32,
+    pub hostile_speed: f32,
+}
+
+impl Default for AiParameters {
+    fn default() -> Self {
+        Self {
+            sight_range: 15.0,
+            hearing_range: 20.0,
+            wander_radius: 8.0,
+            attack_range: 1.6,
+            attack_cooldown: 1.2,
+            calm_speed: 0.4,
+            hostile_speed: 1.0,
+        }
+    }
+}
+
+/// Data driven AI state machine. Authored as `assets/ai/<id>.ai.json` and referenced
+/// by the `ai_profile` of an entity definition.
+#[derive(Asset, TypePath, Debug, Clone, Deserialize, Serialize)]
+pub struct AiProfileDefinition {
+    pub id: String,
+    #[serde(default)]
+    pub initial: AiState,
+    #[serde(default)]
+    pub parameters: AiParameters,
+    pub transitions: Vec<AiTransition>,
+}
+
+impl Definition for AiProfileDefinition {
+    const FOLDER: &'static str = "ai";
+    const EXTENSIONS: &'static [&'static str] = &["ai.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl AiProfileDefinition {
+
+    /// Returns the state the machine switches to from `state`, or `None` to stay.
+    pub fn next_state(&self, state: AiState, context: &AiContext, rng: &mut SeededRng) -> Option<AiState> {
+        self.transitions
+            .iter()
+            .filter(|t| t.from.matches(state) && t.to != state)
+            .find(|t| t.when.evaluate(context, rng))
+            .map(|t| t.to)
+    }
+}
+
+/// Runtime state of an AI controlled entity. `state` shows the active node in the inspector.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+#[require(Blackboard, AiMovement, NavAgent, ThreatTable)]
+pub struct AiBrain {
+    /// Id of the [`AiProfileDefinition`].
+    pub profile: String,
+    /// Currently active state.
+    pub state: AiState,
+    /// Seconds since `state` became active.
+    pub time_in_state: f32,
+}
+
+impl AiBrain {
+    pub fn new(profile: impl Into<String>) -> Self {
+        Self {
+            profile: profile.into(),
+            state: AiState::Idle,
+            time_in_state: 0.0,
+        }
+    }
+}
+
+/// Per entity memory of the AI.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct Blackboard {
+    /// Entity currently targeted.
+    pub target: Option<Entity>,
+    /// Last position the target was seen at.
+    pub last_known_target_position: Option<Vec3>,
+    /// Position of the last heard noise which was not investigated yet.
+    pub noise_position: Option<Vec3>,
+    /// Spawn position, used by wander and return home.
+    pub home: Option<Vec3>,
+    /// Point the entity currently moves to.
+    pub destination: Option<Vec3>,
+    /// Seconds until the next attack is possible.
+    pub attack_cooldown: f32,
+    /// Deterministic random source of this entity.
+    pub rng: SeededRng,
+}
+
+/// Horizontal velocity requested by the AI. Applied by the movement step.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct AiMovement {
+    pub desired_velocity: Vec3,
+}
+
+/// Entity which AI can target besides other AI, e.g. the player or a structure. Whether
+/// it is attacked depends on its [`crate::factions::Faction`].
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct AiTarget;
+
+/// A noise in the world which AI within `radius` investigates.
+#[derive(Message, Debug, Clone)]
+pub struct NoiseEvent {
+    pub position: Vec3,
+    pub radius: f32,
+    pub source: Option<Entity>,
+}
+
+/// Emitted whenever an AI switches its state.
+#[derive(Message, Debug, Clone)]
+pub struct AiStateChanged {
+    pub entity: Entity,
+    pub from: AiState,
+    pub to: AiState,
+}
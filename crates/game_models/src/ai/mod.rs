@@ -0,0 +1,22 @@
+pub mod behaviour;
+
+use bevy::prelude::*;
+use crate::ai::behaviour::{AiBrain, AiMovement, AiProfileDefinition, AiState, AiStateChanged, AiTarget, Blackboard, NoiseEvent};
+use crate::definitions::AppDefinitionExt;
+
+pub struct AiModule;
+
+impl Plugin for AiModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<AiProfileDefinition>();
+        app.add_message::<NoiseEvent>()
+            .add_message::<AiStateChanged>();
+        app.register_type::<AiBrain>()
+            .register_type::<AiState>()
+            .register_type::<Blackboard>()
+            .register_type::<AiMovement>()
+            .register_type::<AiTarget>();
+    }
+}
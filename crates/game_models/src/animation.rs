@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use bevy::ecs::system::SystemParam;
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use serde::Deserialize;
+use crate::definitions::{AppDefinitionExt, Definition, DefinitionLibrary};
+
+pub struct AnimationModule;
+
+impl Plugin for AnimationModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<AnimationSetDefinition>();
+        app.add_message::<AnimationStateChanged>()
+            .add_message::<AnimationEventFired>();
+        app.register_type::<CharacterAnimation>()
+            .register_type::<AnimationState>();
+    }
+}
+
+// =================================================================================================
+//
+//                                          Definition
+//
+// =================================================================================================
+
+/// Animation states of a character, selected from its movement and combat components.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnimationState {
+    #[default]
+    Idle,
+    Walk,
+    Run,
+    Jump,
+    Fall,
+    Attack,
+    Hit,
+    Die,
+}
+
+/// Clips of a glTF character and the states they are played in. Authored as
+/// `assets/animations/<name>.animations.json` and referenced by entity definitions.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct AnimationSetDefinition {
+    pub id: String,
+    /// glTF file containing the named animations.
+    pub source: String,
+    /// Name of the bone moved by root motion.
+    #[serde(default)]
+    pub root_bone: Option<String>,
+    /// Seconds to blend into a clip without an own blend duration.
+    #[serde(default = "default_blend")]
+    pub default_blend: f32,
+    /// Horizontal speed from which the character walks.
+    #[serde(default = "default_walk_speed")]
+    pub walk_speed: f32,
+    /// Horizontal speed from which the character runs.
+    #[serde(default = "default_run_speed")]
+    pub run_speed: f32,
+    /// Vertical speed from which the character jumps or falls.
+    #[serde(default = "default_airborne_speed")]
+    pub airborne_speed: f32,
+    /// Seconds the hit state is held after a hit.
+    #[serde(default = "default_hit_duration")]
+    pub hit_duration: f32,
+    /// Clip key played per state. States without a clip keep the current clip.
+    pub states: HashMap<AnimationState, String>,
+    /// All clips by key. Attack steps of combos reference these keys in their
+    /// `animation`.
+    pub clips: HashMap<String, AnimationClipDefinition>,
+}
+
+impl Definition for AnimationSetDefinition {
+    const FOLDER: &'static str = "animations";
+    const EXTENSIONS: &'static [&'static str] = &["animations.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+fn default_blend() -> f32 {
+    0.2
+}
+
+fn default_walk_speed() -> f32 {
+    0.2
+}
+
+fn default_run_speed() -> f32 {
+    4.0
+}
+
+fn default_airborne_speed() -> f32 {
+    1.5
+}
+
+fn default_hit_duration() -> f32 {
+    0.4
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+/// Single clip of an [`AnimationSetDefinition`].
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnimationClipDefinition {
+    /// Name of the animation in the glTF file.
+    pub clip: String,
+    #[serde(default)]
+    pub looping: bool,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    /// Seconds to blend into this clip, `None` for the default of the set.
+    #[serde(default)]
+    pub blend: Option<f32>,
+    /// The movement of the root bone moves the character instead of the bone.
+    #[serde(default)]
+    pub root_motion: bool,
+    /// Events emitted when the playback passes their time.
+    #[serde(default)]
+    pub events: Vec<AnimationMarker>,
+}
+
+/// Event at a point of a clip, e.g. `{ "time": 0.3, "event": "footstep" }`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct AnimationMarker {
+    /// Seconds from the start of the clip.
+    pub time: f32,
+    pub event: AnimationEventKind,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnimationEventKind {
+    Footstep,
+    /// The frame an attack connects.
+    HitFrame,
+    Custom(String),
+}
+
+/// Facts about a character the animation state is selected from.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AnimationInput {
+    pub velocity: Vec3,
+    pub attacking: bool,
+    pub hit: bool,
+    pub dead: bool,
+}
+
+impl AnimationSetDefinition {
+
+    /// Selects the state for `input`. Dying beats being hit, which beats attacking,
+    /// which beats movement.
+    pub fn select_state(&self, input: &AnimationInput) -> AnimationState {
+        let horizontal = Vec2::new(input.velocity.x, input.velocity.z).length();
+        if input.dead {
+            AnimationState::Die
+        } else if input.hit {
+            AnimationState::Hit
+        } else if input.attacking {
+            AnimationState::Attack
+        } else if input.velocity.y > self.airborne_speed {
+            AnimationState::Jump
+        } else if input.velocity.y < -self.airborne_speed {
+            AnimationState::Fall
+        } else if horizontal >= self.run_speed {
+            AnimationState::Run
+        } else if horizontal >= self.walk_speed {
+            AnimationState::Walk
+        } else {
+            AnimationState::Idle
+        }
+    }
+
+    /// Clip key for `state`. Attacks prefer the clip of the running attack step.
+    pub fn clip_for(&self, state: AnimationState, attack_clip: Option<&str>) -> Option<&str> {
+        if state == AnimationState::Attack
+            && let Some((key, _)) = attack_clip.and_then(|clip| self.clips.get_key_value(clip)) {
+            return Some(key);
+        }
+        self.states.get(&state).map(String::as_str)
+    }
+
+    /// Blend duration into the clip `key` in seconds.
+    pub fn blend_for(&self, key: &str) -> f32 {
+        self.clips.get(key).and_then(|clip| clip.blend).unwrap_or(self.default_blend)
+    }
+}
+
+/// Whether the playback passed `time` while advancing from `previous` to `current`.
+/// A `current` before `previous` means the clip looped.
+pub fn marker_passed(time: f32, previous: f32, current: f32) -> bool {
+    if current >= previous {
+        time > previous && time <= current
+    } else {
+        time > previous || time <= current
+    }
+}
+
+/// Read only access to all loaded [`AnimationSetDefinition`]s by id.
+#[derive(SystemParam)]
+pub struct AnimationSets<'w> {
+    library: Res<'w, DefinitionLibrary<AnimationSetDefinition>>,
+    assets: Res<'w, Assets<AnimationSetDefinition>>,
+}
+
+impl<'w> AnimationSets<'w> {
+
+    pub fn get(&self, id: &str) -> Option<&AnimationSetDefinition> {
+        self.library.get(id, &self.assets)
+    }
+}
+
+// =================================================================================================
+//
+//                                            Runtime
+//
+// =================================================================================================
+
+/// Seek time before a clip started, so markers at `0.0` are emitted.
+pub const CLIP_START: f32 = -1.0;
+
+/// Animation state machine of a glTF character. The [`AnimationPlayer`] of its scene is
+/// bound once the scene spawned.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct CharacterAnimation {
+    /// Id of the [`AnimationSetDefinition`].
+    pub set: String,
+    pub state: AnimationState,
+    /// Key of the playing clip.
+    pub clip: Option<String>,
+    /// Clip key of the running attack step.
+    pub attack_clip: Option<String>,
+    /// Seconds the hit state is held.
+    pub hit_remaining: f32,
+    /// Velocity measured from the movement of the character.
+    pub velocity: Vec3,
+    pub last_position: Option<Vec3>,
+    /// Seek time of the playing clip in the last frame.
+    pub last_seek: f32,
+    /// Entity with the [`AnimationPlayer`] in the scene.
+    pub player: Option<Entity>,
+    pub root_bone: Option<Entity>,
+    /// Translation of the root bone when the player was bound. Root motion keeps the
+    /// bone at its horizontal position.
+    pub root_rest: Vec3,
+    /// Translation of the root bone in the last frame, for root motion.
+    pub last_root: Option<Vec3>,
+    #[reflect(ignore)]
+    pub source: Option<Handle<Gltf>>,
+    #[reflect(ignore)]
+    pub graph: Option<Handle<AnimationGraph>>,
+    /// Graph nodes by clip key.
+    #[reflect(ignore)]
+    pub nodes: HashMap<String, AnimationNodeIndex>,
+}
+
+impl CharacterAnimation {
+
+    pub fn new(set: impl Into<String>) -> Self {
+        Self {
+            set: set.into(),
+            state: AnimationState::Idle,
+            clip: None,
+            attack_clip: None,
+            hit_remaining: 0.0,
+            velocity: Vec3::ZERO,
+            last_position: None,
+            last_seek: CLIP_START,
+            player: None,
+            root_bone: None,
+            root_rest: Vec3::ZERO,
+            last_root: None,
+            source: None,
+            graph: None,
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// Node of the playing clip.
+    pub fn current_node(&self) -> Option<AnimationNodeIndex> {
+        self.clip.as_ref().and_then(|clip| self.nodes.get(clip)).copied()
+    }
+}
+
+// =================================================================================================
+//
+//                                        Notifications
+//
+// =================================================================================================
+
+#[derive(Message, Debug, Clone)]
+pub struct AnimationStateChanged {
+    pub entity: Entity,
+    pub from: AnimationState,
+    pub to: AnimationState,
+}
+
+/// The playback of `clip` passed an [`AnimationMarker`].
+#[derive(Message, Debug, Clone)]
+pub struct AnimationEventFired {
+    pub entity: Entity,
+    pub clip: String,
+    pub event: AnimationEventKind,
+}
@@ -0,0 +1,234 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::entities::definition::EntityStats;
+
+pub struct AttributesModule;
+
+impl Plugin for AttributesModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_type::<Attributes>()
+            .register_type::<AttributeValue>()
+            .register_type::<AttributeModifiers>()
+            .register_type::<AttributeModifier>();
+    }
+}
+
+/// All attributes known by the stats layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeKind {
+    MaxHealth,
+    MaxStamina,
+    MoveSpeed,
+    Damage,
+    Armor,
+}
+
+/// How a [`AttributeModifier`] changes an attribute.
+///
+/// The final value is calculated as `(base + Σ flat) * (1 + Σ percent) * Π multiplicative`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModifierKind {
+    /// Added to the base value (`+10` armor).
+    Flat,
+    /// Summed with all other percent modifiers (`0.1` = +10%).
+    Percent,
+    /// Multiplied with the result (`0.5` = half).
+    Multiplicative,
+}
+
+/// Base and final value of a single attribute, shown side by side in the inspector.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Reflect)]
+pub struct AttributeValue {
+    /// Value without any modifiers.
+    pub base: f32,
+    /// Value with all modifiers applied.
+    pub value: f32,
+}
+
+impl AttributeValue {
+    pub fn new(base: f32) -> Self {
+        Self { base, value: base }
+    }
+}
+
+/// Final stats of an entity. Recomputed from the base values and
+/// [`AttributeModifiers`] whenever the modifiers change.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+#[require(AttributeModifiers)]
+pub struct Attributes {
+    pub max_health: AttributeValue,
+    pub max_stamina: AttributeValue,
+    pub move_speed: AttributeValue,
+    pub damage: AttributeValue,
+    pub armor: AttributeValue,
+}
+
+impl Attributes {
+
+    /// Returns the final value of the given attribute.
+    pub fn get(&self, kind: AttributeKind) -> f32 {
+        self.value(kind).value
+    }
+
+    /// Returns the base value of the given attribute.
+    pub fn base(&self, kind: AttributeKind) -> f32 {
+        self.value(kind).base
+    }
+
+    /// Returns base and final value of the given attribute.
+    pub fn value(&self, kind: AttributeKind) -> &AttributeValue {
+        match kind {
+            AttributeKind::MaxHealth => &self.max_health,
+            AttributeKind::MaxStamina => &self.max_stamina,
+            AttributeKind::MoveSpeed => &self.move_speed,
+            AttributeKind::Damage => &self.damage,
+            AttributeKind::Armor => &self.armor,
+        }
+    }
+
+    fn value_mut(&mut self, kind: AttributeKind) -> &mut AttributeValue {
+        match kind {
+            AttributeKind::MaxHealth => &mut self.max_health,
+            AttributeKind::MaxStamina => &mut self.max_stamina,
+            AttributeKind::MoveSpeed => &mut self.move_speed,
+            AttributeKind::Damage => &mut self.damage,
+            AttributeKind::Armor => &mut self.armor,
+        }
+    }
+
+    /// Changes the base value of an attribute. Call [`Self::recompute`] afterward.
+    pub fn set_base(&mut self, kind: AttributeKind, base: f32) {
+        self.value_mut(kind).base = base;
+    }
+
+    /// Recalculates all final values from the base values and the given modifiers.
+    pub fn recompute(&mut self, modifiers: &AttributeModifiers) {
+        for kind in AttributeKind::ALL {
+            let value = self.value_mut(kind);
+            value.value = modifiers.apply(kind, value.base);
+        }
+    }
+}
+
+impl AttributeKind {
+    pub const ALL: [AttributeKind; 5] = [
+        AttributeKind::MaxHealth,
+        AttributeKind::MaxStamina,
+        AttributeKind::MoveSpeed,
+        AttributeKind::Damage,
+        AttributeKind::Armor,
+    ];
+}
+
+impl From<&EntityStats> for Attributes {
+    fn from(stats: &EntityStats) -> Self {
+        Self {
+            max_health: AttributeValue::new(stats.max_health),
+            max_stamina: AttributeValue::new(100.0),
+            move_speed: AttributeValue::new(stats.move_speed),
+            damage: AttributeValue::new(stats.damage),
+            armor: AttributeValue::new(stats.armor),
+        }
+    }
+}
+
+/// Single change of an attribute, e.g. from a buff or an equipped item.
+#[derive(Debug, Clone, PartialEq, Reflect, Deserialize, Serialize)]
+pub struct AttributeModifier {
+    pub attribute: AttributeKind,
+    pub kind: ModifierKind,
+    pub value: f32,
+    /// Identifies who applied the modifier (e.g. `"item:iron_sword"`, `"buff:rage"`).
+    /// All modifiers of a source can be removed at once.
+    #[serde(default)]
+    pub source: String,
+    /// Remaining lifetime in seconds. `None` lasts until it is removed manually.
+    #[serde(default)]
+    pub remaining: Option<f32>,
+}
+
+impl AttributeModifier {
+
+    /// Creates a permanent modifier.
+    pub fn new(attribute: AttributeKind, kind: ModifierKind, value: f32, source: impl Into<String>) -> Self {
+        Self {
+            attribute,
+            kind,
+            value,
+            source: source.into(),
+            remaining: None,
+        }
+    }
+
+    /// Limits the lifetime of this modifier to `seconds`.
+    pub fn with_duration(mut self, seconds: f32) -> Self {
+        self.remaining = Some(seconds);
+        self
+    }
+}
+
+/// All active modifiers of an entity. Changing this component triggers a
+/// recompute of the [`Attributes`].
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct AttributeModifiers {
+    pub modifiers: Vec<AttributeModifier>,
+}
+
+impl AttributeModifiers {
+
+    pub fn add(&mut self, modifier: AttributeModifier) {
+        self.modifiers.push(modifier);
+    }
+
+    /// Removes all modifiers of the given source and returns how many were removed.
+    pub fn remove_source(&mut self, source: &str) -> usize {
+        let before = self.modifiers.len();
+        self.modifiers.retain(|m| m.source != source);
+        before - self.modifiers.len()
+    }
+
+    pub fn has_source(&self, source: &str) -> bool {
+        self.modifiers.iter().any(|m| m.source == source)
+    }
+
+    /// Applies all modifiers of `kind` to `base`.
+    pub fn apply(&self, kind: AttributeKind, base: f32) -> f32 {
+        let mut flat = 0.0;
+        let mut percent = 0.0;
+        let mut multiplier = 1.0;
+
+        for modifier in self.modifiers.iter().filter(|m| m.attribute == kind) {
+            match modifier.kind {
+                ModifierKind::Flat => flat += modifier.value,
+                ModifierKind::Percent => percent += modifier.value,
+                ModifierKind::Multiplicative => multiplier *= modifier.value,
+            }
+        }
+
+        ((base + flat) * (1.0 + percent) * multiplier).max(0.0)
+    }
+
+    /// Counts down timed modifiers and drops expired ones.
+    ///
+    /// # Returns
+    /// `true` if at least one modifier expired.
+    pub fn tick(&mut self, delta_secs: f32) -> bool {
+        let mut expired = false;
+        self.modifiers.retain_mut(|modifier| {
+            let Some(remaining) = modifier.remaining.as_mut() else {
+                return true;
+            };
+            *remaining -= delta_secs;
+            let keep = *remaining > 0.0;
+            expired |= !keep;
+            keep
+        });
+        expired
+    }
+}
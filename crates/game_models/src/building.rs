@@ -0,0 +1,332 @@
+use std::fmt::{Display, Formatter};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+use crate::definitions::{AppDefinitionExt, Definition, DefinitionLibrary};
+use crate::entities::definition::EntityDefinition;
+use crate::items::inventory::{Inventory, ItemStack};
+
+pub struct BuildingModule;
+
+impl Plugin for BuildingModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<StructureDefinition>();
+        app.init_resource::<BuildMode>()
+            .init_resource::<BuildingSettings>();
+        app.add_message::<PlaceStructure>()
+            .add_message::<StructurePlaced>()
+            .add_message::<PlacementFailed>()
+            .add_message::<StructureDestroyed>();
+        app.register_type::<BuildingSettings>()
+            .register_type::<Structure>()
+            .register_type::<BuildGhost>()
+            .register_type::<Door>();
+    }
+}
+
+// =================================================================================================
+//
+//                                          Definition
+//
+// =================================================================================================
+
+/// Buildable structure, e.g. a wall or a campfire. Authored as
+/// `assets/structures/<name>.structure.json`. Model, collider, durability and armor
+/// come from the referenced [`EntityDefinition`].
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct StructureDefinition {
+    pub id: String,
+    /// Localization key of the name shown in build mode.
+    pub name: String,
+    /// Id of the [`EntityDefinition`] spawned on placement.
+    pub entity: String,
+    /// Items taken from the inventory of the builder.
+    #[serde(default)]
+    pub cost: Vec<ItemStack>,
+    /// Size of the snapping grid on the XZ plane.
+    #[serde(default = "default_grid")]
+    pub grid: f32,
+    /// Rotation step in degrees.
+    #[serde(default = "default_rotation_step")]
+    pub rotation_step: f32,
+    /// Enemies target the structure like the player and break it.
+    #[serde(default = "default_targetable")]
+    pub targetable: bool,
+    /// The structure can be opened and closed by interacting with it.
+    #[serde(default)]
+    pub door: bool,
+}
+
+fn default_grid() -> f32 {
+    1.0
+}
+
+fn default_rotation_step() -> f32 {
+    90.0
+}
+
+fn default_targetable() -> bool {
+    true
+}
+
+impl Definition for StructureDefinition {
+    const FOLDER: &'static str = "structures";
+    const EXTENSIONS: &'static [&'static str] = &["structure.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl StructureDefinition {
+
+    /// Checks whether the inventory holds the full cost.
+    pub fn check_cost(&self, inventory: &Inventory) -> Result<(), PlacementError> {
+        for stack in self.cost.iter() {
+            let available = inventory.count(&stack.item);
+            if available < stack.count {
+                return Err(PlacementError::MissingResources {
+                    item: stack.item.clone(),
+                    required: stack.count,
+                    available,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the cost from the inventory. Nothing is removed if any item is missing.
+    pub fn take_cost(&self, inventory: &mut Inventory) -> Result<(), PlacementError> {
+        let mut remaining = inventory.clone();
+        for stack in self.cost.iter() {
+            remaining.remove(&stack.item, stack.count).map_err(|_| PlacementError::MissingResources {
+                item: stack.item.clone(),
+                required: stack.count,
+                available: remaining.count(&stack.item),
+            })?;
+        }
+        *inventory = remaining;
+        Ok(())
+    }
+}
+
+/// Snaps the XZ coordinates of `point` to a grid of the given size. The height is kept.
+pub fn snap_to_grid(point: Vec3, grid: f32) -> Vec3 {
+    if grid <= 0.0 {
+        return point;
+    }
+    Vec3::new((point.x / grid).round() * grid, point.y, (point.z / grid).round() * grid)
+}
+
+/// Reasons a structure can't be placed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlacementError {
+    /// The structure or its entity definition is not loaded.
+    UnknownStructure(String),
+    /// The builder has no inventory.
+    InvalidBuilder,
+    /// The position is out of reach of the builder.
+    TooFar,
+    /// The structure overlaps another collider.
+    Blocked,
+    MissingResources { item: String, required: u32, available: u32 },
+}
+
+impl Display for PlacementError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlacementError::UnknownStructure(structure) => write!(f, "Structure '{}' is not loaded", structure),
+            PlacementError::InvalidBuilder => write!(f, "Builder has no inventory"),
+            PlacementError::TooFar => write!(f, "Position is out of reach"),
+            PlacementError::Blocked => write!(f, "Position is blocked"),
+            PlacementError::MissingResources { item, required, available } => {
+                write!(f, "Not enough '{}': required {}, available {}", item, required, available)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+// =================================================================================================
+//
+//                                            Runtime
+//
+// =================================================================================================
+
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct BuildingSettings {
+    /// Max distance between builder and structure.
+    pub max_distance: f32,
+    /// Colliders are shrunk by this margin for the overlap check, so structures can be
+    /// placed right next to each other.
+    pub overlap_margin: f32,
+    pub valid_color: Color,
+    pub invalid_color: Color,
+}
+
+impl Default for BuildingSettings {
+    fn default() -> Self {
+        Self {
+            max_distance: 6.0,
+            overlap_margin: 0.05,
+            valid_color: Color::srgba(0.3, 0.9, 0.4, 0.4),
+            invalid_color: Color::srgba(0.9, 0.25, 0.2, 0.4),
+        }
+    }
+}
+
+/// Build mode of the player. Active while a structure is selected.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct BuildMode {
+    /// Id of the selected [`StructureDefinition`].
+    pub selected: Option<String>,
+    /// Yaw of the preview in radians.
+    pub rotation: f32,
+    /// Snapped position under the cursor.
+    pub target: Option<Vec3>,
+    /// Why the structure can't be placed at `target`, `None` if it can.
+    pub error: Option<PlacementError>,
+}
+
+impl BuildMode {
+
+    pub fn is_active(&self) -> bool {
+        self.selected.is_some()
+    }
+
+    /// Selects a structure and resets the rotation.
+    pub fn select(&mut self, structure: Option<String>) {
+        self.selected = structure;
+        self.rotation = 0.0;
+        self.target = None;
+        self.error = None;
+    }
+
+    /// Rotates the preview by `step` degrees.
+    pub fn rotate(&mut self, step: f32) {
+        self.rotation = (self.rotation + step.to_radians()).rem_euclid(std::f32::consts::TAU);
+    }
+
+    /// `true` if the structure can be placed at the current target.
+    pub fn can_place(&self) -> bool {
+        self.target.is_some() && self.error.is_none()
+    }
+}
+
+/// Placed structure. Durability is its [`Health`](crate::health::Health).
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Structure {
+    /// Id of the [`StructureDefinition`].
+    pub definition: String,
+}
+
+/// Transparent preview of the selected structure.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct BuildGhost {
+    /// Id of the previewed [`StructureDefinition`].
+    pub structure: String,
+}
+
+/// Structure which can be opened. Open doors have no collision.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct Door {
+    pub open: bool,
+    /// Hinge edge the door swings around, relative to its center in local space.
+    pub hinge: Vec3,
+}
+
+impl Door {
+
+    /// Closed door swinging around `hinge`.
+    pub fn with_hinge(hinge: Vec3) -> Self {
+        Self { open: false, hinge }
+    }
+
+    /// Transform of the door after it swung open or closed from `transform`.
+    pub fn swing(&self, transform: &Transform) -> Transform {
+        let angle = if self.open { -std::f32::consts::FRAC_PI_2 } else { std::f32::consts::FRAC_PI_2 };
+        let mut swung = *transform;
+        swung.rotate_around(transform.transform_point(self.hinge), Quat::from_rotation_y(angle));
+        swung
+    }
+}
+
+/// Read only access to all loaded [`StructureDefinition`]s and the entities they spawn.
+#[derive(SystemParam)]
+pub struct Structures<'w> {
+    library: Res<'w, DefinitionLibrary<StructureDefinition>>,
+    assets: Res<'w, Assets<StructureDefinition>>,
+    entity_library: Res<'w, DefinitionLibrary<EntityDefinition>>,
+    entity_assets: Res<'w, Assets<EntityDefinition>>,
+}
+
+impl<'w> Structures<'w> {
+
+    pub fn get(&self, id: &str) -> Option<&StructureDefinition> {
+        self.library.get(id, &self.assets)
+    }
+
+    /// Entity definition spawned by the structure.
+    pub fn entity(&self, structure: &StructureDefinition) -> Option<&EntityDefinition> {
+        self.entity_library.get(&structure.entity, &self.entity_assets)
+    }
+
+    /// Ids of all loaded structures, sorted.
+    pub fn ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.library.ids().collect();
+        ids.sort();
+        ids
+    }
+}
+
+// =================================================================================================
+//
+//                                           Requests
+//
+// =================================================================================================
+
+/// Request: `builder` places `structure` at `position` with the yaw `rotation` (radians).
+/// The cost is taken from the inventory of the builder.
+#[derive(Message, Debug, Clone)]
+pub struct PlaceStructure {
+    pub builder: Entity,
+    pub structure: String,
+    /// Snapped ground position, the bottom of the structure.
+    pub position: Vec3,
+    pub rotation: f32,
+}
+
+// =================================================================================================
+//
+//                                        Notifications
+//
+// =================================================================================================
+
+#[derive(Message, Debug, Clone)]
+pub struct StructurePlaced {
+    pub entity: Entity,
+    pub builder: Entity,
+    pub structure: String,
+}
+
+#[derive(Message, Debug, Clone)]
+pub struct PlacementFailed {
+    pub builder: Entity,
+    pub structure: String,
+    pub error: PlacementError,
+}
+
+/// The durability of a structure reached zero.
+#[derive(Message, Debug, Clone)]
+pub struct StructureDestroyed {
+    pub entity: Entity,
+    pub structure: String,
+    pub position: Vec3,
+}
@@ -0,0 +1,466 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+use crate::definitions::{AppDefinitionExt, Definition, DefinitionLibrary};
+use crate::health::DamageType;
+use crate::status_effects::EffectApplication;
+
+pub struct CombatModule;
+
+impl Plugin for CombatModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<ComboDefinition>();
+        app.init_resource::<CombatSettings>()
+            .init_resource::<HitStop>()
+            .init_resource::<CombatStatus>();
+        app.add_message::<MeleeAttack>()
+            .add_message::<SetBlocking>()
+            .add_message::<AttackStarted>()
+            .add_message::<MeleeHit>();
+        app.register_type::<CombatSettings>()
+            .register_type::<HitStop>()
+            .register_type::<CombatStatus>()
+            .register_type::<Combatant>()
+            .register_type::<AttackPhase>()
+            .register_type::<Hitbox>()
+            .register_type::<Staggered>()
+            .register_type::<Knockback>();
+    }
+}
+
+// =================================================================================================
+//
+//                                          Definition
+//
+// =================================================================================================
+
+/// Chain of melee attacks. Authored as `assets/combos/<name>.combo.json` and referenced
+/// by weapons and entity definitions.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct ComboDefinition {
+    pub id: String,
+    /// Attacks in the order they are chained.
+    pub attacks: Vec<AttackStep>,
+}
+
+impl Definition for ComboDefinition {
+    const FOLDER: &'static str = "combos";
+    const EXTENSIONS: &'static [&'static str] = &["combo.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Single attack of a [`ComboDefinition`]. The hitbox is active between windup and
+/// recovery. An attack requested during the active or recovery phase chains the next step.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AttackStep {
+    /// Animation clip played by the animation state machine.
+    #[serde(default)]
+    pub animation: Option<String>,
+    /// Seconds before the hitbox activates.
+    pub windup: f32,
+    /// Seconds the hitbox is active.
+    pub active: f32,
+    /// Seconds after the hitbox until the next attack.
+    pub recovery: f32,
+    /// Multiplier of the `damage` attribute.
+    #[serde(default = "default_multiplier")]
+    pub damage_multiplier: f32,
+    #[serde(default = "default_damage_type")]
+    pub damage_type: DamageType,
+    #[serde(default)]
+    pub stamina_cost: f32,
+    /// Impulse pushing the target away from the attacker.
+    #[serde(default)]
+    pub knockback: f32,
+    /// Seconds the game slows down on a hit.
+    #[serde(default)]
+    pub hit_stop: f32,
+    #[serde(default)]
+    pub hitbox: HitboxShape,
+    /// Status effects applied to hit targets, not to blocked ones.
+    #[serde(default)]
+    pub effects: Vec<EffectApplication>,
+}
+
+fn default_multiplier() -> f32 {
+    1.0
+}
+
+fn default_damage_type() -> DamageType {
+    DamageType::Physical
+}
+
+/// Box in front of the attacker. The offset is local, `-Z` is forward.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct HitboxShape {
+    pub half_extents: [f32; 3],
+    pub offset: [f32; 3],
+}
+
+impl Default for HitboxShape {
+    fn default() -> Self {
+        Self {
+            half_extents: [0.5, 0.5, 0.6],
+            offset: [0.0, 0.0, -1.0],
+        }
+    }
+}
+
+// =================================================================================================
+//
+//                                            Runtime
+//
+// =================================================================================================
+
+/// Read only access to all loaded [`ComboDefinition`]s by id.
+#[derive(SystemParam)]
+pub struct Combos<'w> {
+    library: Res<'w, DefinitionLibrary<ComboDefinition>>,
+    assets: Res<'w, Assets<ComboDefinition>>,
+}
+
+impl<'w> Combos<'w> {
+
+    pub fn get(&self, id: &str) -> Option<&ComboDefinition> {
+        self.library.get(id, &self.assets)
+    }
+}
+
+/// Result of a hit against a defender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitOutcome {
+    Hit,
+    /// The defender blocked, the damage is reduced.
+    Blocked,
+    /// The defender blocked right before the hit, the attacker is staggered.
+    Parried,
+}
+
+/// Tuning of the melee combat. Editable at runtime in the inspector.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct CombatSettings {
+    /// Combo of combatants without a weapon combo.
+    pub default_combo: String,
+    /// Fraction of the damage a block absorbs.
+    pub block_reduction: f32,
+    /// Stamina a blocked hit costs the defender.
+    pub block_stamina_cost: f32,
+    /// Seconds after raising the block in which a hit is parried.
+    pub parry_window: f32,
+    /// Seconds a parried attacker is staggered.
+    pub parry_stagger: f32,
+    /// Minimum dot product between the view of the defender and the direction to the
+    /// attacker for a block. Hits from behind can't be blocked.
+    pub block_facing: f32,
+    /// Share of the knockback applied to blocked hits.
+    pub blocked_knockback: f32,
+    /// Seconds the knockback of kinematic targets fades out over.
+    pub knockback_duration: f32,
+    /// Relative game speed during hit-stop.
+    pub hit_stop_speed: f32,
+    /// Hostile enemies closer than this engage the player in combat.
+    pub engage_distance: f32,
+    /// Seconds without engaged enemies before combat ends.
+    pub disengage_delay: f32,
+}
+
+impl Default for CombatSettings {
+    fn default() -> Self {
+        Self {
+            default_combo: String::from("unarmed"),
+            block_reduction: 0.7,
+            block_stamina_cost: 15.0,
+            parry_window: 0.2,
+            parry_stagger: 1.0,
+            block_facing: 0.3,
+            blocked_knockback: 0.3,
+            knockback_duration: 0.2,
+            hit_stop_speed: 0.05,
+            engage_distance: 12.0,
+            disengage_delay: 4.0,
+        }
+    }
+}
+
+impl CombatSettings {
+
+    /// Outcome of a hit against `defender`.
+    ///
+    /// # Parameters
+    /// * `defender` - Combat state of the target, `None` for targets which can't block.
+    /// * `facing` - Dot product between the forward of the defender and the direction
+    ///   to the attacker.
+    pub fn resolve(&self, defender: Option<&Combatant>, facing: f32) -> HitOutcome {
+        let Some(defender) = defender else {
+            return HitOutcome::Hit;
+        };
+        if !defender.blocking || facing < self.block_facing {
+            return HitOutcome::Hit;
+        }
+        if defender.block_time <= self.parry_window {
+            HitOutcome::Parried
+        } else {
+            HitOutcome::Blocked
+        }
+    }
+}
+
+/// Whether hostile enemies engage the player. Tracked in every simulating state, the
+/// in-game states follow it with `InGameStates::Combat`.
+#[derive(Resource, Debug, Clone, Default, Reflect)]
+#[reflect(Resource)]
+pub struct CombatStatus {
+    pub engaged: bool,
+}
+
+/// Remaining hit-stop. Slows the virtual time down while active.
+#[derive(Resource, Debug, Clone, Default, Reflect)]
+#[reflect(Resource)]
+pub struct HitStop {
+    /// Real seconds left.
+    pub remaining: f32,
+}
+
+impl HitStop {
+
+    /// Starts a hit-stop, a running longer one is kept.
+    pub fn trigger(&mut self, duration: f32) {
+        self.remaining = self.remaining.max(duration);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub enum AttackPhase {
+    #[default]
+    Idle,
+    Windup,
+    Active,
+    Recovery,
+}
+
+/// Melee state of a character.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct Combatant {
+    /// Combo used without a weapon combo, `None` for [`CombatSettings::default_combo`].
+    pub combo: Option<String>,
+    /// Combo of the running attack.
+    pub current: Option<String>,
+    pub phase: AttackPhase,
+    /// Index of the running attack in the combo.
+    pub step: usize,
+    /// Seconds spent in the current phase.
+    pub timer: f32,
+    /// The next attack of the combo was requested.
+    pub queued: bool,
+    /// Entities hit by the running attack, every entity is hit once per attack.
+    pub hit: Vec<Entity>,
+    pub blocking: bool,
+    /// Seconds since the block was raised.
+    pub block_time: f32,
+    /// Sensor child used as hitbox.
+    pub hitbox: Option<Entity>,
+}
+
+impl Combatant {
+
+    pub fn new(combo: Option<String>) -> Self {
+        Self { combo, ..default() }
+    }
+
+    pub fn is_attacking(&self) -> bool {
+        self.phase != AttackPhase::Idle
+    }
+
+    /// Starts the first attack of `combo` when idle, otherwise queues the next one.
+    ///
+    /// # Returns
+    /// `true` if a new combo was started.
+    pub fn request_attack(&mut self, combo: &str) -> bool {
+        match self.phase {
+            AttackPhase::Idle => {
+                self.current = Some(combo.to_string());
+                self.blocking = false;
+                self.enter(AttackPhase::Windup, 0);
+                true
+            }
+            AttackPhase::Windup => false,
+            AttackPhase::Active | AttackPhase::Recovery => {
+                self.queued = true;
+                false
+            }
+        }
+    }
+
+    /// Raises or lowers the block. Attacking characters can't block.
+    pub fn set_blocking(&mut self, blocking: bool) {
+        if blocking && (self.blocking || self.is_attacking()) {
+            return;
+        }
+        self.blocking = blocking;
+        self.block_time = 0.0;
+    }
+
+    /// Cancels the running attack, e.g. when parried.
+    pub fn interrupt(&mut self) {
+        self.current = None;
+        self.enter(AttackPhase::Idle, 0);
+    }
+
+    fn enter(&mut self, phase: AttackPhase, step: usize) {
+        self.phase = phase;
+        self.step = step;
+        self.timer = 0.0;
+        if phase == AttackPhase::Windup {
+            self.queued = false;
+            self.hit.clear();
+        }
+    }
+
+    /// Advances the running attack by `delta` seconds.
+    ///
+    /// # Returns
+    /// The phases entered during this tick, in order.
+    pub fn tick(&mut self, combo: &ComboDefinition, delta: f32) -> Vec<AttackPhase> {
+        if self.blocking {
+            self.block_time += delta;
+        }
+
+        let mut entered = Vec::new();
+        if self.phase == AttackPhase::Idle {
+            return entered;
+        }
+        self.timer += delta;
+
+        loop {
+            let Some(step) = combo.attacks.get(self.step) else {
+                self.interrupt();
+                entered.push(AttackPhase::Idle);
+                return entered;
+            };
+            let duration = match self.phase {
+                AttackPhase::Windup => step.windup,
+                AttackPhase::Active => step.active,
+                AttackPhase::Recovery => step.recovery,
+                AttackPhase::Idle => return entered,
+            };
+            if self.timer < duration {
+                return entered;
+            }
+
+            let overflow = self.timer - duration;
+            match self.phase {
+                AttackPhase::Windup => self.enter(AttackPhase::Active, self.step),
+                AttackPhase::Active => self.enter(AttackPhase::Recovery, self.step),
+                _ if self.queued && self.step + 1 < combo.attacks.len() => self.enter(AttackPhase::Windup, self.step + 1),
+                _ => self.interrupt(),
+            }
+            self.timer = overflow;
+            entered.push(self.phase);
+        }
+    }
+}
+
+/// Sensor child of a [`Combatant`], enabled during the active phase of an attack.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Hitbox {
+    pub owner: Entity,
+}
+
+/// The character can't attack or block until the timer finished.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Staggered(pub Timer);
+
+/// Push of a hit on a kinematic target. Impulses only move dynamic bodies, so the
+/// knockback moves the transform and fades out over its duration.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Knockback {
+    /// Initial velocity of the push.
+    pub velocity: Vec3,
+    pub duration: f32,
+    /// Seconds left.
+    pub remaining: f32,
+}
+
+impl Knockback {
+
+    pub fn new(velocity: Vec3, duration: f32) -> Self {
+        Self { velocity, duration, remaining: duration }
+    }
+
+    /// Advances the knockback by `delta` seconds.
+    ///
+    /// # Returns
+    /// Distance the target moves in this step.
+    pub fn advance(&mut self, delta: f32) -> Vec3 {
+        if self.remaining <= 0.0 || self.duration <= 0.0 {
+            return Vec3::ZERO;
+        }
+        let step = delta.min(self.remaining);
+        // Linear fade, integrated over the step.
+        let start = self.remaining / self.duration;
+        self.remaining -= step;
+        let end = self.remaining / self.duration;
+        self.velocity * (start + end) * 0.5 * step
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.remaining <= 0.0
+    }
+}
+
+// =================================================================================================
+//
+//                                           Requests
+//
+// =================================================================================================
+
+/// Request: `entity` attacks with its current combo, or chains the next attack.
+#[derive(Message, Debug, Clone)]
+pub struct MeleeAttack {
+    pub entity: Entity,
+}
+
+/// Request: `entity` raises or lowers its block.
+#[derive(Message, Debug, Clone)]
+pub struct SetBlocking {
+    pub entity: Entity,
+    pub blocking: bool,
+}
+
+// =================================================================================================
+//
+//                                        Notifications
+//
+// =================================================================================================
+
+/// An attack of a combo started, e.g. to play its animation.
+#[derive(Message, Debug, Clone)]
+pub struct AttackStarted {
+    pub entity: Entity,
+    pub combo: String,
+    pub step: usize,
+    pub animation: Option<String>,
+}
+
+/// The hitbox of `attacker` touched `target`.
+#[derive(Message, Debug, Clone)]
+pub struct MeleeHit {
+    pub attacker: Entity,
+    pub target: Entity,
+    /// Combo and attack index of the hitting attack.
+    pub combo: String,
+    pub step: usize,
+    pub outcome: HitOutcome,
+    /// Damage before resistances, zero for parried hits.
+    pub damage: f32,
+}
@@ -132,6 +132,21 @@ pub struct InputConfig {
     pub system_info: String,
     /// Toggle gizmo/boxes visualization.
     pub gizmos_boxen: String,
+    /// Freeze/unfreeze the time of day.
+    #[serde(default = "default_time_freeze")]
+    pub time_freeze: String,
+    /// Hold to rewind the time of day.
+    #[serde(default = "default_time_rewind")]
+    pub time_rewind: String,
+    /// Hold to fast forward the time of day.
+    #[serde(default = "default_time_forward")]
+    pub time_forward: String,
+    /// Save the game to the quick save.
+    #[serde(default = "default_quick_save")]
+    pub quick_save: String,
+    /// Load the quick save.
+    #[serde(default = "default_quick_load")]
+    pub quick_load: String,
 
     /// Move character up.
     pub movement_up: String,
@@ -143,6 +158,26 @@ pub struct InputConfig {
     pub movement_right: String,
     /// Trigger jump action.
     pub movement_jump: String,
+    /// Hold to sprint, costs stamina.
+    #[serde(default = "default_movement_sprint")]
+    pub movement_sprint: String,
+
+    /// Melee attack, pressed again during an attack to chain the combo.
+    #[serde(default = "default_attack")]
+    pub attack: String,
+    /// Hold to block, raising it right before a hit parries.
+    #[serde(default = "default_block")]
+    pub block: String,
+    /// Fires the equipped ranged weapon at the cursor, held for continuous fire.
+    #[serde(default = "default_fire")]
+    pub fire: String,
+
+    /// Cycle through the buildable structures and leave build mode after the last one.
+    #[serde(default = "default_build_mode")]
+    pub build_mode: String,
+    /// Rotate the structure preview.
+    #[serde(default = "default_build_rotate")]
+    pub build_rotate: String,
 
     /// Context-sensitive interaction (e.g., talk, use).
     pub interact: String,
@@ -154,12 +189,25 @@ impl Default for InputConfig {
             inspector: String::from("F1"),
             system_info: String::from("F3"),
             gizmos_boxen: String::from("F9"),
+            time_freeze: default_time_freeze(),
+            time_rewind: default_time_rewind(),
+            time_forward: default_time_forward(),
+            quick_save: default_quick_save(),
+            quick_load: default_quick_load(),
 
             movement_up: String::from("W"),
             movement_down: String::from("S"),
             movement_left: String::from("A"),
             movement_right: String::from("D"),
             movement_jump: String::from("Space"),
+            movement_sprint: default_movement_sprint(),
+
+            attack: default_attack(),
+            block: default_block(),
+            fire: default_fire(),
+
+            build_mode: default_build_mode(),
+            build_rotate: default_build_rotate(),
 
             interact: String::from("E")
         }
@@ -179,6 +227,34 @@ impl InputConfig {
         convert(self.gizmos_boxen.as_str()).unwrap_or_else(|| KeyCode::F9)
     }
 
+    pub fn get_time_freeze_key(&self) -> KeyCode {
+        convert(self.time_freeze.as_str()).unwrap_or_else(|| KeyCode::F6)
+    }
+
+    pub fn get_time_rewind_key(&self) -> KeyCode {
+        convert(self.time_rewind.as_str()).unwrap_or_else(|| KeyCode::F7)
+    }
+
+    pub fn get_time_forward_key(&self) -> KeyCode {
+        convert(self.time_forward.as_str()).unwrap_or_else(|| KeyCode::F8)
+    }
+
+    pub fn get_quick_save_key(&self) -> KeyCode {
+        convert(self.quick_save.as_str()).unwrap_or_else(|| KeyCode::F5)
+    }
+
+    pub fn get_quick_load_key(&self) -> KeyCode {
+        convert(self.quick_load.as_str()).unwrap_or_else(|| KeyCode::F4)
+    }
+
+    pub fn get_move_up_key(&self) -> KeyCode {
+        convert(self.movement_up.as_str()).unwrap_or_else(|| KeyCode::KeyW)
+    }
+
+    pub fn get_move_down_key(&self) -> KeyCode {
+        convert(self.movement_down.as_str()).unwrap_or_else(|| KeyCode::KeyS)
+    }
+
     pub fn get_move_left_key(&self) -> KeyCode {
         convert(self.movement_left.as_str()).unwrap_or_else(|| KeyCode::KeyA)
     }
@@ -191,6 +267,30 @@ impl InputConfig {
         convert(self.movement_jump.as_str()).unwrap_or_else(|| KeyCode::Space)
     }
 
+    pub fn get_sprint_key(&self) -> KeyCode {
+        convert(self.movement_sprint.as_str()).unwrap_or_else(|| KeyCode::ShiftLeft)
+    }
+
+    pub fn get_attack_key(&self) -> KeyCode {
+        convert(self.attack.as_str()).unwrap_or_else(|| KeyCode::KeyF)
+    }
+
+    pub fn get_block_key(&self) -> KeyCode {
+        convert(self.block.as_str()).unwrap_or_else(|| KeyCode::KeyQ)
+    }
+
+    pub fn get_fire_key(&self) -> KeyCode {
+        convert(self.fire.as_str()).unwrap_or_else(|| KeyCode::KeyG)
+    }
+
+    pub fn get_build_mode_key(&self) -> KeyCode {
+        convert(self.build_mode.as_str()).unwrap_or_else(|| KeyCode::KeyB)
+    }
+
+    pub fn get_build_rotate_key(&self) -> KeyCode {
+        convert(self.build_rotate.as_str()).unwrap_or_else(|| KeyCode::KeyR)
+    }
+
     pub fn get_interact_key(&self) -> KeyCode {
         convert(self.interact.as_str()).unwrap_or_else(|| KeyCode::KeyE)
     }
@@ -203,6 +303,50 @@ impl InputConfig {
 //
 // =================================================================================================
 
+fn default_time_freeze() -> String {
+    String::from("F6")
+}
+
+fn default_time_rewind() -> String {
+    String::from("F7")
+}
+
+fn default_time_forward() -> String {
+    String::from("F8")
+}
+
+fn default_quick_save() -> String {
+    String::from("F5")
+}
+
+fn default_quick_load() -> String {
+    String::from("F4")
+}
+
+fn default_movement_sprint() -> String {
+    String::from("ShiftLeft")
+}
+
+fn default_attack() -> String {
+    String::from("F")
+}
+
+fn default_block() -> String {
+    String::from("Q")
+}
+
+fn default_fire() -> String {
+    String::from("G")
+}
+
+fn default_build_mode() -> String {
+    String::from("B")
+}
+
+fn default_build_rotate() -> String {
+    String::from("R")
+}
+
 /// Parses a resolution string in the form `"<width>x<height>"` (case-insensitive `x`)
 /// into a pair of positive floating-point dimensions.
 ///
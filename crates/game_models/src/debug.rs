@@ -94,6 +94,16 @@ pub struct DebugSnapshot {
     pub player_pos: Vec3,
     /// Current character name.
     pub character_name: String,
+    /// Active pacing phase.
+    pub pacing_phase: String,
+    /// Player intensity score of the pacing director (0.0–1.0).
+    pub pacing_intensity: f32,
+    /// Spawn rate multiplier of the pacing director.
+    pub pacing_spawn_rate: f32,
+    /// Enemy aggression multiplier of the pacing director.
+    pub pacing_aggression: f32,
+    /// Smoothed music intensity (0.0–1.0).
+    pub music_intensity: f32,
 
     // Build / Config
     /// Application name.
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext, LoadedFolder};
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+
+/// Data driven asset which is authored as a JSON file below `assets/<FOLDER>/`.
+///
+/// Every definition carries a unique string id. The id is used by gameplay code
+/// and other definitions to reference it (e.g. an entity referencing a loot table).
+pub trait Definition: Asset + DeserializeOwned {
+    /// Folder below `assets/` which is loaded on startup.
+    const FOLDER: &'static str;
+    /// File extensions (without the leading dot) handled by the loader.
+    const EXTENSIONS: &'static [&'static str];
+
+    /// Unique id of this definition.
+    fn id(&self) -> &str;
+}
+
+// =================================================================================================
+//
+//                                            Loader
+//
+// =================================================================================================
+
+/// Errors which can occur while a [`JsonAssetLoader`] reads a definition file.
+#[derive(Debug)]
+pub enum JsonLoaderError {
+    /// The file could not be read.
+    Io(std::io::Error),
+    /// The file content is not valid JSON for the target type.
+    Json(serde_json::Error),
+}
+
+impl Display for JsonLoaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonLoaderError::Io(e) => write!(f, "Could not read definition file: {}", e),
+            JsonLoaderError::Json(e) => write!(f, "Could not parse definition file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for JsonLoaderError {}
+
+impl From<std::io::Error> for JsonLoaderError {
+    fn from(value: std::io::Error) -> Self {
+        JsonLoaderError::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for JsonLoaderError {
+    fn from(value: serde_json::Error) -> Self {
+        JsonLoaderError::Json(value)
+    }
+}
+
+/// Generic asset loader which deserializes any [`Definition`] from JSON.
+/// Hot reload is handled by the asset server (`file_watcher` feature).
+pub struct JsonAssetLoader<A> {
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A> Default for JsonAssetLoader<A> {
+    fn default() -> Self {
+        Self { _marker: PhantomData }
+    }
+}
+
+impl<A: Definition> AssetLoader for JsonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = JsonLoaderError;
+
+    #[coverage(off)]
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(serde_json::from_slice::<A>(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        A::EXTENSIONS
+    }
+}
+
+// =================================================================================================
+//
+//                                            Library
+//
+// =================================================================================================
+
+/// Index of all loaded definitions of type `A`, keyed by [`Definition::id`].
+///
+/// The library keeps the folder handle alive, so definitions stay loaded
+/// and are re-indexed whenever a file changes on disk.
+#[derive(Resource)]
+pub struct DefinitionLibrary<A: Definition> {
+    /// Handle to the loaded `assets/<FOLDER>/` folder.
+    pub folder: Handle<LoadedFolder>,
+    by_id: HashMap<String, Handle<A>>,
+}
+
+impl<A: Definition> Default for DefinitionLibrary<A> {
+    fn default() -> Self {
+        Self {
+            folder: Handle::default(),
+            by_id: HashMap::new(),
+        }
+    }
+}
+
+impl<A: Definition> DefinitionLibrary<A> {
+
+    /// Returns the handle of the definition with the given id, if it is loaded.
+    pub fn handle(&self, id: &str) -> Option<&Handle<A>> {
+        self.by_id.get(id)
+    }
+
+    /// Returns the definition with the given id, if it is loaded.
+    pub fn get<'a>(&self, id: &str, assets: &'a Assets<A>) -> Option<&'a A> {
+        self.by_id.get(id).and_then(|handle| assets.get(handle))
+    }
+
+    /// Iterates over all known definition ids.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.by_id.keys().map(String::as_str)
+    }
+
+    /// Registers a definition handle manually. Used for definitions which are not
+    /// part of the definition folder (e.g. created at runtime or in headless apps).
+    pub fn insert(&mut self, id: impl Into<String>, handle: Handle<A>) {
+        self.by_id.insert(id.into(), handle);
+    }
+}
+
+/// Extension for [`App`] to register a [`Definition`] type with its loader,
+/// library resource and indexing system.
+pub trait AppDefinitionExt {
+    fn register_definition<A: Definition>(&mut self) -> &mut Self;
+}
+
+impl AppDefinitionExt for App {
+
+    #[coverage(off)]
+    fn register_definition<A: Definition>(&mut self) -> &mut Self {
+        self.init_asset::<A>()
+            .register_asset_loader(JsonAssetLoader::<A>::default())
+            .init_resource::<DefinitionLibrary<A>>()
+            .add_systems(Startup, load_definition_folder::<A>)
+            .add_systems(PreUpdate, index_definitions::<A>)
+    }
+}
+
+/// Starts loading the definition folder of `A` if an asset server is present.
+#[coverage(off)]
+fn load_definition_folder<A: Definition>(
+    asset_server: Option<Res<AssetServer>>,
+    mut library: ResMut<DefinitionLibrary<A>>,
+) {
+    if let Some(asset_server) = asset_server {
+        library.folder = asset_server.load_folder(A::FOLDER);
+    }
+}
+
+/// Rebuilds the id index of [`DefinitionLibrary`] whenever a definition of type `A`
+/// is added, modified or removed, and once the definition folder finished loading.
+/// Definitions usually finish before their folder, so the folder event is required
+/// to index them at all.
+#[coverage(off)]
+fn index_definitions<A: Definition>(
+    mut events: MessageReader<AssetEvent<A>>,
+    mut folder_events: MessageReader<AssetEvent<LoadedFolder>>,
+    folders: Res<Assets<LoadedFolder>>,
+    definitions: Res<Assets<A>>,
+    mut library: ResMut<DefinitionLibrary<A>>,
+) {
+    let definitions_changed = events.read().count() > 0;
+    let folder_loaded = folder_events.read().any(|event| event.is_loaded_with_dependencies(&library.folder));
+    if !definitions_changed && !folder_loaded {
+        return;
+    }
+
+    let Some(folder) = folders.get(&library.folder) else {
+        return;
+    };
+
+    let mut by_id = HashMap::new();
+    for handle in folder.handles.iter().cloned() {
+        let Ok(handle) = handle.try_typed::<A>() else {
+            continue;
+        };
+        if let Some(definition) = definitions.get(&handle) {
+            if by_id.insert(definition.id().to_string(), handle).is_some() {
+                warn!("Duplicate definition id '{}' in '{}'", definition.id(), A::FOLDER);
+            }
+        }
+    }
+
+    // Keep manually inserted definitions which are not part of the folder.
+    for (id, handle) in library.by_id.iter() {
+        if !by_id.contains_key(id) && definitions.contains(handle) {
+            by_id.insert(id.clone(), handle.clone());
+        }
+    }
+
+    library.by_id = by_id;
+}
@@ -0,0 +1,298 @@
+use std::collections::HashSet;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::definitions::{AppDefinitionExt, Definition};
+use crate::items::inventory::Inventory;
+
+pub struct DialogueModule;
+
+impl Plugin for DialogueModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<DialogueDefinition>();
+        app.init_resource::<DialogueState>()
+            .init_resource::<StoryFlags>();
+        app.add_message::<StartDialogue>()
+            .add_message::<ContinueDialogue>()
+            .add_message::<ChooseDialogueOption>()
+            .add_message::<EndDialogue>()
+            .add_message::<DialogueStarted>()
+            .add_message::<DialogueEnded>()
+            .add_message::<DialogueItemsChanged>();
+        app.register_type::<DialogueOwner>()
+            .register_type::<StoryFlags>();
+    }
+}
+
+// =================================================================================================
+//
+//                                          Definition
+//
+// =================================================================================================
+
+/// Dialogue tree authored as `assets/dialogue/<name>.dialogue.json`.
+///
+/// Every list of node ids (`start`, `next` of nodes and choices) is resolved to the
+/// first node whose conditions are met, an empty or unmet list ends the dialogue.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct DialogueDefinition {
+    /// Unique id, referenced by [`DialogueOwner`] and entity definitions.
+    pub id: String,
+    /// Candidate entry nodes.
+    pub start: Vec<String>,
+    pub nodes: Vec<DialogueNode>,
+}
+
+impl Definition for DialogueDefinition {
+    const FOLDER: &'static str = "dialogue";
+    const EXTENSIONS: &'static [&'static str] = &["dialogue.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl DialogueDefinition {
+
+    pub fn node(&self, id: &str) -> Option<&DialogueNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    /// Returns the first node of `candidates` whose conditions are met.
+    pub fn resolve(&self, candidates: &[String], context: &DialogueContext) -> Option<&DialogueNode> {
+        candidates
+            .iter()
+            .filter_map(|id| self.node(id))
+            .find(|node| context.all_met(&node.conditions))
+    }
+}
+
+/// One line of a dialogue.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DialogueNode {
+    pub id: String,
+    #[serde(default)]
+    pub speaker: DialogueSpeaker,
+    /// Localization key of the line.
+    pub text: String,
+    /// Conditions to enter the node.
+    #[serde(default)]
+    pub conditions: Vec<DialogueCondition>,
+    /// Effects applied when the node is entered.
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+    /// Answers of the listener. Without choices the dialogue continues with `next`.
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+    #[serde(default)]
+    pub next: Vec<String>,
+}
+
+/// Who says a [`DialogueNode`].
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogueSpeaker {
+    /// The entity owning the dialogue.
+    #[default]
+    Owner,
+    /// The entity which started the dialogue, usually the player.
+    Listener,
+    /// Someone else, given as localization key of the name.
+    Named(String),
+}
+
+/// Answer of a [`DialogueNode`]. Choices with unmet conditions are hidden.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DialogueChoice {
+    /// Localization key of the answer.
+    pub text: String,
+    #[serde(default)]
+    pub conditions: Vec<DialogueCondition>,
+    /// Effects applied when the choice is picked.
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+    #[serde(default)]
+    pub next: Vec<String>,
+}
+
+/// Condition of a node or choice, checked against the [`StoryFlags`] and the inventory
+/// of the listener.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DialogueCondition {
+    FlagSet { flag: String },
+    FlagUnset { flag: String },
+    HasItem { item: String, #[serde(default = "default_count")] count: u32 },
+    MissingItem { item: String, #[serde(default = "default_count")] count: u32 },
+}
+
+/// Effect of a node or choice.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DialogueEffect {
+    SetFlag { flag: String },
+    ClearFlag { flag: String },
+    /// Gives items to the listener.
+    GiveItem { item: String, #[serde(default = "default_count")] count: u32 },
+    /// Takes items from the listener.
+    TakeItem { item: String, #[serde(default = "default_count")] count: u32 },
+    StartQuest { quest: String },
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+/// World state conditions are checked against.
+pub struct DialogueContext<'a> {
+    pub flags: &'a StoryFlags,
+    /// Inventory of the listener, if it has one.
+    pub inventory: Option<&'a Inventory>,
+}
+
+impl DialogueContext<'_> {
+
+    pub fn is_met(&self, condition: &DialogueCondition) -> bool {
+        let count = |item: &str| self.inventory.map_or(0, |inventory| inventory.count(item));
+        match condition {
+            DialogueCondition::FlagSet { flag } => self.flags.is_set(flag),
+            DialogueCondition::FlagUnset { flag } => !self.flags.is_set(flag),
+            DialogueCondition::HasItem { item, count: needed } => count(item) >= *needed,
+            DialogueCondition::MissingItem { item, count: needed } => count(item) < *needed,
+        }
+    }
+
+    pub fn all_met(&self, conditions: &[DialogueCondition]) -> bool {
+        conditions.iter().all(|condition| self.is_met(condition))
+    }
+}
+
+// =================================================================================================
+//
+//                                            Runtime
+//
+// =================================================================================================
+
+/// Story progress flags set by dialogues and quests. Part of the save game.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Reflect, Deserialize, Serialize)]
+#[reflect(Resource)]
+pub struct StoryFlags(pub HashSet<String>);
+
+impl StoryFlags {
+
+    pub fn is_set(&self, flag: &str) -> bool {
+        self.0.contains(flag)
+    }
+
+    pub fn set(&mut self, flag: impl Into<String>) {
+        self.0.insert(flag.into());
+    }
+
+    pub fn clear(&mut self, flag: &str) {
+        self.0.remove(flag);
+    }
+}
+
+/// Dialogue started when an entity interacts with the owner.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct DialogueOwner(pub String);
+
+/// Running dialogue, driven by the dialogue logic and shown by the dialogue panel.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct DialogueState {
+    pub session: Option<DialogueSession>,
+}
+
+impl DialogueState {
+    pub fn is_active(&self) -> bool {
+        self.session.is_some()
+    }
+}
+
+/// Maximum number of choices offered at once. Further choices with met conditions are
+/// dropped, so every choice has a number key and a row in the dialogue panel.
+pub const MAX_DIALOGUE_CHOICES: usize = 4;
+
+/// One conversation between the owner of a dialogue and a listener.
+#[derive(Debug, Clone)]
+pub struct DialogueSession {
+    pub dialogue: String,
+    pub node: String,
+    pub owner: Entity,
+    pub listener: Entity,
+    /// Indices of the choices of the current node whose conditions are met, at most
+    /// [`MAX_DIALOGUE_CHOICES`].
+    pub choices: Vec<usize>,
+    /// Localized texts of the current node.
+    pub view: DialogueView,
+}
+
+/// Localized texts of the current node, ready to be displayed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DialogueView {
+    pub speaker: String,
+    pub text: String,
+    pub choices: Vec<String>,
+}
+
+/// Run condition pausing gameplay input while a dialogue is running.
+pub fn is_dialogue_inactive(state: Res<DialogueState>) -> bool {
+    !state.is_active()
+}
+
+// =================================================================================================
+//
+//                                           Requests
+//
+// =================================================================================================
+
+/// Request: `listener` starts the dialogue `dialogue` with `owner`.
+#[derive(Message, Debug, Clone)]
+pub struct StartDialogue {
+    pub dialogue: String,
+    pub owner: Entity,
+    pub listener: Entity,
+}
+
+/// Request: continues a node without visible choices with its `next` nodes.
+#[derive(Message, Debug, Clone)]
+pub struct ContinueDialogue;
+
+/// Request: picks the `index`-th visible choice of the current node.
+#[derive(Message, Debug, Clone)]
+pub struct ChooseDialogueOption {
+    pub index: usize,
+}
+
+/// Request: ends the running dialogue.
+#[derive(Message, Debug, Clone)]
+pub struct EndDialogue;
+
+// =================================================================================================
+//
+//                                        Notifications
+//
+// =================================================================================================
+
+#[derive(Message, Debug, Clone)]
+pub struct DialogueStarted {
+    pub dialogue: String,
+    pub owner: Entity,
+    pub listener: Entity,
+}
+
+#[derive(Message, Debug, Clone)]
+pub struct DialogueEnded {
+    pub dialogue: String,
+    pub owner: Entity,
+    pub listener: Entity,
+}
+
+/// A dialogue effect gave `item` to the listener or took it away.
+#[derive(Message, Debug, Clone)]
+pub struct DialogueItemsChanged {
+    pub listener: Entity,
+    pub item: String,
+}
@@ -1,5 +1,137 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::factions::Reputation;
+use crate::progression::{ProgressionDefinition, SkillNode};
 
 /// The character. Is not in the world! Look at `Player` for more information.
-#[derive(Component)]
-pub struct Character;
\ No newline at end of file
+///
+/// Holds the progression of the player which outlives the in world entity and is
+/// persisted as is.
+#[derive(Component, Debug, Clone, PartialEq, Reflect, Deserialize, Serialize)]
+#[reflect(Component)]
+pub struct Character {
+    /// Id of the [`ProgressionDefinition`] with the level curve and the skill tree.
+    pub progression: String,
+    pub level: u32,
+    /// Total experience gained, not reset on level up.
+    pub experience: u32,
+    /// Points which can be spent on skills.
+    pub skill_points: u32,
+    /// Ids of the unlocked skill nodes.
+    pub skills: Vec<String>,
+    /// Ids of the abilities unlocked by skills.
+    pub abilities: Vec<String>,
+    /// Standing with the factions.
+    #[serde(default)]
+    pub reputation: Reputation,
+}
+
+impl Default for Character {
+    fn default() -> Self {
+        Self {
+            progression: "player".to_string(),
+            level: 1,
+            experience: 0,
+            skill_points: 0,
+            skills: Vec::new(),
+            abilities: Vec::new(),
+            reputation: Reputation::default(),
+        }
+    }
+}
+
+impl Character {
+
+    /// Adds experience and raises the level while the thresholds of `definition` are
+    /// reached. Every level grants the skill points of the definition.
+    ///
+    /// # Returns
+    /// The number of gained levels.
+    pub fn add_experience(&mut self, amount: u32, definition: &ProgressionDefinition) -> u32 {
+        self.experience = self.experience.saturating_add(amount);
+        let mut gained = 0;
+        while self.level < definition.max_level
+            && self.experience >= definition.experience_for_level(self.level + 1) {
+            self.level += 1;
+            self.skill_points += definition.skill_points_per_level;
+            gained += 1;
+        }
+        gained
+    }
+
+    /// Experience still missing for the next level, `None` at the maximum level.
+    pub fn experience_to_next_level(&self, definition: &ProgressionDefinition) -> Option<u32> {
+        if self.level >= definition.max_level {
+            return None;
+        }
+        Some(definition.experience_for_level(self.level + 1).saturating_sub(self.experience))
+    }
+
+    pub fn has_skill(&self, skill: &str) -> bool {
+        self.skills.iter().any(|unlocked| unlocked == skill)
+    }
+
+    pub fn has_ability(&self, ability: &str) -> bool {
+        self.abilities.iter().any(|unlocked| unlocked == ability)
+    }
+
+    /// Spends the cost of `node` and unlocks it with its abilities. Check the node with
+    /// [`crate::progression::SkillTreeDefinition::check_unlock`] first.
+    pub fn unlock(&mut self, node: &SkillNode) {
+        self.skill_points = self.skill_points.saturating_sub(node.cost);
+        self.skills.push(node.id.clone());
+        for ability in node.unlocks.iter() {
+            if !self.has_ability(ability) {
+                self.abilities.push(ability.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progression::LevelCurve;
+
+    fn definition() -> ProgressionDefinition {
+        ProgressionDefinition {
+            id: "player".to_string(),
+            curve: LevelCurve { base: 100.0, exponent: 1.5 },
+            max_level: 4,
+            skill_points_per_level: 2,
+            skill_tree: "player".to_string(),
+        }
+    }
+
+    #[test]
+    fn experience_below_the_threshold_keeps_the_level() {
+        let mut character = Character::default();
+        assert_eq!(character.add_experience(99, &definition()), 0);
+        assert_eq!(character.level, 1);
+        assert_eq!(character.experience_to_next_level(&definition()), Some(1));
+    }
+
+    #[test]
+    fn experience_accumulates_over_several_gains() {
+        let mut character = Character::default();
+        assert_eq!(character.add_experience(150, &definition()), 1);
+        assert_eq!(character.add_experience(200, &definition()), 1);
+        assert_eq!(character.level, 3);
+        assert_eq!(character.experience, 350);
+        assert_eq!(character.skill_points, 4);
+        assert_eq!(character.experience_to_next_level(&definition()), Some(170));
+    }
+
+    #[test]
+    fn one_gain_can_raise_several_levels_up_to_the_max_level() {
+        let mut character = Character::default();
+        assert_eq!(character.add_experience(10_000, &definition()), 3);
+        assert_eq!(character.level, 4);
+        assert_eq!(character.skill_points, 6);
+        assert_eq!(character.experience_to_next_level(&definition()), None);
+
+        assert_eq!(character.add_experience(10_000, &definition()), 0);
+        assert_eq!(character.level, 4);
+        assert_eq!(character.experience, 20_000);
+    }
+}
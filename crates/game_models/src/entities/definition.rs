@@ -0,0 +1,493 @@
+use std::collections::HashMap;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::animation::CharacterAnimation;
+use crate::attributes::{AttributeKind, Attributes};
+use crate::combat::Combatant;
+use crate::definitions::{Definition, DefinitionLibrary};
+use crate::dialogue::DialogueOwner;
+use crate::entities::EntityBaseInformation;
+use crate::factions::Faction;
+use crate::health::{DeathBehaviour, Health, Resistances};
+use crate::interaction::Interactable;
+use crate::items::crafting::Workstation;
+use crate::items::loot::LootContainer;
+use crate::status_effects::{StatusAura, StatusEffects};
+use crate::survival::HeatSource;
+
+/// Data driven description of a spawnable entity (prefab). Authored as
+/// `assets/entities/<name>.entity.json` and hot reloaded by the asset server.
+#[derive(Asset, TypePath, Deserialize, Serialize, Debug, Clone)]
+pub struct EntityDefinition {
+    /// Unique id used by spawners and other definitions.
+    pub id: String,
+    /// Name shown in debug tools and logs.
+    pub display_name: String,
+    /// Localization key of the in game name.
+    pub localized_name: String,
+    /// Visual representation of the entity.
+    pub model: EntityModel,
+    /// Physics shape of the entity. `None` creates no collider.
+    #[serde(default)]
+    pub collider: Option<ColliderShape>,
+    /// Physics body type.
+    #[serde(default)]
+    pub body: BodyKind,
+    /// Base stats of the entity.
+    #[serde(default)]
+    pub stats: EntityStats,
+    /// Id of the AI profile which drives this entity.
+    #[serde(default)]
+    pub ai_profile: Option<String>,
+    /// Id of the loot table rolled when this entity dies.
+    #[serde(default)]
+    pub loot_table: Option<String>,
+    /// The loot table is rolled once on interaction instead of on death, e.g. chests.
+    #[serde(default)]
+    pub container: bool,
+    /// Id of the dialogue started when the player interacts with this entity.
+    #[serde(default)]
+    pub dialogue: Option<String>,
+    /// Kind of the crafting workstation this entity provides, e.g. `workbench`.
+    #[serde(default)]
+    pub workstation: Option<String>,
+    /// Warms characters nearby, e.g. a campfire.
+    #[serde(default)]
+    pub heat: Option<HeatSource>,
+    /// Id of the melee combo used without a weapon. Entities with a combo fight in melee.
+    #[serde(default)]
+    pub combo: Option<String>,
+    /// Status effect ids or tags the entity ignores.
+    #[serde(default)]
+    pub immunities: Vec<String>,
+    /// Status effect applied to characters nearby, e.g. burning next to a fire.
+    #[serde(default)]
+    pub aura: Option<StatusAura>,
+    /// Experience the player gains for killing this entity.
+    #[serde(default)]
+    pub experience: u32,
+    /// Id of the animation set of scene models.
+    #[serde(default)]
+    pub animations: Option<String>,
+    /// Id of the ragdoll built from the skeleton of scene models on death. Without one
+    /// the whole body ragdolls.
+    #[serde(default)]
+    pub ragdoll: Option<String>,
+    /// Id of the faction the entity belongs to. Without one the entity is neither friend
+    /// nor foe of anybody.
+    #[serde(default)]
+    pub faction: Option<String>,
+    /// What happens to the body on death. Defaults to despawning after 10 seconds.
+    #[serde(default = "default_death")]
+    pub death: DeathBehaviour,
+}
+
+fn default_death() -> DeathBehaviour {
+    DeathBehaviour::Despawn(10.0)
+}
+
+impl Definition for EntityDefinition {
+    const FOLDER: &'static str = "entities";
+    const EXTENSIONS: &'static [&'static str] = &["entity.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Visual part of an [`EntityDefinition`].
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EntityModel {
+    /// glTF scene loaded from the given asset path (first scene of the file).
+    Scene { path: String },
+    /// Simple box mesh, mostly used for prototypes.
+    Cuboid { size: [f32; 3], color: [f32; 3] },
+    /// Simple capsule mesh, mostly used for prototypes.
+    Capsule { radius: f32, length: f32, color: [f32; 3] },
+}
+
+impl EntityModel {
+
+    /// Mesh of the primitive models, `None` for scenes.
+    pub fn primitive_mesh(&self) -> Option<Mesh> {
+        match self {
+            EntityModel::Scene { .. } => None,
+            EntityModel::Cuboid { size: [x, y, z], .. } => Some(Mesh::from(Cuboid::new(*x, *y, *z))),
+            EntityModel::Capsule { radius, length, .. } => Some(Mesh::from(Capsule3d::new(*radius, *length))),
+        }
+    }
+}
+
+/// Collider shape of an [`EntityDefinition`].
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ColliderShape {
+    Cuboid { half_extents: [f32; 3] },
+    Capsule { half_height: f32, radius: f32 },
+    Ball { radius: f32 },
+}
+
+impl ColliderShape {
+
+    /// Creates the matching rapier [`Collider`].
+    pub fn to_collider(&self) -> Collider {
+        match self {
+            ColliderShape::Cuboid { half_extents: [x, y, z] } => Collider::cuboid(*x, *y, *z),
+            ColliderShape::Capsule { half_height, radius } => Collider::capsule_y(*half_height, *radius),
+            ColliderShape::Ball { radius } => Collider::ball(*radius),
+        }
+    }
+
+    /// Same collider reduced by `margin` on every side, used for overlap checks which
+    /// should ignore touching neighbours.
+    pub fn to_shrunk_collider(&self, margin: f32) -> Collider {
+        let shrink = |value: f32| (value - margin).max(0.01);
+        match self {
+            ColliderShape::Cuboid { half_extents: [x, y, z] } => Collider::cuboid(shrink(*x), shrink(*y), shrink(*z)),
+            ColliderShape::Capsule { half_height, radius } => Collider::capsule_y(shrink(*half_height), shrink(*radius)),
+            ColliderShape::Ball { radius } => Collider::ball(shrink(*radius)),
+        }
+    }
+
+    /// Distance from the center to the bottom of the shape.
+    pub fn half_height(&self) -> f32 {
+        match self {
+            ColliderShape::Cuboid { half_extents: [_, y, _] } => *y,
+            ColliderShape::Capsule { half_height, radius } => half_height + radius,
+            ColliderShape::Ball { radius } => *radius,
+        }
+    }
+
+    /// Half size of the bounding box of the unrotated shape.
+    pub fn half_extents(&self) -> Vec3 {
+        match self {
+            ColliderShape::Cuboid { half_extents } => Vec3::from_array(*half_extents),
+            ColliderShape::Capsule { half_height, radius } => Vec3::new(*radius, half_height + radius, *radius),
+            ColliderShape::Ball { radius } => Vec3::splat(*radius),
+        }
+    }
+}
+
+/// Physics body type of an [`EntityDefinition`].
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyKind {
+    /// Simulated body, rotation is locked so characters stay upright.
+    #[default]
+    Dynamic,
+    /// Static body which never moves.
+    Fixed,
+    /// Body which is moved by gameplay code.
+    Kinematic,
+}
+
+impl BodyKind {
+    pub fn to_rigid_body(self) -> RigidBody {
+        match self {
+            BodyKind::Dynamic => RigidBody::Dynamic,
+            BodyKind::Fixed => RigidBody::Fixed,
+            BodyKind::Kinematic => RigidBody::KinematicPositionBased,
+        }
+    }
+}
+
+/// Base stats of an entity as authored in its definition.
+/// Turned into the base values of [`Attributes`] on spawn.
+#[derive(Deserialize, Serialize, Debug, Clone, Reflect)]
+#[serde(default)]
+pub struct EntityStats {
+    pub max_health: f32,
+    pub move_speed: f32,
+    pub damage: f32,
+    pub armor: f32,
+}
+
+impl Default for EntityStats {
+    fn default() -> Self {
+        Self {
+            max_health: 100.0,
+            move_speed: 4.0,
+            damage: 10.0,
+            armor: 0.0,
+        }
+    }
+}
+
+/// Links a spawned entity to the definition it was created from.
+/// Used to re-apply the definition when the file is hot reloaded.
+#[derive(Component, Debug, Clone)]
+pub struct EntityDefinitionRef {
+    pub id: String,
+    pub handle: Handle<EntityDefinition>,
+}
+
+/// Id of the AI profile which drives this entity.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct AiProfile(pub String);
+
+/// Id of the loot table rolled when this entity dies, or when it is opened for a
+/// [`LootContainer`].
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct LootReference(pub String);
+
+/// Id of the ragdoll built on death.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct RagdollReference(pub String);
+
+/// Marks an [`Interactable`] added by the definition. It is removed again when a reload
+/// drops the dialogue or container it belongs to, other interactables stay.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct DefinitionInteractable;
+
+/// Removes the [`Interactable`] of `entity` if the definition added it.
+fn remove_definition_interactable(mut entity: EntityWorldMut) {
+    if entity.contains::<DefinitionInteractable>() {
+        entity.remove::<(Interactable, DefinitionInteractable)>();
+    }
+}
+
+/// Adds the open prompt of the [`LootContainer`] of `entity` unless it was looted already.
+fn insert_container_interactable(mut entity: EntityWorldMut) {
+    if entity.get::<LootContainer>().is_some_and(|container| !container.looted) {
+        entity.insert((Interactable::new("interact.open", 2.0), DefinitionInteractable));
+    }
+}
+
+/// Mesh and material of the primitive models per definition id, shared by all entities
+/// spawned from the same definition.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct DefinitionModels(HashMap<String, (Handle<Mesh>, Handle<StandardMaterial>)>);
+
+/// System parameter for creating fully configured entities from [`EntityDefinition`]s.
+///
+/// # Example
+/// ```rust,ignore
+/// fn spawn_ghoul(mut spawner: DefinitionSpawner) {
+///     spawner.spawn_from_definition("ghoul", Transform::from_xyz(4.0, 1.0, 0.0));
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct DefinitionSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    asset_server: Res<'w, AssetServer>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+    models: ResMut<'w, DefinitionModels>,
+    library: Res<'w, DefinitionLibrary<EntityDefinition>>,
+    definitions: Res<'w, Assets<EntityDefinition>>,
+}
+
+impl<'w, 's> DefinitionSpawner<'w, 's> {
+
+    /// Spawns a new entity from the definition with the given id.
+    ///
+    /// # Parameters
+    /// * `id` - Id of the [`EntityDefinition`].
+    /// * `transform` - Initial transform of the entity.
+    ///
+    /// # Returns
+    /// The spawned entity or `None` if the definition is not (yet) loaded.
+    pub fn spawn_from_definition(&mut self, id: &str, transform: Transform) -> Option<Entity> {
+        let Some(handle) = self.library.handle(id).cloned() else {
+            warn!("Entity definition '{}' is not loaded", id);
+            return None;
+        };
+
+        let max_health = self.definitions.get(&handle).map_or(0.0, |d| d.stats.max_health);
+        let entity = self.commands.spawn((
+            transform,
+            Health::new(max_health),
+            Resistances::default(),
+        )).id();
+        self.apply_definition(entity, &handle);
+        Some(entity)
+    }
+
+    /// Drops the shared model of the definition behind `handle`, so the next
+    /// [`Self::apply_definition`] builds it from the modified definition.
+    pub fn invalidate_model(&mut self, handle: impl Into<AssetId<EntityDefinition>>) {
+        if let Some(definition) = self.definitions.get(handle.into()) {
+            self.models.0.remove(&definition.id);
+        }
+    }
+
+    /// (Re-)applies the definition behind `handle` to an existing entity. Components
+    /// which are not part of the definition anymore are removed.
+    pub fn apply_definition(&mut self, entity: Entity, handle: &Handle<EntityDefinition>) {
+        let Some(definition) = self.definitions.get(handle) else {
+            return;
+        };
+
+        let mut entity_commands = self.commands.entity(entity);
+        entity_commands.insert((
+            Name::new(definition.display_name.clone()),
+            EntityBaseInformation {
+                id: entity,
+                display_name: definition.display_name.clone(),
+                localized_name: definition.localized_name.clone(),
+            },
+            EntityDefinitionRef {
+                id: definition.id.clone(),
+                handle: handle.clone(),
+            },
+            definition.body.to_rigid_body(),
+            definition.death.clone(),
+        ));
+
+        let stats = definition.stats.clone();
+        entity_commands
+            .entry::<Attributes>()
+            .and_modify(move |mut attributes| {
+                attributes.set_base(AttributeKind::MaxHealth, stats.max_health);
+                attributes.set_base(AttributeKind::MoveSpeed, stats.move_speed);
+                attributes.set_base(AttributeKind::Damage, stats.damage);
+                attributes.set_base(AttributeKind::Armor, stats.armor);
+            })
+            .or_insert(Attributes::from(&definition.stats));
+
+        if definition.body == BodyKind::Dynamic {
+            entity_commands.insert((LockedAxes::ROTATION_LOCKED, Velocity::default()));
+        }
+
+        match &definition.collider {
+            Some(shape) => { entity_commands.insert(shape.to_collider()); }
+            None => { entity_commands.remove::<Collider>(); }
+        }
+
+        match &definition.ai_profile {
+            Some(profile) => { entity_commands.insert(AiProfile(profile.clone())); }
+            None => { entity_commands.remove::<AiProfile>(); }
+        }
+
+        match &definition.loot_table {
+            Some(table) => { entity_commands.insert(LootReference(table.clone())); }
+            None => { entity_commands.remove::<LootReference>(); }
+        }
+
+        // Both would need the single interactable of the entity, the dialogue wins.
+        let container = definition.container && definition.loot_table.is_some();
+        if container && definition.dialogue.is_some() {
+            warn!("Entity definition '{}' has a dialogue and is a container, the container is ignored", definition.id);
+        }
+
+        match &definition.dialogue {
+            Some(dialogue) => {
+                entity_commands.insert((
+                    DialogueOwner(dialogue.clone()),
+                    Interactable::new("interact.talk", 2.5).with_priority(1),
+                    DefinitionInteractable,
+                ));
+            }
+            None => {
+                entity_commands
+                    .remove::<DialogueOwner>()
+                    .queue(remove_definition_interactable);
+            }
+        }
+
+        if container && definition.dialogue.is_none() {
+            entity_commands
+                .entry::<LootContainer>()
+                .or_default();
+            entity_commands.queue(insert_container_interactable);
+        } else {
+            entity_commands.remove::<LootContainer>();
+        }
+
+        match &definition.workstation {
+            Some(kind) => {
+                let updated_kind = kind.clone();
+                entity_commands
+                    .entry::<Workstation>()
+                    .and_modify(move |mut workstation| workstation.kind = updated_kind)
+                    .or_insert(Workstation::new(kind.clone(), 5));
+            }
+            None => { entity_commands.remove::<Workstation>(); }
+        }
+
+        match &definition.heat {
+            Some(heat) => { entity_commands.insert(heat.clone()); }
+            None => { entity_commands.remove::<HeatSource>(); }
+        }
+
+        match &definition.combo {
+            Some(combo) => {
+                let updated_combo = combo.clone();
+                entity_commands
+                    .entry::<Combatant>()
+                    .and_modify(move |mut combatant| combatant.combo = Some(updated_combo))
+                    .or_insert(Combatant::new(Some(combo.clone())));
+            }
+            None => { entity_commands.remove::<Combatant>(); }
+        }
+
+        let immunities = definition.immunities.clone();
+        entity_commands
+            .entry::<StatusEffects>()
+            .and_modify(move |mut effects| effects.immunities = immunities)
+            .or_insert(StatusEffects::with_immunities(definition.immunities.clone()));
+
+        match &definition.aura {
+            Some(aura) => { entity_commands.insert(aura.clone()); }
+            None => { entity_commands.remove::<StatusAura>(); }
+        }
+
+        match &definition.animations {
+            Some(set) => { entity_commands.insert(CharacterAnimation::new(set.clone())); }
+            None => { entity_commands.remove::<CharacterAnimation>(); }
+        }
+
+        match &definition.ragdoll {
+            Some(ragdoll) => { entity_commands.insert(RagdollReference(ragdoll.clone())); }
+            None => { entity_commands.remove::<RagdollReference>(); }
+        }
+
+        match &definition.faction {
+            Some(faction) => { entity_commands.insert(Faction(faction.clone())); }
+            None => { entity_commands.remove::<Faction>(); }
+        }
+
+        match &definition.model {
+            EntityModel::Scene { path } => {
+                let scene = self.asset_server.load(GltfAssetLabel::Scene(0).from_asset(path.clone()));
+                entity_commands
+                    .remove::<(Mesh3d, MeshMaterial3d<StandardMaterial>)>()
+                    .insert(SceneRoot(scene));
+            }
+            EntityModel::Cuboid { color, .. } | EntityModel::Capsule { color, .. } => {
+                let cached = self.models.0.get(&definition.id).cloned();
+                let model = cached.or_else(|| {
+                    let mesh = definition.model.primitive_mesh()?;
+                    let model = (self.meshes.add(mesh), self.materials.add(color_material(color)));
+                    self.models.0.insert(definition.id.clone(), model.clone());
+                    Some(model)
+                });
+                if let Some((mesh, material)) = model {
+                    entity_commands
+                        .remove::<SceneRoot>()
+                        .insert((Mesh3d(mesh), MeshMaterial3d(material)));
+                }
+            }
+        }
+    }
+
+    /// Direct access to the underlying [`Commands`], e.g. to add extra components
+    /// to an entity returned by [`Self::spawn_from_definition`].
+    pub fn commands(&mut self) -> &mut Commands<'w, 's> {
+        &mut self.commands
+    }
+}
+
+fn color_material(color: &[f32; 3]) -> StandardMaterial {
+    StandardMaterial {
+        base_color: Color::srgb(color[0], color[1], color[2]),
+        ..default()
+    }
+}
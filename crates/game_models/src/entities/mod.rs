@@ -1,18 +1,29 @@
 pub mod player;
 pub mod character;
+pub mod definition;
 
 use bevy::prelude::*;
+use crate::definitions::AppDefinitionExt;
+use crate::entities::definition::{AiProfile, DefinitionInteractable, DefinitionModels, EntityDefinition, LootReference, RagdollReference};
 
 pub struct EntitiesModule;
 
 impl Plugin for EntitiesModule {
 
     #[coverage(off)]
-    fn build(&self, _app: &mut App) {
+    fn build(&self, app: &mut App) {
+        app.register_definition::<EntityDefinition>();
+        app.init_resource::<DefinitionModels>();
+        app.register_type::<EntityBaseInformation>()
+            .register_type::<AiProfile>()
+            .register_type::<LootReference>()
+            .register_type::<RagdollReference>()
+            .register_type::<DefinitionInteractable>();
     }
 }
 
-#[derive(Debug, Clone, Reflect)]
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
 pub struct EntityBaseInformation {
     pub id: Entity,
     pub display_name: String,
@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::definitions::{AppDefinitionExt, Definition, DefinitionLibrary};
+use crate::entities::character::Character;
+
+pub struct FactionsModule;
+
+impl Plugin for FactionsModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<FactionDefinition>();
+        app.init_resource::<FactionSettings>();
+        app.add_message::<ChangeReputation>()
+            .add_message::<ReputationChanged>();
+        app.register_type::<FactionSettings>()
+            .register_type::<Faction>()
+            .register_type::<ThreatTable>()
+            .register_type::<Reputation>()
+            .register_type::<Relation>();
+    }
+}
+
+// =================================================================================================
+//
+//                                          Definition
+//
+// =================================================================================================
+
+/// Faction of the player, its structures and its allies.
+pub const PLAYER_FACTION: &str = "player";
+
+/// Standing of two factions towards each other. Ordered from worst to best, so the
+/// minimum of two relations is the more hostile one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Relation {
+    /// Attacked on sight.
+    Hostile,
+    /// Ignored until it attacks.
+    #[default]
+    Neutral,
+    /// Never attacked. Damage between friends is blocked unless friendly fire is enabled.
+    Friendly,
+}
+
+/// Group of entities sharing their enemies and friends. Authored as
+/// `assets/factions/<name>.faction.json`. The `relations` of all factions form the
+/// relationship matrix.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct FactionDefinition {
+    pub id: String,
+    /// Localization key of the name shown in the UI.
+    pub localized_name: String,
+    /// Relation towards factions which are not listed in `relations`.
+    #[serde(default)]
+    pub default_relation: Relation,
+    /// Relation towards other factions by id.
+    #[serde(default)]
+    pub relations: HashMap<String, Relation>,
+    /// The relation to the player follows the reputation instead of the matrix.
+    #[serde(default)]
+    pub reputation: Option<ReputationRules>,
+}
+
+impl Definition for FactionDefinition {
+    const FOLDER: &'static str = "factions";
+    const EXTENSIONS: &'static [&'static str] = &["faction.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl FactionDefinition {
+
+    /// Relation of this faction towards `other` as authored.
+    pub fn relation_to(&self, other: &str) -> Relation {
+        if self.id == other {
+            return Relation::Friendly;
+        }
+        self.relations.get(other).copied().unwrap_or(self.default_relation)
+    }
+}
+
+/// How the standing of the player with a faction starts and changes. Standings range
+/// from [`MIN_REPUTATION`] to [`MAX_REPUTATION`].
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ReputationRules {
+    /// Standing at the start of the game.
+    pub initial: i32,
+    /// The faction turns hostile below this standing.
+    pub hostile_below: i32,
+    /// The faction turns friendly from this standing.
+    pub friendly_from: i32,
+    /// Change when the player damages a member.
+    pub attack: i32,
+    /// Change when the player kills a member.
+    pub kill: i32,
+    /// Change when the player kills a member of a faction this one is hostile to.
+    pub kill_enemy: i32,
+}
+
+impl Default for ReputationRules {
+    fn default() -> Self {
+        Self {
+            initial: 0,
+            hostile_below: -25,
+            friendly_from: 25,
+            attack: -2,
+            kill: -20,
+            kill_enemy: 2,
+        }
+    }
+}
+
+impl ReputationRules {
+
+    pub fn relation(&self, standing: i32) -> Relation {
+        if standing < self.hostile_below {
+            Relation::Hostile
+        } else if standing >= self.friendly_from {
+            Relation::Friendly
+        } else {
+            Relation::Neutral
+        }
+    }
+}
+
+/// Relation between the factions `a` and `b`. Factions with reputation rules treat the
+/// player by its standing, all others use the more hostile relation of the matrix.
+pub fn relation_between(a: &FactionDefinition, b: &FactionDefinition, reputation: &Reputation) -> Relation {
+    if a.id == b.id {
+        return Relation::Friendly;
+    }
+    for (player, other) in [(a, b), (b, a)] {
+        if player.id == PLAYER_FACTION
+            && let Some(rules) = &other.reputation {
+            return rules.relation(reputation.standing(&other.id, rules));
+        }
+    }
+    a.relation_to(&b.id).min(b.relation_to(&a.id))
+}
+
+/// Reputation changes when the player kills a member of `victim`: the faction itself
+/// loses standing, factions hostile to it gain standing.
+pub fn kill_reputation<'a>(victim: &str, factions: impl IntoIterator<Item = &'a FactionDefinition>) -> Vec<(&'a str, i32)> {
+    factions
+        .into_iter()
+        .filter_map(|faction| {
+            let rules = faction.reputation.as_ref()?;
+            let amount = if faction.id == victim {
+                rules.kill
+            } else if faction.relation_to(victim) == Relation::Hostile {
+                rules.kill_enemy
+            } else {
+                0
+            };
+            (amount != 0).then_some((faction.id.as_str(), amount))
+        })
+        .collect()
+}
+
+/// Threat and friendly fire tuning. Editable at runtime in the inspector.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct FactionSettings {
+    /// Members of friendly factions can damage each other.
+    pub friendly_fire: bool,
+    /// Threat gained per point of damage taken.
+    pub damage_threat: f32,
+    /// Fraction of the threat gained by members of the same faction within `assist_radius`.
+    pub assist_threat: f32,
+    pub assist_radius: f32,
+    /// Threat lost per second.
+    pub threat_decay: f32,
+}
+
+impl Default for FactionSettings {
+    fn default() -> Self {
+        Self {
+            friendly_fire: false,
+            damage_threat: 1.0,
+            assist_threat: 0.5,
+            assist_radius: 12.0,
+            threat_decay: 2.0,
+        }
+    }
+}
+
+/// Relations of entities by their [`Faction`], including the reputation of the player.
+#[derive(SystemParam)]
+pub struct Factions<'w, 's> {
+    library: Res<'w, DefinitionLibrary<FactionDefinition>>,
+    assets: Res<'w, Assets<FactionDefinition>>,
+    settings: Res<'w, FactionSettings>,
+    members: Query<'w, 's, &'static Faction>,
+    characters: Query<'w, 's, &'static Character>,
+}
+
+impl<'w, 's> Factions<'w, 's> {
+
+    pub fn get(&self, id: &str) -> Option<&FactionDefinition> {
+        self.library.get(id, &self.assets)
+    }
+
+    /// All loaded faction definitions.
+    pub fn iter(&self) -> impl Iterator<Item = &FactionDefinition> {
+        self.library.ids().filter_map(|id| self.get(id))
+    }
+
+    /// Faction id of `entity`.
+    pub fn faction_of(&self, entity: Entity) -> Option<&str> {
+        self.members.get(entity).ok().map(|faction| faction.0.as_str())
+    }
+
+    /// Relation between the factions `a` and `b`. Unknown factions are neutral to all
+    /// others.
+    pub fn relation(&self, a: &str, b: &str) -> Relation {
+        if a == b {
+            return Relation::Friendly;
+        }
+        let (Some(a), Some(b)) = (self.get(a), self.get(b)) else {
+            return Relation::Neutral;
+        };
+        match self.characters.single() {
+            Ok(character) => relation_between(a, b, &character.reputation),
+            Err(_) => relation_between(a, b, &Reputation::default()),
+        }
+    }
+
+    /// Relation between the entities `a` and `b`, `None` if one of them has no faction.
+    pub fn relation_of(&self, a: Entity, b: Entity) -> Option<Relation> {
+        Some(self.relation(self.faction_of(a)?, self.faction_of(b)?))
+    }
+
+    /// Whether `attacker` can damage `target`. Only friends are protected and only while
+    /// friendly fire is disabled.
+    pub fn can_damage(&self, attacker: Entity, target: Entity) -> bool {
+        attacker == target
+            || self.settings.friendly_fire
+            || self.relation_of(attacker, target) != Some(Relation::Friendly)
+    }
+}
+
+// =================================================================================================
+//
+//                                            Runtime
+//
+// =================================================================================================
+
+/// Lowest standing of the player with a faction.
+pub const MIN_REPUTATION: i32 = -100;
+/// Highest standing of the player with a faction.
+pub const MAX_REPUTATION: i32 = 100;
+
+/// Membership of an entity in a [`FactionDefinition`].
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Faction(pub String);
+
+impl Faction {
+    pub fn player() -> Self {
+        Self(PLAYER_FACTION.to_string())
+    }
+}
+
+/// Standing of the player with every faction with [`ReputationRules`]. Part of the
+/// [`Character`], factions without an entry are at their initial standing.
+#[derive(Debug, Clone, Default, PartialEq, Reflect, Deserialize, Serialize)]
+pub struct Reputation {
+    standings: HashMap<String, i32>,
+}
+
+impl Reputation {
+
+    pub fn standing(&self, faction: &str, rules: &ReputationRules) -> i32 {
+        self.standings.get(faction).copied().unwrap_or(rules.initial)
+    }
+
+    /// Changes the standing with `faction` within [`MIN_REPUTATION`] and
+    /// [`MAX_REPUTATION`].
+    ///
+    /// # Returns
+    /// The new standing.
+    pub fn change(&mut self, faction: &str, amount: i32, rules: &ReputationRules) -> i32 {
+        let standing = (self.standing(faction, rules) + amount).clamp(MIN_REPUTATION, MAX_REPUTATION);
+        self.standings.insert(faction.to_string(), standing);
+        standing
+    }
+}
+
+/// Threat of the entities which attacked an AI. The AI prefers the target with the most
+/// threat and even attacks neutral entities while they have threat.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct ThreatTable {
+    threat: HashMap<Entity, f32>,
+}
+
+impl ThreatTable {
+
+    pub fn threat(&self, entity: Entity) -> f32 {
+        self.threat.get(&entity).copied().unwrap_or(0.0)
+    }
+
+    pub fn add(&mut self, entity: Entity, amount: f32) {
+        *self.threat.entry(entity).or_default() += amount;
+    }
+
+    /// Lowers all threat by `amount` and forgets entities without threat or for which
+    /// `keep` fails, e.g. dead ones.
+    pub fn decay(&mut self, amount: f32, keep: impl Fn(Entity) -> bool) {
+        self.threat.retain(|entity, threat| {
+            *threat -= amount;
+            *threat > 0.0 && keep(*entity)
+        });
+    }
+
+    /// Target among `candidates` with their distance: the one with the most threat,
+    /// the nearest one if none has threat.
+    pub fn select(&self, candidates: impl IntoIterator<Item = (Entity, f32)>) -> Option<Entity> {
+        candidates
+            .into_iter()
+            .map(|(entity, distance)| (entity, self.threat(entity), distance))
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.2.total_cmp(&a.2)))
+            .map(|(entity, ..)| entity)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.threat.is_empty()
+    }
+}
+
+// =================================================================================================
+//
+//                                           Requests
+//
+// =================================================================================================
+
+/// What changed the reputation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReputationSource {
+    /// Damaged a member of the given faction.
+    Attack(String),
+    /// Killed a member of the given faction.
+    Kill(String),
+    /// Completed the given quest.
+    Quest(String),
+}
+
+/// Changes the standing of the player with `faction` by `amount`.
+#[derive(Message, Debug, Clone)]
+pub struct ChangeReputation {
+    pub faction: String,
+    pub amount: i32,
+    pub source: ReputationSource,
+}
+
+// =================================================================================================
+//
+//                                        Notifications
+//
+// =================================================================================================
+
+/// The standing of the player with `faction` changed.
+#[derive(Message, Debug, Clone)]
+pub struct ReputationChanged {
+    pub faction: String,
+    pub standing: i32,
+    pub from: Relation,
+    pub to: Relation,
+}
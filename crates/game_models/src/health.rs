@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::status_effects::StatusEffects;
+
+pub struct HealthModule;
+
+impl Plugin for HealthModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.add_message::<DamageEvent>()
+            .add_message::<DamageApplied>()
+            .add_message::<DeathEvent>();
+        app.register_type::<Health>()
+            .register_type::<Resistances>()
+            .register_type::<Invulnerable>()
+            .register_type::<InvulnerabilityFrames>()
+            .register_type::<DeathBehaviour>()
+            .register_type::<Dead>();
+    }
+}
+
+/// Kind of damage dealt by a [`DamageEvent`]. Used to look up [`Resistances`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DamageType {
+    Physical,
+    Fire,
+    Cold,
+    Poison,
+    Bleed,
+    /// Ignores resistances and armor.
+    True,
+}
+
+/// Hit points of an entity. The entity dies when `current` reaches zero.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+#[require(StatusEffects)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+
+    /// Creates a full health pool with the given maximum.
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    /// Removes `amount` hit points (never below zero) and returns the damage actually taken.
+    pub fn take_damage(&mut self, amount: f32) -> f32 {
+        let taken = amount.max(0.0).min(self.current);
+        self.current -= taken;
+        taken
+    }
+
+    /// Restores `amount` hit points (never above `max`) and returns the amount actually healed.
+    pub fn heal(&mut self, amount: f32) -> f32 {
+        let healed = amount.max(0.0).min(self.max - self.current);
+        self.current += healed;
+        healed
+    }
+
+    /// Changes the maximum and keeps the current health in range.
+    pub fn set_max(&mut self, max: f32) {
+        self.max = max.max(0.0);
+        self.current = self.current.min(self.max);
+    }
+
+    pub fn is_depleted(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    /// Current health in range `0.0..=1.0`.
+    pub fn fraction(&self) -> f32 {
+        if self.max <= 0.0 { 0.0 } else { self.current / self.max }
+    }
+}
+
+/// Damage reduction per [`DamageType`] as a fraction (`0.25` = 25% less damage, `1.0` = immune).
+/// Negative values make the entity take more damage.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct Resistances {
+    pub values: HashMap<DamageType, f32>,
+}
+
+impl Resistances {
+
+    /// Returns `amount` reduced by the resistance against `damage_type`.
+    /// [`DamageType::True`] is never reduced.
+    pub fn mitigate(&self, damage_type: DamageType, amount: f32) -> f32 {
+        if damage_type == DamageType::True {
+            return amount;
+        }
+        let resistance = self.values.get(&damage_type).copied().unwrap_or(0.0).min(1.0);
+        amount * (1.0 - resistance)
+    }
+}
+
+/// Reduces physical damage by `armor`. 100 armor halves the damage, mitigation follows
+/// `100 / (100 + armor)`, so armor never grants full immunity.
+pub fn armor_mitigation(armor: f32, amount: f32) -> f32 {
+    amount * 100.0 / (100.0 + armor.max(0.0))
+}
+
+/// Grants invulnerability for `duration` seconds after each received hit.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct InvulnerabilityFrames {
+    pub duration: f32,
+}
+
+impl Default for InvulnerabilityFrames {
+    fn default() -> Self {
+        Self { duration: 0.5 }
+    }
+}
+
+/// Entity currently ignores all incoming damage. Removed when the timer finishes.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Invulnerable(pub Timer);
+
+/// Describes what happens to a non-player entity when it dies. Authored in snake case,
+/// e.g. `{ "ragdoll": 10.0 }`.
+#[derive(Component, Debug, Clone, PartialEq, Reflect, Deserialize, Serialize)]
+#[reflect(Component)]
+#[serde(rename_all = "snake_case")]
+pub enum DeathBehaviour {
+    /// Despawn after the given delay in seconds.
+    Despawn(f32),
+    /// Keep the body in the world as a ragdoll, cleaned up after the given delay in seconds.
+    Ragdoll(f32),
+    /// Keep the entity in the world.
+    Keep,
+}
+
+impl Default for DeathBehaviour {
+    fn default() -> Self {
+        DeathBehaviour::Despawn(0.0)
+    }
+}
+
+/// Marker for dead entities. Dead entities don't receive damage anymore.
+/// The timer counts down until the body is removed (if the [`DeathBehaviour`] requires it).
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Dead {
+    pub cleanup: Option<Timer>,
+}
+
+/// Request to damage `target`. Processed once per frame by the health logic.
+#[derive(Message, Debug, Clone)]
+pub struct DamageEvent {
+    /// Entity receiving the damage.
+    pub target: Entity,
+    /// Entity which caused the damage, if any (e.g. attacker, trap, fire).
+    pub source: Option<Entity>,
+    pub damage_type: DamageType,
+    /// Raw damage before resistances.
+    pub amount: f32,
+}
+
+/// A [`DamageEvent`] got through friendly fire and invulnerability and `target` lost
+/// `amount` health, after resistances and armor.
+#[derive(Message, Debug, Clone)]
+pub struct DamageApplied {
+    pub target: Entity,
+    pub source: Option<Entity>,
+    pub damage_type: DamageType,
+    /// Health actually lost.
+    pub amount: f32,
+}
+
+/// Emitted once when an entity's health reaches zero.
+#[derive(Message, Debug, Clone)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    /// Source of the killing blow.
+    pub killer: Option<Entity>,
+    /// Damage type of the killing blow.
+    pub damage_type: DamageType,
+}
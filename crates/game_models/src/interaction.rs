@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+
+pub struct InteractionModule;
+
+impl Plugin for InteractionModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InteractionSettings>();
+        app.add_message::<Interacted>();
+        app.register_type::<InteractionSettings>()
+            .register_type::<Interactable>()
+            .register_type::<Interactor>()
+            .register_type::<InteractionSensor>()
+            .register_type::<InteractionPrompt>();
+    }
+}
+
+/// Something in the world the player can interact with, e.g. a door, a pickup or an NPC.
+///
+/// A sensor collider with the radius `range` is attached as child on spawn. Entities
+/// with an [`Interactor`] entering it become candidates, the best one gets the focus.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Interactable {
+    /// Localization key of the prompt text, e.g. `interact.open_door`.
+    pub prompt: String,
+    /// Candidates with a higher priority win regardless of the view direction.
+    pub priority: i32,
+    /// Radius of the proximity sensor.
+    pub range: f32,
+    /// Disabled interactables stay in range but can't be focused.
+    pub enabled: bool,
+}
+
+impl Interactable {
+    pub fn new(prompt: impl Into<String>, range: f32) -> Self {
+        Self {
+            prompt: prompt.into(),
+            priority: 0,
+            range,
+            enabled: true,
+        }
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// Entity which can interact, usually the player. Needs a collider to be detected
+/// by the sensors of [`Interactable`]s.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct Interactor {
+    /// Interactables whose sensor currently overlaps the interactor.
+    pub in_range: Vec<Entity>,
+    /// Best candidate, triggered by the interact key.
+    pub focused: Option<Entity>,
+}
+
+impl Interactor {
+
+    pub fn enter(&mut self, target: Entity) {
+        if !self.in_range.contains(&target) {
+            self.in_range.push(target);
+        }
+    }
+
+    pub fn leave(&mut self, target: Entity) {
+        self.in_range.retain(|entity| *entity != target);
+        if self.focused == Some(target) {
+            self.focused = None;
+        }
+    }
+}
+
+/// Sensor child of an [`Interactable`], maps collision events back to its owner.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct InteractionSensor {
+    pub owner: Entity,
+}
+
+/// On-screen text showing the interact key and the prompt of the focused [`Interactable`].
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct InteractionPrompt;
+
+/// Tuning of the candidate selection.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct InteractionSettings {
+    /// Minimum cosine between the view direction and the direction to a candidate.
+    /// Candidates behind the camera are ignored.
+    pub min_facing: f32,
+    /// Weight of the distance against the view direction when two candidates have
+    /// the same priority.
+    pub distance_weight: f32,
+}
+
+impl Default for InteractionSettings {
+    fn default() -> Self {
+        Self {
+            min_facing: 0.0,
+            distance_weight: 0.1,
+        }
+    }
+}
+
+impl InteractionSettings {
+
+    /// Rates a candidate, higher is better. `None` if the candidate is out of view.
+    ///
+    /// # Parameters
+    /// * `view` - Normalized view direction.
+    /// * `offset` - Offset from the interactor to the candidate.
+    pub fn score(&self, view: Vec3, offset: Vec3) -> Option<f32> {
+        let facing = view.dot(offset.normalize_or_zero());
+        if facing < self.min_facing {
+            return None;
+        }
+        Some(facing - offset.length() * self.distance_weight)
+    }
+}
+
+/// Emitted when `interactor` used the interact key on `target`. Doors, pickups and
+/// NPCs listen for it and filter by their own components.
+#[derive(Message, Debug, Clone)]
+pub struct Interacted {
+    pub interactor: Entity,
+    pub target: Entity,
+}
@@ -0,0 +1,340 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+use crate::definitions::{Definition, DefinitionLibrary};
+use crate::items::equipment::Equipment;
+use crate::items::inventory::{Inventory, ItemStack};
+
+/// Kind of the [`Workstation`] every crafter carries, used by recipes without a workstation.
+pub const HAND_WORKSTATION: &str = "hand";
+/// Maximum distance between a crafter and the workstation it queues jobs at.
+pub const CRAFTING_RANGE: f32 = 3.0;
+/// Fraction of a job after which [`CraftingProgressed`] is written again.
+pub const CRAFTING_PROGRESS_STEP: f32 = 0.1;
+
+/// Data driven crafting recipe. Authored as `assets/recipes/<name>.recipe.json`.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct RecipeDefinition {
+    pub id: String,
+    /// Items consumed when the recipe is queued.
+    pub inputs: Vec<ItemStack>,
+    /// Items given to the crafter when the job finishes.
+    pub outputs: Vec<ItemStack>,
+    /// Seconds the job takes.
+    #[serde(default)]
+    pub time: f32,
+    /// Kind of the [`Workstation`] needed. `None` can be crafted at any workstation
+    /// and by hand.
+    #[serde(default)]
+    pub workstation: Option<String>,
+    /// Item id which has to be carried or equipped. Tools are not consumed.
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Experience the player gains when the job finishes.
+    #[serde(default)]
+    pub experience: u32,
+}
+
+impl Definition for RecipeDefinition {
+    const FOLDER: &'static str = "recipes";
+    const EXTENSIONS: &'static [&'static str] = &["recipe.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl RecipeDefinition {
+
+    /// Inputs with the counts of the same item summed up, in the order of their first
+    /// occurrence.
+    pub fn total_inputs(&self) -> Vec<ItemStack> {
+        let mut totals: Vec<ItemStack> = Vec::with_capacity(self.inputs.len());
+        for input in self.inputs.iter() {
+            match totals.iter_mut().find(|total| total.item == input.item) {
+                Some(total) => total.count += input.count,
+                None => totals.push(input.clone()),
+            }
+        }
+        totals
+    }
+
+    /// Checks whether the recipe can be queued at `workstation` by a crafter with the
+    /// given inventory and equipment.
+    pub fn validate(
+        &self,
+        workstation: &Workstation,
+        inventory: &Inventory,
+        equipment: Option<&Equipment>,
+    ) -> Result<(), CraftingError> {
+        if let Some(kind) = &self.workstation
+            && *kind != workstation.kind {
+            return Err(CraftingError::WrongWorkstation { required: kind.clone(), found: workstation.kind.clone() });
+        }
+        if workstation.is_full() {
+            return Err(CraftingError::QueueFull);
+        }
+        if let Some(tool) = &self.tool
+            && !inventory.contains(tool, 1)
+            && !equipment.is_some_and(|equipment| equipment.is_equipped(tool)) {
+            return Err(CraftingError::MissingTool(tool.clone()));
+        }
+        for input in self.total_inputs() {
+            let available = inventory.count(&input.item);
+            if available < input.count {
+                return Err(CraftingError::MissingInput {
+                    item: input.item,
+                    required: input.count,
+                    available,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reasons a recipe can't be crafted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CraftingError {
+    /// The recipe is not loaded.
+    UnknownRecipe(String),
+    /// The target entity is no workstation or the crafter has no inventory.
+    InvalidTarget,
+    /// The crafter is further than [`CRAFTING_RANGE`] away from the workstation.
+    TooFar,
+    WrongWorkstation { required: String, found: String },
+    /// The queue of the workstation has no free place.
+    QueueFull,
+    MissingTool(String),
+    MissingInput { item: String, required: u32, available: u32 },
+}
+
+impl Display for CraftingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CraftingError::UnknownRecipe(recipe) => write!(f, "Recipe '{}' is not loaded", recipe),
+            CraftingError::InvalidTarget => write!(f, "Target is no workstation or crafter has no inventory"),
+            CraftingError::TooFar => write!(f, "Workstation is out of reach"),
+            CraftingError::WrongWorkstation { required, found } => {
+                write!(f, "Recipe needs workstation '{}', found '{}'", required, found)
+            }
+            CraftingError::QueueFull => write!(f, "Crafting queue is full"),
+            CraftingError::MissingTool(tool) => write!(f, "Tool '{}' is missing", tool),
+            CraftingError::MissingInput { item, required, available } => {
+                write!(f, "Not enough '{}': required {}, available {}", item, required, available)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CraftingError {}
+
+/// Job in the queue of a [`Workstation`]. The inputs are already consumed.
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct CraftingJob {
+    pub recipe: String,
+    /// Entity receiving the outputs.
+    pub crafter: Entity,
+    /// Seconds of work done.
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl CraftingJob {
+
+    /// Progress in range `0.0..=1.0`.
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            (self.elapsed / self.duration).clamp(0.0, 1.0)
+        }
+    }
+
+    /// Number of [`CRAFTING_PROGRESS_STEP`]s done.
+    pub fn progress_step(&self) -> u32 {
+        (self.progress() / CRAFTING_PROGRESS_STEP).floor() as u32
+    }
+}
+
+/// Entity which crafts recipes one after another, e.g. a workbench or a campfire.
+/// Characters carry a workstation of the kind [`HAND_WORKSTATION`].
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Workstation {
+    pub kind: String,
+    /// Queued jobs, the first one is worked on.
+    pub queue: VecDeque<CraftingJob>,
+    pub max_queue: usize,
+}
+
+impl Workstation {
+    pub fn new(kind: impl Into<String>, max_queue: usize) -> Self {
+        Self {
+            kind: kind.into(),
+            queue: VecDeque::new(),
+            max_queue,
+        }
+    }
+
+    /// Workstation for recipes crafted by hand.
+    pub fn hand() -> Self {
+        Self::new(HAND_WORKSTATION, 3)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.queue.len() >= self.max_queue
+    }
+
+    /// Works `delta` seconds on the first job.
+    ///
+    /// # Returns
+    /// The finished job, if any.
+    pub fn tick(&mut self, delta: f32) -> Option<CraftingJob> {
+        let job = self.queue.front_mut()?;
+        job.elapsed += delta;
+        if job.elapsed < job.duration {
+            return None;
+        }
+        self.queue.pop_front()
+    }
+}
+
+/// Read only access to all loaded [`RecipeDefinition`]s by id.
+#[derive(SystemParam)]
+pub struct Recipes<'w> {
+    library: Res<'w, DefinitionLibrary<RecipeDefinition>>,
+    assets: Res<'w, Assets<RecipeDefinition>>,
+}
+
+impl<'w> Recipes<'w> {
+
+    pub fn get(&self, id: &str) -> Option<&RecipeDefinition> {
+        self.library.get(id, &self.assets)
+    }
+
+    /// Ids of all recipes which can be crafted at a workstation of the given kind.
+    pub fn for_workstation<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.library.ids().filter(move |id| self
+            .get(id)
+            .is_some_and(|recipe| recipe.workstation.as_deref().is_none_or(|required| required == kind)))
+    }
+}
+
+/// Request: `crafter` queues `recipe` at `workstation`. The inputs are taken from the
+/// inventory of the crafter.
+#[derive(Message, Debug, Clone)]
+pub struct CraftItem {
+    pub crafter: Entity,
+    pub workstation: Entity,
+    pub recipe: String,
+}
+
+/// Request: removes the job at `index` from the queue of `workstation` and refunds its inputs.
+#[derive(Message, Debug, Clone)]
+pub struct CancelCrafting {
+    pub workstation: Entity,
+    pub index: usize,
+}
+
+/// A recipe was validated and added to the queue of `workstation`.
+#[derive(Message, Debug, Clone)]
+pub struct CraftingQueued {
+    pub crafter: Entity,
+    pub workstation: Entity,
+    pub recipe: String,
+}
+
+/// Progress of the active job of `workstation`, written when a job starts and whenever
+/// it passed another [`CRAFTING_PROGRESS_STEP`].
+#[derive(Message, Debug, Clone)]
+pub struct CraftingProgressed {
+    pub workstation: Entity,
+    pub recipe: String,
+    /// Progress in range `0.0..=1.0`.
+    pub progress: f32,
+    /// Jobs waiting behind the active one.
+    pub queued: usize,
+}
+
+/// A job finished and its outputs were handed to `crafter`.
+#[derive(Message, Debug, Clone)]
+pub struct CraftingCompleted {
+    pub crafter: Entity,
+    pub workstation: Entity,
+    pub recipe: String,
+    pub outputs: Vec<ItemStack>,
+}
+
+/// A craft request was rejected.
+#[derive(Message, Debug, Clone)]
+pub struct CraftingFailed {
+    pub crafter: Entity,
+    pub recipe: String,
+    pub error: CraftingError,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(inputs: Vec<ItemStack>) -> RecipeDefinition {
+        RecipeDefinition {
+            id: "test".to_string(),
+            inputs,
+            outputs: vec![ItemStack::new("plank", 1)],
+            time: 1.0,
+            workstation: Some("workbench".to_string()),
+            tool: Some("saw".to_string()),
+            experience: 0,
+        }
+    }
+
+    fn inventory(stacks: &[(&str, u32)]) -> Inventory {
+        let mut inventory = Inventory::new(10);
+        for (item, count) in stacks {
+            inventory.add(item, *count, |_| 99);
+        }
+        inventory
+    }
+
+    #[test]
+    fn total_inputs_sum_duplicate_items() {
+        let recipe = recipe(vec![ItemStack::new("wood", 2), ItemStack::new("nail", 1), ItemStack::new("wood", 3)]);
+        assert_eq!(recipe.total_inputs(), vec![ItemStack::new("wood", 5), ItemStack::new("nail", 1)]);
+    }
+
+    #[test]
+    fn validate_checks_the_summed_inputs() {
+        let recipe = recipe(vec![ItemStack::new("wood", 2), ItemStack::new("wood", 3)]);
+        let workbench = Workstation::new("workbench", 2);
+
+        assert_eq!(
+            recipe.validate(&workbench, &inventory(&[("saw", 1), ("wood", 4)]), None),
+            Err(CraftingError::MissingInput { item: "wood".to_string(), required: 5, available: 4 }),
+        );
+        assert_eq!(recipe.validate(&workbench, &inventory(&[("saw", 1), ("wood", 5)]), None), Ok(()));
+    }
+
+    #[test]
+    fn validate_checks_workstation_queue_and_tool() {
+        let recipe = recipe(vec![ItemStack::new("wood", 1)]);
+        let materials = inventory(&[("saw", 1), ("wood", 1)]);
+
+        assert_eq!(
+            recipe.validate(&Workstation::hand(), &materials, None),
+            Err(CraftingError::WrongWorkstation { required: "workbench".to_string(), found: HAND_WORKSTATION.to_string() }),
+        );
+
+        let mut full = Workstation::new("workbench", 1);
+        full.queue.push_back(CraftingJob { recipe: "test".to_string(), crafter: Entity::PLACEHOLDER, elapsed: 0.0, duration: 1.0 });
+        assert_eq!(recipe.validate(&full, &materials, None), Err(CraftingError::QueueFull));
+
+        assert_eq!(
+            recipe.validate(&Workstation::new("workbench", 1), &inventory(&[("wood", 1)]), None),
+            Err(CraftingError::MissingTool("saw".to_string())),
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::attributes::AttributeModifier;
+use crate::projectiles::RangedData;
+
+/// Slots of the [`Equipment`] component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EquipmentSlot {
+    Weapon,
+    Offhand,
+    Head,
+    Body,
+    LightSource,
+}
+
+impl EquipmentSlot {
+
+    /// Name of the bone/child the item model is attached to if the item doesn't specify one.
+    pub fn default_bone(&self) -> &'static str {
+        match self {
+            EquipmentSlot::Weapon => "hand_r",
+            EquipmentSlot::Offhand | EquipmentSlot::LightSource => "hand_l",
+            EquipmentSlot::Head => "head",
+            EquipmentSlot::Body => "spine",
+        }
+    }
+
+    /// Source id of the attribute modifiers applied while an item is equipped in this slot.
+    pub fn modifier_source(&self) -> String {
+        format!("equipment:{:?}", self).to_lowercase()
+    }
+}
+
+/// Equipment part of an `ItemDefinition`. Only items with this data can be equipped.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EquipmentData {
+    /// Slot the item is equipped into.
+    pub slot: EquipmentSlot,
+    /// Weapon which occupies the offhand as well.
+    #[serde(default)]
+    pub two_handed: bool,
+    /// Modifiers applied while the item is equipped. The `source` is set on equip.
+    #[serde(default)]
+    pub modifiers: Vec<AttributeModifier>,
+    /// glTF scene of the item which is attached to the wearer.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Name of the bone/child the model is attached to. Defaults to [`EquipmentSlot::default_bone`].
+    #[serde(default)]
+    pub attach_to: Option<String>,
+    /// Id of the melee combo used while the item is equipped as weapon.
+    #[serde(default)]
+    pub combo: Option<String>,
+    /// Projectile, ammo and fire rate of ranged weapons.
+    #[serde(default)]
+    pub ranged: Option<RangedData>,
+}
+
+/// Currently equipped items by slot.
+#[derive(Component, Debug, Clone, Default, Reflect, Deserialize, Serialize)]
+#[reflect(Component)]
+pub struct Equipment {
+    /// Item id per occupied slot.
+    pub items: HashMap<EquipmentSlot, String>,
+    /// Spawned model entity per occupied slot.
+    #[serde(skip)]
+    pub visuals: HashMap<EquipmentSlot, Entity>,
+}
+
+impl Equipment {
+
+    pub fn get(&self, slot: EquipmentSlot) -> Option<&str> {
+        self.items.get(&slot).map(String::as_str)
+    }
+
+    pub fn is_equipped(&self, item: &str) -> bool {
+        self.items.values().any(|i| i == item)
+    }
+}
+
+/// Model of an equipped item. Re-parented to the bone named `bone` as soon as
+/// the wearer's scene contains it.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct EquipmentVisual {
+    pub wearer: Entity,
+    pub slot: EquipmentSlot,
+    pub bone: String,
+    pub attached: bool,
+}
+
+/// Request: equip the item in `inventory_slot` of the entity's inventory.
+#[derive(Message, Debug, Clone)]
+pub struct EquipItem {
+    pub entity: Entity,
+    pub inventory_slot: usize,
+}
+
+/// Request: move the item of `slot` back into the entity's inventory.
+#[derive(Message, Debug, Clone)]
+pub struct UnequipItem {
+    pub entity: Entity,
+    pub slot: EquipmentSlot,
+}
+
+/// An item was equipped by `entity`.
+#[derive(Message, Debug, Clone)]
+pub struct ItemEquipped {
+    pub entity: Entity,
+    pub slot: EquipmentSlot,
+    pub item: String,
+}
+
+/// An item was unequipped by `entity`.
+#[derive(Message, Debug, Clone)]
+pub struct ItemUnequipped {
+    pub entity: Entity,
+    pub slot: EquipmentSlot,
+    pub item: String,
+}
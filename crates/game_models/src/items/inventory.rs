@@ -0,0 +1,239 @@
+use std::fmt::{Display, Formatter};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Amount of a single item in an inventory slot or in the world.
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Deserialize, Serialize)]
+pub struct ItemStack {
+    pub item: String,
+    pub count: u32,
+}
+
+impl ItemStack {
+    pub fn new(item: impl Into<String>, count: u32) -> Self {
+        Self { item: item.into(), count }
+    }
+}
+
+/// Errors returned by [`Inventory`] operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InventoryError {
+    /// The slot index is out of range.
+    InvalidSlot(usize),
+    /// The slot holds no item.
+    EmptySlot(usize),
+    /// Not enough items to fulfill the request.
+    NotEnough { item: String, requested: u32, available: u32 },
+    /// The amount is zero or can't be split from the stack.
+    InvalidAmount(u32),
+    /// No free slot left.
+    Full,
+}
+
+impl Display for InventoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InventoryError::InvalidSlot(slot) => write!(f, "Slot {} does not exist", slot),
+            InventoryError::EmptySlot(slot) => write!(f, "Slot {} is empty", slot),
+            InventoryError::NotEnough { item, requested, available } => {
+                write!(f, "Not enough '{}': requested {}, available {}", item, requested, available)
+            }
+            InventoryError::InvalidAmount(count) => write!(f, "Invalid amount {}", count),
+            InventoryError::Full => write!(f, "Inventory is full"),
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
+/// Slot based item container used for the player, chests and workstations.
+///
+/// Operations which need the stack size of an item take a `stack_size` lookup,
+/// usually `|id| items.stack_size(id)` with the `Items` system parameter.
+#[derive(Component, Debug, Clone, Default, Reflect, Deserialize, Serialize)]
+#[reflect(Component)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+}
+
+impl Inventory {
+
+    /// Creates an empty inventory with `size` slots.
+    pub fn new(size: usize) -> Self {
+        Self { slots: vec![None; size] }
+    }
+
+    pub fn size(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&ItemStack> {
+        self.slots.get(slot).and_then(Option::as_ref)
+    }
+
+    /// Total amount of `item` across all slots.
+    pub fn count(&self, item: &str) -> u32 {
+        self.stacks().filter(|s| s.item == item).map(|s| s.count).sum()
+    }
+
+    pub fn contains(&self, item: &str, count: u32) -> bool {
+        self.count(item) >= count
+    }
+
+    /// Iterates over all non-empty slots.
+    pub fn stacks(&self) -> impl Iterator<Item = &ItemStack> {
+        self.slots.iter().flatten()
+    }
+
+    /// Total weight of the inventory content.
+    pub fn weight(&self, item_weight: impl Fn(&str) -> f32) -> f32 {
+        self.stacks().map(|s| item_weight(&s.item) * s.count as f32).sum()
+    }
+
+    /// Returns how many of `item` still fit into the inventory.
+    pub fn capacity_for(&self, item: &str, stack_size: impl Fn(&str) -> u32) -> u32 {
+        let max = stack_size(item).max(1);
+        self.slots.iter().map(|slot| match slot {
+            None => max,
+            Some(stack) if stack.item == item => max.saturating_sub(stack.count),
+            Some(_) => 0,
+        }).sum()
+    }
+
+    /// Adds `count` of `item`, filling existing stacks first and then empty slots.
+    ///
+    /// # Returns
+    /// The amount which did not fit into the inventory.
+    pub fn add(&mut self, item: &str, count: u32, stack_size: impl Fn(&str) -> u32) -> u32 {
+        let max = stack_size(item).max(1);
+        let mut remaining = count;
+
+        for stack in self.slots.iter_mut().flatten().filter(|s| s.item == item) {
+            if remaining == 0 {
+                break;
+            }
+            let moved = remaining.min(max.saturating_sub(stack.count));
+            stack.count += moved;
+            remaining -= moved;
+        }
+
+        for slot in self.slots.iter_mut().filter(|s| s.is_none()) {
+            if remaining == 0 {
+                break;
+            }
+            let moved = remaining.min(max);
+            *slot = Some(ItemStack::new(item, moved));
+            remaining -= moved;
+        }
+
+        remaining
+    }
+
+    /// Removes `count` of `item` from any slots. Nothing is removed if there are not enough.
+    pub fn remove(&mut self, item: &str, count: u32) -> Result<(), InventoryError> {
+        let available = self.count(item);
+        if available < count {
+            return Err(InventoryError::NotEnough { item: item.to_string(), requested: count, available });
+        }
+
+        let mut remaining = count;
+        for slot in self.slots.iter_mut().rev() {
+            if remaining == 0 {
+                break;
+            }
+            let Some(stack) = slot.as_mut().filter(|s| s.item == item) else {
+                continue;
+            };
+            let taken = remaining.min(stack.count);
+            stack.count -= taken;
+            remaining -= taken;
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes up to `count` items out of `slot`.
+    pub fn take_from_slot(&mut self, slot: usize, count: u32) -> Result<ItemStack, InventoryError> {
+        let entry = self.slots.get_mut(slot).ok_or(InventoryError::InvalidSlot(slot))?;
+        let stack = entry.as_mut().ok_or(InventoryError::EmptySlot(slot))?;
+        let taken = count.min(stack.count);
+        stack.count -= taken;
+        let result = ItemStack::new(stack.item.clone(), taken);
+        if stack.count == 0 {
+            *entry = None;
+        }
+        Ok(result)
+    }
+
+    /// Splits `count` items of `slot` into the first empty slot.
+    ///
+    /// # Returns
+    /// The index of the slot receiving the split stack.
+    pub fn split(&mut self, slot: usize, count: u32) -> Result<usize, InventoryError> {
+        let stack = self.get(slot).ok_or(InventoryError::EmptySlot(slot))?;
+        if count == 0 || count >= stack.count {
+            return Err(InventoryError::InvalidAmount(count));
+        }
+        let target = self.slots.iter().position(Option::is_none).ok_or(InventoryError::Full)?;
+        let taken = self.take_from_slot(slot, count)?;
+        self.slots[target] = Some(taken);
+        Ok(target)
+    }
+
+    /// Moves the stack of `from` into slot `to` of the same inventory.
+    /// Equal items are merged, different items are swapped.
+    pub fn move_slot(&mut self, from: usize, to: usize, stack_size: impl Fn(&str) -> u32) -> Result<(), InventoryError> {
+        if from >= self.size() {
+            return Err(InventoryError::InvalidSlot(from));
+        }
+        if to >= self.size() {
+            return Err(InventoryError::InvalidSlot(to));
+        }
+        if from == to {
+            return Ok(());
+        }
+        let source = self.slots[from].take();
+        let target = self.slots[to].take();
+        let (source, target) = merge_or_swap(source, target, &stack_size);
+        self.slots[from] = source;
+        self.slots[to] = target;
+        Ok(())
+    }
+
+    /// Moves the stack of `from` into slot `to` of another container.
+    /// Equal items are merged, different items are swapped.
+    pub fn move_to(&mut self, from: usize, other: &mut Inventory, to: usize, stack_size: impl Fn(&str) -> u32) -> Result<(), InventoryError> {
+        if from >= self.size() {
+            return Err(InventoryError::InvalidSlot(from));
+        }
+        if to >= other.size() {
+            return Err(InventoryError::InvalidSlot(to));
+        }
+        let source = self.slots[from].take();
+        let target = other.slots[to].take();
+        let (source, target) = merge_or_swap(source, target, &stack_size);
+        self.slots[from] = source;
+        other.slots[to] = target;
+        Ok(())
+    }
+}
+
+/// Moves `source` onto `target`. Returns the new content of both slots.
+fn merge_or_swap(
+    source: Option<ItemStack>,
+    target: Option<ItemStack>,
+    stack_size: &impl Fn(&str) -> u32,
+) -> (Option<ItemStack>, Option<ItemStack>) {
+    match (source, target) {
+        (Some(mut source), Some(mut target)) if source.item == target.item => {
+            let max = stack_size(&target.item).max(1);
+            let moved = source.count.min(max.saturating_sub(target.count));
+            target.count += moved;
+            source.count -= moved;
+            ((source.count > 0).then_some(source), Some(target))
+        }
+        (source, target) => (target, source),
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::definitions::{Definition, DefinitionLibrary};
+use crate::items::equipment::EquipmentData;
+use crate::status_effects::EffectApplication;
+use crate::survival::NeedRestore;
+
+/// Data driven item description. Authored as `assets/items/<name>.item.json`.
+#[derive(Asset, TypePath, Deserialize, Serialize, Debug, Clone)]
+pub struct ItemDefinition {
+    /// Unique id of the item, referenced by inventories, recipes and loot tables.
+    pub id: String,
+    /// Localization key of the item name.
+    pub localized_name: String,
+    /// Asset path of the inventory icon.
+    #[serde(default)]
+    pub icon: String,
+    /// Maximum amount of this item in a single slot.
+    #[serde(default = "default_stack_size")]
+    pub stack_size: u32,
+    /// Weight of a single item.
+    #[serde(default)]
+    pub weight: f32,
+    /// Free form tags used by gameplay code (e.g. `"consumable"`, `"fuel"`).
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Present if the item can be equipped.
+    #[serde(default)]
+    pub equipment: Option<EquipmentData>,
+    /// Needs restored when the item is used, e.g. hunger for food.
+    #[serde(default)]
+    pub restores: Vec<NeedRestore>,
+    /// Status effects applied when the item is used.
+    #[serde(default)]
+    pub applies: Vec<EffectApplication>,
+    /// Ids of status effects removed when the item is used, e.g. `bleeding` for bandages.
+    #[serde(default)]
+    pub cures: Vec<String>,
+}
+
+fn default_stack_size() -> u32 {
+    1
+}
+
+impl ItemDefinition {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+}
+
+impl Definition for ItemDefinition {
+    const FOLDER: &'static str = "items";
+    const EXTENSIONS: &'static [&'static str] = &["item.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Read only access to all loaded [`ItemDefinition`]s by id.
+#[derive(SystemParam)]
+pub struct Items<'w> {
+    library: Res<'w, DefinitionLibrary<ItemDefinition>>,
+    assets: Res<'w, Assets<ItemDefinition>>,
+}
+
+impl<'w> Items<'w> {
+
+    /// Returns the definition of the given item id.
+    pub fn get(&self, id: &str) -> Option<&ItemDefinition> {
+        self.library.get(id, &self.assets)
+    }
+
+    /// Returns the stack size of the given item id. Unknown items don't stack.
+    pub fn stack_size(&self, id: &str) -> u32 {
+        self.get(id).map_or(1, |item| item.stack_size.max(1))
+    }
+
+    /// Returns the weight of a single item. Unknown items weigh nothing.
+    pub fn weight(&self, id: &str) -> f32 {
+        self.get(id).map_or(0.0, |item| item.weight)
+    }
+}
@@ -0,0 +1,351 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::definitions::{Definition, DefinitionLibrary};
+use crate::items::inventory::ItemStack;
+use crate::rng::{clock_seed, SeededRng};
+
+/// Nested tables deeper than this are ignored, so cyclic references can't hang the game.
+pub const MAX_LOOT_DEPTH: u32 = 8;
+
+/// Data driven loot table. Authored as `assets/loot/<name>.loot.json` and referenced by
+/// the `loot_table` of entity definitions.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct LootTableDefinition {
+    pub id: String,
+    /// Number of weighted picks from `entries`.
+    #[serde(default = "default_rolls")]
+    pub rolls: CountRange,
+    /// Entries which always drop if their conditions are met.
+    #[serde(default)]
+    pub guaranteed: Vec<LootEntry>,
+    /// Entries picked by weight, once per roll.
+    #[serde(default)]
+    pub entries: Vec<LootEntry>,
+}
+
+impl Definition for LootTableDefinition {
+    const FOLDER: &'static str = "loot";
+    const EXTENSIONS: &'static [&'static str] = &["loot.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+fn default_rolls() -> CountRange {
+    CountRange { min: 1, max: 1 }
+}
+
+/// Inclusive range of a random count.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Default for CountRange {
+    fn default() -> Self {
+        default_rolls()
+    }
+}
+
+impl CountRange {
+    pub fn roll(&self, rng: &mut SeededRng) -> u32 {
+        rng.range_u32(self.min, self.max)
+    }
+}
+
+/// Single drop of a [`LootTableDefinition`]. Drops `item`, rolls the nested `table`, or
+/// drops nothing if neither is set.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LootEntry {
+    /// Relative chance to be picked. Ignored for guaranteed entries.
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    #[serde(default)]
+    pub item: Option<String>,
+    /// Amount of `item`.
+    #[serde(default)]
+    pub count: CountRange,
+    /// Id of a nested table which is rolled instead of an item.
+    #[serde(default)]
+    pub table: Option<String>,
+    /// All conditions have to be met for the entry to drop.
+    #[serde(default)]
+    pub conditions: Vec<LootCondition>,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+impl LootEntry {
+    pub fn is_available(&self, context: &LootContext) -> bool {
+        self.conditions.iter().all(|condition| condition.evaluate(context))
+    }
+}
+
+/// Condition of a [`LootEntry`]. Authored in snake case, e.g. `{ "min_night": 3 }`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LootCondition {
+    /// The current night is at least the given number.
+    MinNight(u32),
+    /// The current night is at most the given number.
+    MaxNight(u32),
+    /// The loot source lies in the given biome.
+    Biome(String),
+    /// The player has at least the given level.
+    MinPlayerLevel(u32),
+}
+
+impl LootCondition {
+    pub fn evaluate(&self, context: &LootContext) -> bool {
+        match self {
+            LootCondition::MinNight(night) => context.night >= *night,
+            LootCondition::MaxNight(night) => context.night <= *night,
+            LootCondition::Biome(biome) => context.biome.as_deref() == Some(biome.as_str()),
+            LootCondition::MinPlayerLevel(level) => context.player_level >= *level,
+        }
+    }
+}
+
+/// Facts about the world used to evaluate [`LootCondition`]s.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LootContext {
+    pub night: u32,
+    pub biome: Option<String>,
+    pub player_level: u32,
+}
+
+/// Biome of the area a loot source lies in, e.g. set on chests by the level.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Biome(pub String);
+
+/// Loot is rolled when the entity is interacted with instead of when it dies, e.g. chests.
+/// The entity can be looted once.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct LootContainer {
+    pub looted: bool,
+}
+
+/// Seed of the loot rolls. Editable at runtime in the inspector.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct LootSettings {
+    /// Seed of the [`LootRng`]. Drawn from the clock, so every session rolls different
+    /// loot. Set a fixed seed to reproduce the drops of a world.
+    pub seed: u64,
+}
+
+impl Default for LootSettings {
+    fn default() -> Self {
+        Self { seed: clock_seed() }
+    }
+}
+
+/// Random source of all loot rolls, seeded from [`LootSettings::seed`].
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct LootRng(pub SeededRng);
+
+impl FromWorld for LootRng {
+    fn from_world(world: &mut World) -> Self {
+        let settings = world.get_resource_or_init::<LootSettings>();
+        Self(SeededRng::new(settings.seed))
+    }
+}
+
+/// Rolls the table `id` and its nested tables. Stacks of the same item are merged.
+///
+/// # Parameters
+/// * `id` - Id of the rolled table.
+/// * `tables` - Lookup of all loot tables by id.
+/// * `context` - Facts for the entry conditions.
+/// * `rng` - Random source. The same seed always produces the same drops.
+pub fn roll_loot<'a>(
+    id: &str,
+    tables: &impl Fn(&str) -> Option<&'a LootTableDefinition>,
+    context: &LootContext,
+    rng: &mut SeededRng,
+) -> Vec<ItemStack> {
+    let mut drops = Vec::new();
+    roll_table(id, tables, context, rng, 0, &mut drops);
+    drops
+}
+
+fn roll_table<'a>(
+    id: &str,
+    tables: &impl Fn(&str) -> Option<&'a LootTableDefinition>,
+    context: &LootContext,
+    rng: &mut SeededRng,
+    depth: u32,
+    drops: &mut Vec<ItemStack>,
+) {
+    if depth > MAX_LOOT_DEPTH {
+        warn!("Loot table '{}' is nested deeper than {}", id, MAX_LOOT_DEPTH);
+        return;
+    }
+    let Some(table) = tables(id) else {
+        warn!("Loot table '{}' is not loaded", id);
+        return;
+    };
+
+    for entry in table.guaranteed.iter().filter(|entry| entry.is_available(context)) {
+        drop_entry(entry, tables, context, rng, depth, drops);
+    }
+
+    let available: Vec<&LootEntry> = table.entries.iter().filter(|entry| entry.is_available(context)).collect();
+    let weights: Vec<f32> = available.iter().map(|entry| entry.weight).collect();
+    for _ in 0..table.rolls.roll(rng) {
+        let Some(index) = rng.weighted_index(&weights) else {
+            break;
+        };
+        drop_entry(available[index], tables, context, rng, depth, drops);
+    }
+}
+
+fn drop_entry<'a>(
+    entry: &LootEntry,
+    tables: &impl Fn(&str) -> Option<&'a LootTableDefinition>,
+    context: &LootContext,
+    rng: &mut SeededRng,
+    depth: u32,
+    drops: &mut Vec<ItemStack>,
+) {
+    if let Some(item) = &entry.item {
+        let count = entry.count.roll(rng);
+        if count == 0 {
+            return;
+        }
+        match drops.iter_mut().find(|stack| stack.item == *item) {
+            Some(stack) => stack.count += count,
+            None => drops.push(ItemStack::new(item.clone(), count)),
+        }
+    } else if let Some(table) = &entry.table {
+        roll_table(table, tables, context, rng, depth + 1, drops);
+    }
+}
+
+/// Read only access to all loaded [`LootTableDefinition`]s by id.
+#[derive(SystemParam)]
+pub struct LootTables<'w> {
+    library: Res<'w, DefinitionLibrary<LootTableDefinition>>,
+    assets: Res<'w, Assets<LootTableDefinition>>,
+}
+
+impl<'w> LootTables<'w> {
+
+    pub fn get(&self, id: &str) -> Option<&LootTableDefinition> {
+        self.library.get(id, &self.assets)
+    }
+
+    /// Rolls the table `id`, see [`roll_loot`].
+    pub fn roll(&self, id: &str, context: &LootContext, rng: &mut SeededRng) -> Vec<ItemStack> {
+        roll_loot(id, &|id| self.get(id), context, rng)
+    }
+}
+
+/// Loot of `source` was rolled. `items` were given to `receiver`, or dropped into the
+/// world without one.
+#[derive(Message, Debug, Clone)]
+pub struct LootDropped {
+    pub source: Entity,
+    pub receiver: Option<Entity>,
+    pub table: String,
+    pub items: Vec<ItemStack>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+
+    fn tables(json: &[&str]) -> HashMap<String, LootTableDefinition> {
+        json.iter()
+            .map(|json| serde_json::from_str::<LootTableDefinition>(json).unwrap())
+            .map(|table| (table.id.clone(), table))
+            .collect()
+    }
+
+    fn roll(tables: &HashMap<String, LootTableDefinition>, id: &str, context: &LootContext, seed: u64) -> Vec<ItemStack> {
+        roll_loot(id, &|id| tables.get(id), context, &mut SeededRng::new(seed))
+    }
+
+    #[test]
+    fn weighted_entries_drop_the_same_items_for_a_seed() {
+        let tables = tables(&[r#"{
+            "id": "weighted",
+            "rolls": { "min": 3, "max": 3 },
+            "entries": [
+                { "item": "wood", "weight": 3.0 },
+                { "item": "stone", "weight": 1.0, "count": { "min": 2, "max": 4 } }
+            ]
+        }"#]);
+
+        let drops = roll(&tables, "weighted", &LootContext::default(), 7);
+
+        assert_eq!(drops, vec![ItemStack::new("wood", 2), ItemStack::new("stone", 2)]);
+    }
+
+    #[test]
+    fn nested_tables_are_rolled_with_the_same_random_source() {
+        let tables = tables(&[
+            r#"{ "id": "outer", "rolls": { "min": 0, "max": 0 }, "guaranteed": [{ "table": "inner" }] }"#,
+            r#"{
+                "id": "inner",
+                "entries": [
+                    { "item": "arrow", "count": { "min": 5, "max": 10 } },
+                    { "item": "berries" }
+                ]
+            }"#,
+        ]);
+
+        assert_eq!(roll(&tables, "outer", &LootContext::default(), 7), vec![ItemStack::new("arrow", 5)]);
+        assert_eq!(roll(&tables, "outer", &LootContext::default(), 1), vec![ItemStack::new("berries", 1)]);
+    }
+
+    #[test]
+    fn unavailable_entries_are_never_picked() {
+        let tables = tables(&[r#"{
+            "id": "conditional",
+            "rolls": { "min": 4, "max": 4 },
+            "entries": [
+                { "item": "wood" },
+                { "item": "scrap", "conditions": [{ "min_night": 3 }] }
+            ]
+        }"#]);
+        let early = LootContext { night: 1, ..default() };
+        let late = LootContext { night: 3, ..default() };
+
+        assert_eq!(roll(&tables, "conditional", &early, 7), vec![ItemStack::new("wood", 4)]);
+        assert_eq!(
+            roll(&tables, "conditional", &late, 7),
+            vec![ItemStack::new("wood", 2), ItemStack::new("scrap", 2)]
+        );
+    }
+
+    #[test]
+    fn guaranteed_entries_always_drop_when_available() {
+        let tables = tables(&[r#"{
+            "id": "guaranteed",
+            "guaranteed": [
+                { "item": "bandage" },
+                { "item": "scrap", "count": { "min": 2, "max": 2 }, "conditions": [{ "min_player_level": 5 }] }
+            ]
+        }"#]);
+        let novice = LootContext { player_level: 1, ..default() };
+        let veteran = LootContext { player_level: 5, ..default() };
+
+        for seed in [1, 7, 42] {
+            assert_eq!(roll(&tables, "guaranteed", &novice, seed), vec![ItemStack::new("bandage", 1)]);
+            assert_eq!(
+                roll(&tables, "guaranteed", &veteran, seed),
+                vec![ItemStack::new("bandage", 1), ItemStack::new("scrap", 2)]
+            );
+        }
+    }
+}
@@ -0,0 +1,118 @@
+pub mod item;
+pub mod inventory;
+pub mod equipment;
+pub mod crafting;
+pub mod loot;
+
+use bevy::prelude::*;
+use crate::definitions::AppDefinitionExt;
+use crate::items::crafting::{CancelCrafting, CraftItem, CraftingCompleted, CraftingFailed, CraftingJob, CraftingProgressed, CraftingQueued, RecipeDefinition, Workstation};
+use crate::items::equipment::{EquipItem, Equipment, EquipmentSlot, EquipmentVisual, ItemEquipped, ItemUnequipped, UnequipItem};
+use crate::items::inventory::{Inventory, ItemStack};
+use crate::items::item::ItemDefinition;
+use crate::items::loot::{Biome, LootContainer, LootDropped, LootRng, LootSettings, LootTableDefinition};
+
+pub struct ItemsModule;
+
+impl Plugin for ItemsModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<ItemDefinition>()
+            .register_definition::<RecipeDefinition>()
+            .register_definition::<LootTableDefinition>();
+        app.init_resource::<LootSettings>()
+            .init_resource::<LootRng>();
+        app.add_message::<PickUpItem>()
+            .add_message::<DropItem>()
+            .add_message::<UseItem>()
+            .add_message::<ItemPickedUp>()
+            .add_message::<ItemDropped>()
+            .add_message::<ItemUsed>()
+            .add_message::<EquipItem>()
+            .add_message::<UnequipItem>()
+            .add_message::<ItemEquipped>()
+            .add_message::<ItemUnequipped>()
+            .add_message::<CraftItem>()
+            .add_message::<CancelCrafting>()
+            .add_message::<CraftingQueued>()
+            .add_message::<CraftingProgressed>()
+            .add_message::<CraftingCompleted>()
+            .add_message::<CraftingFailed>()
+            .add_message::<LootDropped>();
+        app.register_type::<Inventory>()
+            .register_type::<ItemStack>()
+            .register_type::<WorldItem>()
+            .register_type::<Equipment>()
+            .register_type::<EquipmentSlot>()
+            .register_type::<EquipmentVisual>()
+            .register_type::<Workstation>()
+            .register_type::<CraftingJob>()
+            .register_type::<LootContainer>()
+            .register_type::<Biome>()
+            .register_type::<LootSettings>()
+            .register_type::<LootRng>();
+    }
+}
+
+/// Item stack lying in the world which can be picked up.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct WorldItem(pub ItemStack);
+
+// =================================================================================================
+//
+//                                           Requests
+//
+// =================================================================================================
+
+/// Request: `entity` picks up the [`WorldItem`] `item_entity` into its [`Inventory`].
+#[derive(Message, Debug, Clone)]
+pub struct PickUpItem {
+    pub entity: Entity,
+    pub item_entity: Entity,
+}
+
+/// Request: `entity` drops `count` items of `slot` into the world.
+#[derive(Message, Debug, Clone)]
+pub struct DropItem {
+    pub entity: Entity,
+    pub slot: usize,
+    pub count: u32,
+}
+
+/// Request: `entity` uses (consumes) one item of `slot`.
+#[derive(Message, Debug, Clone)]
+pub struct UseItem {
+    pub entity: Entity,
+    pub slot: usize,
+}
+
+// =================================================================================================
+//
+//                                        Notifications
+//
+// =================================================================================================
+
+/// An item was added to the inventory of `entity` from the world.
+#[derive(Message, Debug, Clone)]
+pub struct ItemPickedUp {
+    pub entity: Entity,
+    pub stack: ItemStack,
+}
+
+/// An item was dropped by `entity` and now exists as `world_item`.
+#[derive(Message, Debug, Clone)]
+pub struct ItemDropped {
+    pub entity: Entity,
+    pub world_item: Entity,
+    pub stack: ItemStack,
+}
+
+/// An item was used (and consumed) by `entity`. Effects of the item are applied
+/// by the systems listening for this message.
+#[derive(Message, Debug, Clone)]
+pub struct ItemUsed {
+    pub entity: Entity,
+    pub item: String,
+}
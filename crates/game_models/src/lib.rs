@@ -7,9 +7,57 @@ pub mod key_utils;
 pub mod debug;
 pub mod entities;
 pub mod camera;
+pub mod definitions;
+pub mod health;
+pub mod attributes;
+pub mod items;
+pub mod rng;
+pub mod ai;
+pub mod navigation;
+pub mod time_of_day;
+pub mod spawning;
+pub mod pacing;
+pub mod weather;
+pub mod interaction;
+pub mod localization;
+pub mod dialogue;
+pub mod quests;
+pub mod building;
+pub mod survival;
+pub mod combat;
+pub mod projectiles;
+pub mod status_effects;
+pub mod progression;
+pub mod animation;
+pub mod ragdoll;
+pub mod factions;
+pub mod save_game;
 
 use bevy::prelude::*;
+use crate::ai::AiModule;
+use crate::animation::AnimationModule;
+use crate::attributes::AttributesModule;
+use crate::building::BuildingModule;
+use crate::combat::CombatModule;
+use crate::dialogue::DialogueModule;
 use crate::entities::EntitiesModule;
+use crate::factions::FactionsModule;
+use crate::health::HealthModule;
+use crate::interaction::InteractionModule;
+use crate::items::ItemsModule;
+use crate::localization::LocalizationModule;
+use crate::navigation::NavigationModule;
+use crate::pacing::PacingModule;
+use crate::progression::ProgressionModule;
+use crate::projectiles::ProjectilesModule;
+use crate::quests::QuestsModule;
+use crate::ragdoll::RagdollModule;
+use crate::save_game::SaveGameModule;
+use crate::spawning::SpawningModule;
+use crate::status_effects::StatusEffectsModule;
+use crate::survival::SurvivalModule;
+use crate::time_of_day::TimeOfDayModule;
+use crate::weather::WeatherModule;
 
 /// Core of all game relevant resources and structures. This Plugin initializes resources
 /// with `init_resource` from bevy. This Plugin is registered at [`ManagerPlugin`] which is
@@ -20,7 +68,34 @@ impl Plugin for GameCorePlugin {
 
     #[coverage(off)]
     fn build(&self, app: &mut App) {
-        app.add_plugins(EntitiesModule);
+        app.add_plugins((
+            EntitiesModule,
+            HealthModule,
+            AttributesModule,
+            ItemsModule,
+            AiModule,
+            NavigationModule,
+            TimeOfDayModule,
+            SpawningModule,
+            PacingModule,
+            WeatherModule,
+            InteractionModule,
+            LocalizationModule,
+            DialogueModule,
+            QuestsModule,
+        ));
+        app.add_plugins((
+            BuildingModule,
+            SurvivalModule,
+            CombatModule,
+            ProjectilesModule,
+            StatusEffectsModule,
+            ProgressionModule,
+            AnimationModule,
+            RagdollModule,
+            FactionsModule,
+            SaveGameModule,
+        ));
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+use crate::definitions::{AppDefinitionExt, Definition, DefinitionLibrary};
+
+pub struct LocalizationModule;
+
+impl Plugin for LocalizationModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<LanguageDefinition>();
+        app.init_resource::<LocalizationSettings>();
+        app.register_type::<LocalizationSettings>();
+    }
+}
+
+/// Translated texts of one language. Authored as `assets/lang/<language>.lang.json`.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct LanguageDefinition {
+    /// Language code, e.g. `en`.
+    pub id: String,
+    /// Texts keyed by their localization key.
+    pub texts: HashMap<String, String>,
+}
+
+impl Definition for LanguageDefinition {
+    const FOLDER: &'static str = "lang";
+    const EXTENSIONS: &'static [&'static str] = &["lang.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Active language of all localized texts.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct LocalizationSettings {
+    pub language: String,
+    /// Language used for keys missing in the active language.
+    pub fallback: String,
+}
+
+impl Default for LocalizationSettings {
+    fn default() -> Self {
+        Self {
+            language: String::from("en"),
+            fallback: String::from("en"),
+        }
+    }
+}
+
+/// Read only access to the texts of the active language.
+#[derive(SystemParam)]
+pub struct Localization<'w> {
+    settings: Res<'w, LocalizationSettings>,
+    library: Res<'w, DefinitionLibrary<LanguageDefinition>>,
+    assets: Res<'w, Assets<LanguageDefinition>>,
+}
+
+impl<'w> Localization<'w> {
+
+    /// Returns the text of `key` in the active or the fallback language.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        [&self.settings.language, &self.settings.fallback]
+            .into_iter()
+            .filter_map(|language| self.library.get(language, &self.assets))
+            .find_map(|language| language.texts.get(key))
+            .map(String::as_str)
+    }
+
+    /// Returns the text of `key`, or the key itself if no language knows it, so
+    /// missing translations stay visible in game.
+    pub fn text(&self, key: &str) -> String {
+        self.get(key).unwrap_or(key).to_string()
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+
+/// State of the path of a [`NavAgent`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub enum NavPathStatus {
+    /// No goal is set.
+    #[default]
+    Idle,
+    /// A path query is running.
+    Pending,
+    /// The agent follows `path`.
+    Following,
+    /// No path to the goal exists (or the goal is outside the baked chunks).
+    Unreachable,
+}
+
+/// Entity which moves along paths on the [`NavMesh`](crate::navigation::grid::NavMesh).
+/// The AI sets the `goal`, the navigation logic computes the path asynchronously.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct NavAgent {
+    /// Radius used for local avoidance between agents.
+    pub radius: f32,
+    /// Point the agent wants to reach.
+    pub goal: Option<Vec3>,
+    /// Smoothed waypoints of the current path.
+    pub path: Vec<Vec3>,
+    /// Index of the waypoint the agent currently walks to.
+    pub next_waypoint: usize,
+    pub status: NavPathStatus,
+    /// Goal the current path (or running query) was computed for.
+    pub path_goal: Option<Vec3>,
+    /// Distance the goal has to move before a new path is requested.
+    pub repath_distance: f32,
+    /// Distance at which a waypoint counts as reached.
+    pub waypoint_radius: f32,
+}
+
+impl Default for NavAgent {
+    fn default() -> Self {
+        Self {
+            radius: 0.4,
+            goal: None,
+            path: Vec::new(),
+            next_waypoint: 0,
+            status: NavPathStatus::Idle,
+            path_goal: None,
+            repath_distance: 1.5,
+            waypoint_radius: 0.5,
+        }
+    }
+}
+
+impl NavAgent {
+
+    /// Sets the point to walk to. `None` stops navigating and clears the path.
+    pub fn set_goal(&mut self, goal: Option<Vec3>) {
+        self.goal = goal;
+        if goal.is_none() {
+            self.clear_path();
+        }
+    }
+
+    /// `true` if the goal moved too far from the one of the current path and
+    /// no query is running yet.
+    pub fn needs_path(&self) -> bool {
+        if self.status == NavPathStatus::Pending {
+            return false;
+        }
+        match (self.goal, self.path_goal) {
+            (Some(goal), Some(path_goal)) => goal.distance(path_goal) > self.repath_distance,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Marks a path query for the current goal as running.
+    pub fn start_query(&mut self) {
+        self.status = NavPathStatus::Pending;
+        self.path_goal = self.goal;
+    }
+
+    /// Stores the result of a path query.
+    pub fn set_path(&mut self, path: Option<Vec<Vec3>>) {
+        match path {
+            Some(path) => {
+                self.path = path;
+                self.next_waypoint = 0;
+                self.status = NavPathStatus::Following;
+            }
+            None => {
+                self.path.clear();
+                self.next_waypoint = 0;
+                self.status = NavPathStatus::Unreachable;
+            }
+        }
+    }
+
+    /// Drops the current path, so a new one is requested for the goal.
+    pub fn clear_path(&mut self) {
+        self.path.clear();
+        self.next_waypoint = 0;
+        self.path_goal = None;
+        if self.status != NavPathStatus::Pending {
+            self.status = NavPathStatus::Idle;
+        }
+    }
+
+    /// Returns the waypoint to steer to from `position`, skipping waypoints which are
+    /// already reached. `None` if there is no path or its end is reached.
+    pub fn steering_target(&mut self, position: Vec3) -> Option<Vec3> {
+        while let Some(waypoint) = self.path.get(self.next_waypoint) {
+            let distance = Vec2::new(waypoint.x - position.x, waypoint.z - position.z).length();
+            if distance > self.waypoint_radius {
+                return Some(*waypoint);
+            }
+            self.next_waypoint += 1;
+        }
+        None
+    }
+
+    /// Waypoints which are not reached yet.
+    pub fn remaining_path(&self) -> &[Vec3] {
+        self.path.get(self.next_waypoint..).unwrap_or(&[])
+    }
+}
+
+/// Request to (re-)bake a navigation chunk, e.g. after the terrain or a building changed.
+#[derive(Message, Debug, Clone)]
+pub struct RebakeNavChunk {
+    pub chunk: IVec2,
+}
+
+/// Emitted after a navigation chunk was baked.
+#[derive(Message, Debug, Clone)]
+pub struct NavChunkBaked {
+    pub chunk: IVec2,
+}
@@ -0,0 +1,614 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use bevy::prelude::*;
+
+/// Cells searched around a query point when it is not on a walkable cell.
+const SNAP_CELLS: i32 = 4;
+
+/// Tuning values for baking and querying the navigation grid.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct NavSettings {
+    /// Edge length of one grid cell in world units.
+    pub cell_size: f32,
+    /// Number of cells along one side of a chunk.
+    pub chunk_cells: u32,
+    /// Radius of the largest agent. Cells closer than this to a drop or wall are not walkable.
+    pub agent_radius: f32,
+    /// Free space required above a walkable surface.
+    pub agent_height: f32,
+    /// Maximum height difference agents can step up or down between neighbouring cells.
+    pub max_step: f32,
+    /// Maximum walkable slope in degrees.
+    pub max_slope: f32,
+    /// Highest point sampled while baking.
+    pub bake_top: f32,
+    /// Lowest point sampled while baking.
+    pub bake_bottom: f32,
+    /// Maximum number of stacked walkable layers per cell (terrain, cave floors, ...).
+    pub max_layers: usize,
+    /// Ground height used for chunks without any collider. `None` leaves those chunks empty.
+    pub fallback_height: Option<f32>,
+    /// Chunks around the player (in chunks) which are kept baked.
+    pub bake_radius: i32,
+    /// Maximum number of chunks baked per frame.
+    pub bakes_per_frame: usize,
+    /// Maximum number of nodes expanded by one path query before it gives up.
+    pub max_search_nodes: usize,
+    /// Extra distance agents keep between each other.
+    pub avoidance_margin: f32,
+    /// Strength of the avoidance steering compared to the path direction.
+    pub avoidance_weight: f32,
+    /// Distance around the player in which the navigation grid is drawn as gizmos.
+    pub gizmo_radius: f32,
+}
+
+impl Default for NavSettings {
+    fn default() -> Self {
+        Self {
+            cell_size: 0.5,
+            chunk_cells: 64,
+            agent_radius: 0.4,
+            agent_height: 1.8,
+            max_step: 0.45,
+            max_slope: 45.0,
+            bake_top: 128.0,
+            bake_bottom: -64.0,
+            max_layers: 4,
+            fallback_height: Some(0.0),
+            bake_radius: 2,
+            bakes_per_frame: 1,
+            max_search_nodes: 20_000,
+            avoidance_margin: 0.2,
+            avoidance_weight: 1.5,
+            gizmo_radius: 16.0,
+        }
+    }
+}
+
+// =================================================================================================
+//
+//                                            Chunk
+//
+// =================================================================================================
+
+/// Walkable surfaces of one world chunk. Every cell stores any number of stacked
+/// layers, so cave floors below the terrain surface are part of the same chunk.
+#[derive(Debug, Clone, Default)]
+pub struct NavChunk {
+    /// Cells along one side.
+    size: u32,
+    /// Start index into `heights` per cell (row major, `size * size + 1` entries).
+    offsets: Vec<u32>,
+    /// Surface heights of all walkable layers, sorted from top to bottom per cell.
+    heights: Vec<f32>,
+}
+
+impl NavChunk {
+
+    /// Creates a chunk from the walkable heights of every cell (row major).
+    pub fn from_columns(size: u32, columns: Vec<Vec<f32>>) -> Self {
+        let mut offsets = Vec::with_capacity(columns.len() + 1);
+        let mut heights = Vec::new();
+        offsets.push(0);
+        for mut column in columns {
+            column.sort_by(|a, b| b.total_cmp(a));
+            heights.extend(column);
+            offsets.push(heights.len() as u32);
+        }
+        Self { size, offsets, heights }
+    }
+
+    /// Creates a chunk which is completely walkable at `height`.
+    pub fn flat(size: u32, height: f32) -> Self {
+        Self::from_columns(size, vec![vec![height]; (size * size) as usize])
+    }
+
+    /// Bakes the chunk at `coord` from a height sampler.
+    ///
+    /// `sample` receives the XZ center of a cell and returns the heights of all walkable
+    /// surfaces in that column. Cells are sampled with a border of the agent radius, so
+    /// surfaces which are too close to a wall or drop are removed across chunk borders.
+    pub fn bake(coord: IVec2, settings: &NavSettings, mut sample: impl FnMut(Vec2) -> Vec<f32>) -> Self {
+        let size = settings.chunk_cells as i32;
+        let pad = (settings.agent_radius / settings.cell_size).ceil() as i32;
+        let span = size + pad * 2;
+        let origin = coord * size - IVec2::splat(pad);
+
+        let columns: Vec<Vec<f32>> = (0..span * span)
+            .map(|i| {
+                let cell = origin + IVec2::new(i % span, i / span);
+                sample((cell.as_vec2() + 0.5) * settings.cell_size)
+            })
+            .collect();
+
+        let has_clearance = |cell: IVec2, height: f32| {
+            for dz in -pad..=pad {
+                for dx in -pad..=pad {
+                    if dx * dx + dz * dz > pad * pad {
+                        continue;
+                    }
+                    let neighbour = cell + IVec2::new(dx, dz);
+                    let column = &columns[(neighbour.y * span + neighbour.x) as usize];
+                    if !column.iter().any(|h| (h - height).abs() <= settings.max_step) {
+                        return false;
+                    }
+                }
+            }
+            true
+        };
+
+        let mut walkable = Vec::with_capacity((size * size) as usize);
+        for z in 0..size {
+            for x in 0..size {
+                let cell = IVec2::new(x + pad, z + pad);
+                let column = &columns[(cell.y * span + cell.x) as usize];
+                walkable.push(column.iter().copied().filter(|h| has_clearance(cell, *h)).collect());
+            }
+        }
+
+        Self::from_columns(size as u32, walkable)
+    }
+
+    /// Walkable heights of the cell at `local`, sorted from top to bottom.
+    pub fn layers(&self, local: UVec2) -> &[f32] {
+        let index = (local.y * self.size + local.x) as usize;
+        match (self.offsets.get(index), self.offsets.get(index + 1)) {
+            (Some(start), Some(end)) => &self.heights[*start as usize..*end as usize],
+            _ => &[],
+        }
+    }
+
+    /// `true` if no cell of the chunk is walkable.
+    pub fn is_empty(&self) -> bool {
+        self.heights.is_empty()
+    }
+}
+
+// =================================================================================================
+//
+//                                            Grid
+//
+// =================================================================================================
+
+/// One walkable layer of a grid cell. Nodes of the path search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NavNode {
+    /// Global cell coordinate.
+    pub cell: IVec2,
+    /// Index into the layers of the cell.
+    pub layer: u8,
+}
+
+/// Layered navigation grid made of baked [`NavChunk`]s. Read only during path queries,
+/// so it can be shared with async tasks through [`NavMesh`].
+#[derive(Debug, Clone, Default)]
+pub struct NavGrid {
+    pub cell_size: f32,
+    pub chunk_cells: u32,
+    pub max_step: f32,
+    chunks: HashMap<IVec2, NavChunk>,
+}
+
+impl NavGrid {
+
+    pub fn new(settings: &NavSettings) -> Self {
+        Self {
+            cell_size: settings.cell_size,
+            chunk_cells: settings.chunk_cells,
+            max_step: settings.max_step,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Cell containing the given world position.
+    pub fn cell_of(&self, position: Vec3) -> IVec2 {
+        (Vec2::new(position.x, position.z) / self.cell_size).floor().as_ivec2()
+    }
+
+    /// Chunk containing the given cell.
+    pub fn chunk_of(&self, cell: IVec2) -> IVec2 {
+        cell.div_euclid(IVec2::splat(self.chunk_cells as i32))
+    }
+
+    /// Chunk containing the given world position.
+    pub fn chunk_at(&self, position: Vec3) -> IVec2 {
+        self.chunk_of(self.cell_of(position))
+    }
+
+    /// XZ center of a cell.
+    pub fn cell_center(&self, cell: IVec2) -> Vec2 {
+        (cell.as_vec2() + 0.5) * self.cell_size
+    }
+
+    pub fn insert_chunk(&mut self, coord: IVec2, chunk: NavChunk) {
+        self.chunks.insert(coord, chunk);
+    }
+
+    pub fn remove_chunk(&mut self, coord: IVec2) -> Option<NavChunk> {
+        self.chunks.remove(&coord)
+    }
+
+    pub fn contains_chunk(&self, coord: IVec2) -> bool {
+        self.chunks.contains_key(&coord)
+    }
+
+    /// Iterates over the coordinates of all baked chunks.
+    pub fn chunk_coords(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// Walkable heights of a cell, sorted from top to bottom. Empty if the chunk is not baked.
+    pub fn layers(&self, cell: IVec2) -> &[f32] {
+        let size = IVec2::splat(self.chunk_cells as i32);
+        self.chunks
+            .get(&cell.div_euclid(size))
+            .map_or(&[], |chunk| chunk.layers(cell.rem_euclid(size).as_uvec2()))
+    }
+
+    /// World position of a node (cell center on the walkable surface).
+    pub fn node_position(&self, node: NavNode) -> Option<Vec3> {
+        let height = *self.layers(node.cell).get(node.layer as usize)?;
+        let center = self.cell_center(node.cell);
+        Some(Vec3::new(center.x, height, center.y))
+    }
+
+    /// Layer of `cell` an agent at `height` can step onto (the closest within `max_step`).
+    pub fn connecting_node(&self, cell: IVec2, height: f32) -> Option<NavNode> {
+        self.layers(cell)
+            .iter()
+            .enumerate()
+            .map(|(layer, h)| (layer, (h - height).abs()))
+            .filter(|(_, difference)| *difference <= self.max_step)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(layer, _)| NavNode { cell, layer: layer as u8 })
+    }
+
+    /// Floor node below `position`: the highest layer of its cell which is not above
+    /// `position` by more than `max_step`.
+    pub fn node_below(&self, position: Vec3) -> Option<NavNode> {
+        let cell = self.cell_of(position);
+        self.layers(cell)
+            .iter()
+            .position(|h| *h <= position.y + self.max_step)
+            .map(|layer| NavNode { cell, layer: layer as u8 })
+    }
+
+    /// Closest floor node to `position`, searching a few cells around it if the
+    /// position itself is not walkable (e.g. close to a wall).
+    pub fn nearest_node(&self, position: Vec3) -> Option<NavNode> {
+        if let Some(node) = self.node_below(position) {
+            return Some(node);
+        }
+
+        let center = self.cell_of(position);
+        let mut best: Option<(NavNode, f32)> = None;
+        for dz in -SNAP_CELLS..=SNAP_CELLS {
+            for dx in -SNAP_CELLS..=SNAP_CELLS {
+                let cell = center + IVec2::new(dx, dz);
+                let xz = self.cell_center(cell);
+                let Some(layer) = self.layers(cell).iter().position(|h| *h <= position.y + self.max_step) else {
+                    continue;
+                };
+                let node = NavNode { cell, layer: layer as u8 };
+                let distance = Vec2::new(position.x, position.z).distance_squared(xz);
+                if best.is_none_or(|(_, d)| distance < d) {
+                    best = Some((node, distance));
+                }
+            }
+        }
+        best.map(|(node, _)| node)
+    }
+
+    /// Reachable neighbours of `node` with their move cost. Diagonal moves need both
+    /// adjacent straight moves to be possible, so paths never cut corners.
+    pub fn neighbours(&self, node: NavNode) -> Vec<(NavNode, f32)> {
+        let Some(position) = self.node_position(node) else {
+            return Vec::new();
+        };
+
+        let mut neighbours = Vec::with_capacity(8);
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                if dx == 0 && dz == 0 {
+                    continue;
+                }
+                if dx != 0 && dz != 0 {
+                    let straight_x = self.connecting_node(node.cell + IVec2::new(dx, 0), position.y);
+                    let straight_z = self.connecting_node(node.cell + IVec2::new(0, dz), position.y);
+                    if straight_x.is_none() || straight_z.is_none() {
+                        continue;
+                    }
+                }
+                let Some(next) = self.connecting_node(node.cell + IVec2::new(dx, dz), position.y) else {
+                    continue;
+                };
+                if let Some(next_position) = self.node_position(next) {
+                    neighbours.push((next, position.distance(next_position)));
+                }
+            }
+        }
+        neighbours
+    }
+
+    /// Octile distance between two nodes on the XZ plane. Never overestimates the path cost.
+    fn heuristic(&self, from: NavNode, to: NavNode) -> f32 {
+        let delta = (to.cell - from.cell).abs();
+        let (long, short) = (delta.x.max(delta.y) as f32, delta.x.min(delta.y) as f32);
+        (long + (std::f32::consts::SQRT_2 - 1.0) * short) * self.cell_size
+    }
+
+    /// Finds a path between two world positions with A* and smooths it.
+    ///
+    /// # Parameters
+    /// * `start` - Position of the agent.
+    /// * `goal` - Position to reach.
+    /// * `max_nodes` - Maximum number of expanded nodes before the search gives up.
+    ///
+    /// # Returns
+    /// The waypoints on the walkable surface, starting next to `start`, or `None`
+    /// if `goal` can't be reached.
+    pub fn find_path(&self, start: Vec3, goal: Vec3, max_nodes: usize) -> Option<Vec<Vec3>> {
+        let start_node = self.nearest_node(start)?;
+        let goal_node = self.nearest_node(goal)?;
+
+        let mut open = BinaryHeap::new();
+        let mut closed = HashSet::new();
+        let mut came_from: HashMap<NavNode, NavNode> = HashMap::new();
+        let mut costs: HashMap<NavNode, f32> = HashMap::new();
+
+        costs.insert(start_node, 0.0);
+        open.push(OpenNode { node: start_node, estimate: self.heuristic(start_node, goal_node) });
+
+        while let Some(OpenNode { node, .. }) = open.pop() {
+            if node == goal_node {
+                let mut nodes = VecDeque::from([node]);
+                let mut current = node;
+                while let Some(previous) = came_from.get(&current) {
+                    nodes.push_front(*previous);
+                    current = *previous;
+                }
+                let points = nodes.into_iter().filter_map(|n| self.node_position(n)).collect();
+                return Some(self.smooth_path(points));
+            }
+
+            if !closed.insert(node) {
+                continue;
+            }
+            if closed.len() > max_nodes {
+                return None;
+            }
+
+            let cost = costs[&node];
+            for (next, step) in self.neighbours(node) {
+                if closed.contains(&next) {
+                    continue;
+                }
+                let next_cost = cost + step;
+                if costs.get(&next).is_none_or(|c| next_cost < *c) {
+                    costs.insert(next, next_cost);
+                    came_from.insert(next, node);
+                    open.push(OpenNode { node: next, estimate: next_cost + self.heuristic(next, goal_node) });
+                }
+            }
+        }
+        None
+    }
+
+    /// `true` if an agent can walk in a straight line from `from` to `to` (both on the
+    /// walkable surface) without leaving the grid or taking a too high step.
+    pub fn is_segment_walkable(&self, from: Vec3, to: Vec3) -> bool {
+        let start = Vec2::new(from.x, from.z);
+        let delta = Vec2::new(to.x, to.z) - start;
+        let steps = (delta.length() / (self.cell_size * 0.5)).ceil().max(1.0) as usize;
+
+        let mut height = from.y;
+        for i in 1..=steps {
+            let point = start + delta * (i as f32 / steps as f32);
+            let cell = (point / self.cell_size).floor().as_ivec2();
+            let Some(node) = self.connecting_node(cell, height) else {
+                return false;
+            };
+            height = self.layers(cell)[node.layer as usize];
+        }
+        true
+    }
+
+    /// Removes every waypoint which can be skipped by walking straight to a later one
+    /// (string pulling), so agents don't zigzag along the grid.
+    pub fn smooth_path(&self, points: Vec<Vec3>) -> Vec<Vec3> {
+        if points.len() <= 2 {
+            return points;
+        }
+
+        let mut smoothed = vec![points[0]];
+        let mut anchor = points[0];
+        for i in 1..points.len() - 1 {
+            if !self.is_segment_walkable(anchor, points[i + 1]) {
+                anchor = points[i];
+                smoothed.push(anchor);
+            }
+        }
+        smoothed.push(points[points.len() - 1]);
+        smoothed
+    }
+
+    /// `true` if the line through `points` touches one of `chunks` on the XZ plane.
+    /// Segments are sampled once per cell.
+    pub fn path_crosses_chunks(&self, points: &[Vec3], chunks: &HashSet<IVec2>) -> bool {
+        if let [point] = points {
+            return chunks.contains(&self.chunk_at(*point));
+        }
+        points.windows(2).any(|segment| {
+            let delta = segment[1] - segment[0];
+            let steps = (Vec2::new(delta.x, delta.z).length() / self.cell_size).ceil().max(1.0) as usize;
+            (0..=steps).any(|i| chunks.contains(&self.chunk_at(segment[0] + delta * (i as f32 / steps as f32))))
+        })
+    }
+}
+
+/// Entry of the A* open list, ordered so the [`BinaryHeap`] pops the lowest estimate first.
+struct OpenNode {
+    node: NavNode,
+    estimate: f32,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+// =================================================================================================
+//
+//                                           Resources
+//
+// =================================================================================================
+
+/// Navigation grid of the world. The grid sits behind an [`Arc`], so path queries running
+/// on other threads keep a consistent snapshot while chunks are re-baked.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct NavMesh {
+    grid: Arc<NavGrid>,
+}
+
+impl NavMesh {
+
+    pub fn new(settings: &NavSettings) -> Self {
+        Self { grid: Arc::new(NavGrid::new(settings)) }
+    }
+
+    /// Shared snapshot of the grid for path queries.
+    pub fn grid(&self) -> Arc<NavGrid> {
+        Arc::clone(&self.grid)
+    }
+
+    /// Mutable access to the grid. Clones it first if a query still holds a snapshot.
+    pub fn grid_mut(&mut self) -> &mut NavGrid {
+        Arc::make_mut(&mut self.grid)
+    }
+}
+
+impl FromWorld for NavMesh {
+    fn from_world(world: &mut World) -> Self {
+        let settings = world.get_resource_or_init::<NavSettings>();
+        Self::new(&settings)
+    }
+}
+
+/// Chunks waiting to be (re-)baked, baked in order by the navigation logic.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct NavBakeQueue(pub VecDeque<IVec2>);
+
+impl NavBakeQueue {
+
+    /// Queues `chunk` unless it is already waiting.
+    pub fn push(&mut self, chunk: IVec2) {
+        if !self.0.contains(&chunk) {
+            self.0.push_back(chunk);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CELLS: u32 = 16;
+
+    /// Grid with one chunk at the origin. `height` returns the walkable height of a cell,
+    /// `None` for blocked cells.
+    fn grid(height: impl Fn(IVec2) -> Option<f32>) -> NavGrid {
+        let settings = NavSettings { chunk_cells: CELLS, ..default() };
+        let columns = (0..CELLS * CELLS)
+            .map(|i| IVec2::new((i % CELLS) as i32, (i / CELLS) as i32))
+            .map(|cell| height(cell).into_iter().collect())
+            .collect();
+        let mut grid = NavGrid::new(&settings);
+        grid.insert_chunk(IVec2::ZERO, NavChunk::from_columns(CELLS, columns));
+        grid
+    }
+
+    fn center(grid: &NavGrid, cell: IVec2) -> Vec3 {
+        let xz = grid.cell_center(cell);
+        Vec3::new(xz.x, 0.0, xz.y)
+    }
+
+    #[test]
+    fn open_ground_is_crossed_in_a_straight_line() {
+        let grid = grid(|_| Some(0.0));
+        let goal = center(&grid, IVec2::new(15, 15));
+        let path = grid.find_path(center(&grid, IVec2::ZERO), goal, 1000).unwrap();
+        assert_eq!(path, vec![center(&grid, IVec2::ZERO), goal]);
+    }
+
+    #[test]
+    fn walls_are_walked_around() {
+        let grid = grid(|cell| (cell.x != 8 || cell.y == 15).then_some(0.0));
+        let start = center(&grid, IVec2::new(2, 2));
+        let goal = center(&grid, IVec2::new(14, 2));
+        let path = grid.find_path(start, goal, 1000).unwrap();
+
+        assert!(path.len() > 2);
+        assert_eq!(path.last(), Some(&goal));
+        assert!(path.windows(2).all(|segment| grid.is_segment_walkable(segment[0], segment[1])));
+        assert!(path.iter().any(|point| grid.cell_of(*point).y == 15));
+    }
+
+    #[test]
+    fn closed_walls_make_the_goal_unreachable() {
+        let grid = grid(|cell| (cell.x != 8).then_some(0.0));
+        let path = grid.find_path(center(&grid, IVec2::new(2, 2)), center(&grid, IVec2::new(14, 2)), 1000);
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn steps_higher_than_max_step_are_not_taken() {
+        let low = grid(|cell| Some(if cell.x < 8 { 0.0 } else { 0.3 }));
+        let high = grid(|cell| Some(if cell.x < 8 { 0.0 } else { 1.0 }));
+        let start = center(&low, IVec2::new(2, 2));
+        let goal = Vec3::new(7.25, 1.0, 1.25);
+
+        assert!(low.find_path(start, goal, 1000).is_some());
+        assert_eq!(high.find_path(start, goal, 1000), None);
+    }
+
+    #[test]
+    fn search_gives_up_after_max_nodes() {
+        let grid = grid(|cell| (cell.x != 8 || cell.y == 15).then_some(0.0));
+        let start = center(&grid, IVec2::new(2, 2));
+        let goal = center(&grid, IVec2::new(14, 2));
+        assert_eq!(grid.find_path(start, goal, 10), None);
+    }
+
+    #[test]
+    fn goals_outside_baked_chunks_are_unreachable() {
+        let grid = grid(|_| Some(0.0));
+        let path = grid.find_path(center(&grid, IVec2::ZERO), Vec3::new(100.0, 0.0, 100.0), 1000);
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn paths_cross_the_chunks_of_their_segments() {
+        let grid = grid(|_| Some(0.0));
+        let path = [Vec3::new(1.0, 0.0, 1.0), Vec3::new(20.0, 0.0, 1.0)];
+        assert!(grid.path_crosses_chunks(&path, &HashSet::from([IVec2::new(1, 0)])));
+        assert!(grid.path_crosses_chunks(&path, &HashSet::from([IVec2::new(2, 0)])));
+        assert!(!grid.path_crosses_chunks(&path, &HashSet::from([IVec2::new(0, 1)])));
+    }
+}
@@ -0,0 +1,23 @@
+pub mod grid;
+pub mod agent;
+
+use bevy::prelude::*;
+use crate::navigation::agent::{NavAgent, NavChunkBaked, NavPathStatus, RebakeNavChunk};
+use crate::navigation::grid::{NavBakeQueue, NavMesh, NavSettings};
+
+pub struct NavigationModule;
+
+impl Plugin for NavigationModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavSettings>()
+            .init_resource::<NavMesh>()
+            .init_resource::<NavBakeQueue>();
+        app.add_message::<RebakeNavChunk>()
+            .add_message::<NavChunkBaked>();
+        app.register_type::<NavSettings>()
+            .register_type::<NavAgent>()
+            .register_type::<NavPathStatus>();
+    }
+}
@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+
+pub struct PacingModule;
+
+impl Plugin for PacingModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PacingSettings>()
+            .init_resource::<PacingDirector>();
+        app.add_message::<PacingPhaseChanged>();
+        app.register_type::<PacingSettings>()
+            .register_type::<PacingDirector>()
+            .register_type::<PacingPhase>()
+            .register_type::<PacingProfile>();
+    }
+}
+
+/// Phase of the pacing cycle. Threat builds up until the player is under pressure,
+/// stays high for a short peak and then backs off to give the player room to breathe.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum PacingPhase {
+    #[default]
+    BuildUp,
+    Peak,
+    Relax,
+}
+
+/// How strong the world pushes the player during a [`PacingPhase`].
+#[derive(Debug, Clone, Reflect)]
+pub struct PacingProfile {
+    /// Multiplier of the spawn rate, `0.0` pauses spawning.
+    pub spawn_rate: f32,
+    /// Multiplier of the enemy perception range.
+    pub aggression: f32,
+    /// Target music intensity in range `0.0..=1.0`.
+    pub music_intensity: f32,
+}
+
+/// Tuning of the pacing director. Editable at runtime in the inspector.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct PacingSettings {
+    /// Intensity gained per damage taken, relative to the max health of the player.
+    pub damage_weight: f32,
+    /// Intensity per second for every hostile enemy within `nearby_radius`.
+    pub nearby_enemy_weight: f32,
+    pub nearby_radius: f32,
+    /// Health fraction below which the player counts as low on health.
+    pub low_health_threshold: f32,
+    /// Intensity per second at zero health, fading out towards the threshold.
+    pub low_health_weight: f32,
+    /// Intensity per second in full darkness.
+    pub darkness_weight: f32,
+    /// Intensity lost per second.
+    pub decay: f32,
+    /// Intensity at which the build-up turns into a peak.
+    pub peak_threshold: f32,
+    /// Seconds a peak lasts.
+    pub peak_duration: f32,
+    /// Minimum seconds of relaxing.
+    pub relax_duration: f32,
+    /// Intensity below which relaxing ends.
+    pub relax_threshold: f32,
+    /// Speed at which the music intensity follows its target.
+    pub music_smoothing: f32,
+    pub build_up: PacingProfile,
+    pub peak: PacingProfile,
+    pub relax: PacingProfile,
+}
+
+impl Default for PacingSettings {
+    fn default() -> Self {
+        Self {
+            damage_weight: 1.5,
+            nearby_enemy_weight: 0.02,
+            nearby_radius: 15.0,
+            low_health_threshold: 0.35,
+            low_health_weight: 0.05,
+            darkness_weight: 0.01,
+            decay: 0.03,
+            peak_threshold: 0.8,
+            peak_duration: 20.0,
+            relax_duration: 30.0,
+            relax_threshold: 0.25,
+            music_smoothing: 0.5,
+            build_up: PacingProfile { spawn_rate: 1.0, aggression: 1.0, music_intensity: 0.4 },
+            peak: PacingProfile { spawn_rate: 1.5, aggression: 1.3, music_intensity: 1.0 },
+            relax: PacingProfile { spawn_rate: 0.0, aggression: 0.7, music_intensity: 0.1 },
+        }
+    }
+}
+
+impl PacingSettings {
+
+    pub fn profile(&self, phase: PacingPhase) -> &PacingProfile {
+        match phase {
+            PacingPhase::BuildUp => &self.build_up,
+            PacingPhase::Peak => &self.peak,
+            PacingPhase::Relax => &self.relax,
+        }
+    }
+}
+
+/// Current pacing state. Other systems read the multipliers, the debug overlay shows it.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct PacingDirector {
+    /// Stress of the player in range `0.0..=1.0`.
+    pub intensity: f32,
+    pub phase: PacingPhase,
+    /// Seconds since `phase` became active.
+    pub time_in_phase: f32,
+    /// Active spawn rate multiplier.
+    pub spawn_rate: f32,
+    /// Active enemy aggression multiplier.
+    pub aggression: f32,
+    /// Smoothed music intensity in range `0.0..=1.0`.
+    pub music_intensity: f32,
+}
+
+impl Default for PacingDirector {
+    fn default() -> Self {
+        Self {
+            intensity: 0.0,
+            phase: PacingPhase::BuildUp,
+            time_in_phase: 0.0,
+            spawn_rate: 1.0,
+            aggression: 1.0,
+            music_intensity: 0.0,
+        }
+    }
+}
+
+impl PacingDirector {
+
+    /// Returns the phase the director switches to, or `None` to stay.
+    pub fn next_phase(&self, settings: &PacingSettings) -> Option<PacingPhase> {
+        match self.phase {
+            PacingPhase::BuildUp if self.intensity >= settings.peak_threshold => Some(PacingPhase::Peak),
+            PacingPhase::Peak if self.time_in_phase >= settings.peak_duration => Some(PacingPhase::Relax),
+            PacingPhase::Relax if self.time_in_phase >= settings.relax_duration
+                && self.intensity <= settings.relax_threshold => Some(PacingPhase::BuildUp),
+            _ => None,
+        }
+    }
+}
+
+/// Emitted whenever the [`PacingPhase`] changes.
+#[derive(Message, Debug, Clone)]
+pub struct PacingPhaseChanged {
+    pub from: PacingPhase,
+    pub to: PacingPhase,
+}
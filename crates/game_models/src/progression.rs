@@ -0,0 +1,329 @@
+use std::fmt::{Display, Formatter};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+use crate::attributes::{AttributeModifier, AttributeModifiers};
+use crate::definitions::{AppDefinitionExt, Definition, DefinitionLibrary};
+use crate::entities::character::Character;
+
+pub struct ProgressionModule;
+
+impl Plugin for ProgressionModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<ProgressionDefinition>()
+            .register_definition::<SkillTreeDefinition>();
+        app.add_message::<GainExperience>()
+            .add_message::<LevelUp>()
+            .add_message::<UnlockSkill>()
+            .add_message::<SkillUnlocked>()
+            .add_message::<SkillUnlockFailed>();
+        app.register_type::<Character>();
+    }
+}
+
+// =================================================================================================
+//
+//                                          Definition
+//
+// =================================================================================================
+
+/// Level curve and skill tree of a [`Character`]. Authored as
+/// `assets/progression/<name>.progression.json`.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct ProgressionDefinition {
+    pub id: String,
+    pub curve: LevelCurve,
+    #[serde(default = "default_max_level")]
+    pub max_level: u32,
+    #[serde(default = "default_skill_points")]
+    pub skill_points_per_level: u32,
+    /// Id of the [`SkillTreeDefinition`] the skill points are spent on.
+    pub skill_tree: String,
+}
+
+impl Definition for ProgressionDefinition {
+    const FOLDER: &'static str = "progression";
+    const EXTENSIONS: &'static [&'static str] = &["progression.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+fn default_max_level() -> u32 {
+    30
+}
+
+fn default_skill_points() -> u32 {
+    1
+}
+
+impl ProgressionDefinition {
+
+    /// Total experience needed to reach `level`, see [`LevelCurve::experience_for_level`].
+    pub fn experience_for_level(&self, level: u32) -> u32 {
+        self.curve.experience_for_level(level.min(self.max_level))
+    }
+}
+
+/// Total experience needed per level: `base * (level - 1)^exponent`, rounded.
+/// Level 1 needs no experience.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LevelCurve {
+    pub base: f32,
+    pub exponent: f32,
+}
+
+impl LevelCurve {
+    pub fn experience_for_level(&self, level: u32) -> u32 {
+        if level <= 1 {
+            return 0;
+        }
+        (self.base * ((level - 1) as f32).powf(self.exponent)).round() as u32
+    }
+}
+
+/// Nodes skill points are spent on. Authored as `assets/skills/<name>.skills.json`.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct SkillTreeDefinition {
+    pub id: String,
+    pub nodes: Vec<SkillNode>,
+}
+
+impl Definition for SkillTreeDefinition {
+    const FOLDER: &'static str = "skills";
+    const EXTENSIONS: &'static [&'static str] = &["skills.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Single skill of a [`SkillTreeDefinition`].
+#[derive(Deserialize, Debug, Clone)]
+pub struct SkillNode {
+    /// Unique id within the tree, stored in [`Character::skills`].
+    pub id: String,
+    /// Localization key of the name.
+    pub name: String,
+    /// Localization key of the description.
+    pub description: String,
+    /// Position in the skill tree UI.
+    #[serde(default)]
+    pub position: [f32; 2],
+    /// Skill points spent on unlock.
+    #[serde(default = "default_skill_points")]
+    pub cost: u32,
+    #[serde(default)]
+    pub min_level: u32,
+    /// Ids of the nodes which have to be unlocked first.
+    #[serde(default)]
+    pub requires: Vec<String>,
+    /// Applied to the player while the skill is unlocked. The source is replaced by
+    /// [`skill_modifier_source`].
+    #[serde(default)]
+    pub modifiers: Vec<AttributeModifier>,
+    /// Ids of the abilities the skill unlocks, see [`Character::has_ability`].
+    #[serde(default)]
+    pub unlocks: Vec<String>,
+}
+
+impl SkillTreeDefinition {
+
+    pub fn node(&self, id: &str) -> Option<&SkillNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    /// Checks whether `character` can unlock the node `skill`.
+    ///
+    /// # Returns
+    /// The node to pass to [`Character::unlock`] or why it can't be unlocked.
+    pub fn check_unlock(&self, character: &Character, skill: &str) -> Result<&SkillNode, SkillError> {
+        let node = self.node(skill).ok_or_else(|| SkillError::UnknownSkill(skill.to_string()))?;
+        if character.has_skill(skill) {
+            return Err(SkillError::AlreadyUnlocked);
+        }
+        if character.level < node.min_level {
+            return Err(SkillError::LevelTooLow { required: node.min_level, level: character.level });
+        }
+        if let Some(missing) = node.requires.iter().find(|required| !character.has_skill(required)) {
+            return Err(SkillError::MissingRequirement(missing.clone()));
+        }
+        if character.skill_points < node.cost {
+            return Err(SkillError::NotEnoughPoints { required: node.cost, available: character.skill_points });
+        }
+        Ok(node)
+    }
+}
+
+/// Reasons a skill can't be unlocked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkillError {
+    /// The skill is not part of the tree, or the tree is not loaded.
+    UnknownSkill(String),
+    AlreadyUnlocked,
+    LevelTooLow { required: u32, level: u32 },
+    /// The given node has to be unlocked first.
+    MissingRequirement(String),
+    NotEnoughPoints { required: u32, available: u32 },
+}
+
+impl Display for SkillError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkillError::UnknownSkill(skill) => write!(f, "Skill '{}' is not loaded", skill),
+            SkillError::AlreadyUnlocked => write!(f, "Skill is already unlocked"),
+            SkillError::LevelTooLow { required, level } => write!(f, "Requires level {}, is {}", required, level),
+            SkillError::MissingRequirement(skill) => write!(f, "Requires skill '{}'", skill),
+            SkillError::NotEnoughPoints { required, available } => {
+                write!(f, "Not enough skill points: required {}, available {}", required, available)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SkillError {}
+
+/// Read only access to all loaded progression and skill tree definitions by id.
+#[derive(SystemParam)]
+pub struct Progressions<'w> {
+    library: Res<'w, DefinitionLibrary<ProgressionDefinition>>,
+    assets: Res<'w, Assets<ProgressionDefinition>>,
+    tree_library: Res<'w, DefinitionLibrary<SkillTreeDefinition>>,
+    tree_assets: Res<'w, Assets<SkillTreeDefinition>>,
+}
+
+impl<'w> Progressions<'w> {
+
+    pub fn get(&self, id: &str) -> Option<&ProgressionDefinition> {
+        self.library.get(id, &self.assets)
+    }
+
+    pub fn skill_tree(&self, id: &str) -> Option<&SkillTreeDefinition> {
+        self.tree_library.get(id, &self.tree_assets)
+    }
+
+    /// Skill tree of the progression of `character`.
+    pub fn skill_tree_of(&self, character: &Character) -> Option<&SkillTreeDefinition> {
+        self.get(&character.progression).and_then(|progression| self.skill_tree(&progression.skill_tree))
+    }
+}
+
+// =================================================================================================
+//
+//                                            Runtime
+//
+// =================================================================================================
+
+/// Source id of the attribute modifiers of the skill `id`.
+pub fn skill_modifier_source(id: &str) -> String {
+    format!("skill:{}", id)
+}
+
+/// Replaces the skill modifiers in `modifiers` with the ones of all nodes of `tree`
+/// unlocked by `character`.
+pub fn apply_skill_modifiers(modifiers: &mut AttributeModifiers, tree: &SkillTreeDefinition, character: &Character) {
+    for node in tree.nodes.iter() {
+        let source = skill_modifier_source(&node.id);
+        modifiers.remove_source(&source);
+        if !character.has_skill(&node.id) {
+            continue;
+        }
+        for modifier in node.modifiers.iter() {
+            let mut modifier = modifier.clone();
+            modifier.source = source.clone();
+            modifiers.add(modifier);
+        }
+    }
+}
+
+// =================================================================================================
+//
+//                                           Requests
+//
+// =================================================================================================
+
+/// What granted experience.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExperienceSource {
+    /// Killed an entity of the given definition id.
+    Kill(String),
+    /// Completed the given quest.
+    Quest(String),
+    /// Crafted the given recipe.
+    Crafting(String),
+}
+
+/// Grants experience to the [`Character`].
+#[derive(Message, Debug, Clone)]
+pub struct GainExperience {
+    pub amount: u32,
+    pub source: ExperienceSource,
+}
+
+/// Spends skill points of the [`Character`] on the node `skill` of its skill tree.
+#[derive(Message, Debug, Clone)]
+pub struct UnlockSkill {
+    pub skill: String,
+}
+
+// =================================================================================================
+//
+//                                        Notifications
+//
+// =================================================================================================
+
+/// The [`Character`] reached a new level.
+#[derive(Message, Debug, Clone)]
+pub struct LevelUp {
+    pub level: u32,
+    /// Unspent skill points after the level up.
+    pub skill_points: u32,
+}
+
+#[derive(Message, Debug, Clone)]
+pub struct SkillUnlocked {
+    pub skill: String,
+}
+
+#[derive(Message, Debug, Clone)]
+pub struct SkillUnlockFailed {
+    pub skill: String,
+    pub error: SkillError,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_one_needs_no_experience() {
+        let curve = LevelCurve { base: 100.0, exponent: 1.5 };
+        assert_eq!(curve.experience_for_level(0), 0);
+        assert_eq!(curve.experience_for_level(1), 0);
+    }
+
+    #[test]
+    fn experience_follows_the_curve_and_is_rounded() {
+        let curve = LevelCurve { base: 100.0, exponent: 1.5 };
+        assert_eq!(curve.experience_for_level(2), 100);
+        assert_eq!(curve.experience_for_level(3), 283);
+        assert_eq!(curve.experience_for_level(4), 520);
+        assert_eq!(curve.experience_for_level(5), 800);
+    }
+
+    #[test]
+    fn experience_is_capped_at_the_max_level() {
+        let definition = ProgressionDefinition {
+            id: "player".to_string(),
+            curve: LevelCurve { base: 50.0, exponent: 2.0 },
+            max_level: 3,
+            skill_points_per_level: 1,
+            skill_tree: "player".to_string(),
+        };
+        assert_eq!(definition.experience_for_level(3), 200);
+        assert_eq!(definition.experience_for_level(10), 200);
+    }
+}
@@ -0,0 +1,417 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::definitions::{AppDefinitionExt, Definition, DefinitionLibrary};
+use crate::health::DamageType;
+use crate::rng::{clock_seed, SeededRng};
+use crate::status_effects::EffectApplication;
+
+pub struct ProjectilesModule;
+
+impl Plugin for ProjectilesModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<ProjectileDefinition>();
+        app.init_resource::<ProjectileSettings>()
+            .init_resource::<ProjectileRng>()
+            .init_resource::<ProjectilePool>();
+        app.add_message::<FireWeapon>()
+            .add_message::<ProjectileFired>()
+            .add_message::<ProjectileHit>()
+            .add_message::<OutOfAmmo>();
+        app.register_type::<ProjectileSettings>()
+            .register_type::<ProjectileRng>()
+            .register_type::<Projectile>()
+            .register_type::<PooledProjectile>()
+            .register_type::<RangedCooldown>();
+    }
+}
+
+// =================================================================================================
+//
+//                                          Definition
+//
+// =================================================================================================
+
+/// Arrow, bullet or thrown object. Authored as `assets/projectiles/<name>.projectile.json`
+/// and referenced by the [`RangedData`] of weapons.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct ProjectileDefinition {
+    pub id: String,
+    /// Damage dealt to the first target.
+    pub damage: f32,
+    #[serde(default = "default_damage_type")]
+    pub damage_type: DamageType,
+    /// Launch speed in meters per second.
+    pub speed: f32,
+    /// Multiplier of [`ProjectileSettings::gravity`], `0.0` flies straight.
+    #[serde(default = "default_one")]
+    pub gravity: f32,
+    /// Number of targets the projectile passes through after the first.
+    #[serde(default)]
+    pub penetration: u32,
+    /// Damage multiplier applied for every penetrated target.
+    #[serde(default = "default_penetration_falloff")]
+    pub penetration_falloff: f32,
+    /// Seconds in flight before the projectile is returned to the pool.
+    #[serde(default = "default_lifetime")]
+    pub lifetime: f32,
+    /// Length of the visual along the flight direction.
+    #[serde(default = "default_length")]
+    pub length: f32,
+    /// Thickness of the visual.
+    #[serde(default = "default_thickness")]
+    pub thickness: f32,
+    #[serde(default = "default_color")]
+    pub color: [f32; 3],
+    /// Status effects applied to hit targets.
+    #[serde(default)]
+    pub effects: Vec<EffectApplication>,
+}
+
+impl Definition for ProjectileDefinition {
+    const FOLDER: &'static str = "projectiles";
+    const EXTENSIONS: &'static [&'static str] = &["projectile.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Ranged part of the [`EquipmentData`](crate::items::equipment::EquipmentData) of a weapon.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RangedData {
+    /// Id of the fired [`ProjectileDefinition`].
+    pub projectile: String,
+    /// Item consumed from the inventory per shot, `None` for unlimited ammo. Throwables
+    /// use their own id.
+    #[serde(default)]
+    pub ammo: Option<String>,
+    #[serde(default = "default_ammo_per_shot")]
+    pub ammo_per_shot: u32,
+    /// Seconds between two shots.
+    #[serde(default = "default_cooldown")]
+    pub cooldown: f32,
+    /// Projectiles per shot, e.g. pellets of a shotgun.
+    #[serde(default = "default_ammo_per_shot")]
+    pub projectiles_per_shot: u32,
+    /// Opening angle of the cone the projectiles are scattered in, in degrees.
+    #[serde(default)]
+    pub spread: f32,
+    /// Multiplier of the projectile speed, e.g. the draw strength of a bow.
+    #[serde(default = "default_one")]
+    pub speed_multiplier: f32,
+}
+
+fn default_damage_type() -> DamageType {
+    DamageType::Physical
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+fn default_penetration_falloff() -> f32 {
+    0.5
+}
+
+fn default_lifetime() -> f32 {
+    5.0
+}
+
+fn default_length() -> f32 {
+    0.6
+}
+
+fn default_thickness() -> f32 {
+    0.04
+}
+
+fn default_color() -> [f32; 3] {
+    [0.55, 0.45, 0.3]
+}
+
+fn default_ammo_per_shot() -> u32 {
+    1
+}
+
+fn default_cooldown() -> f32 {
+    0.5
+}
+
+// =================================================================================================
+//
+//                                            Runtime
+//
+// =================================================================================================
+
+/// Read only access to all loaded [`ProjectileDefinition`]s by id.
+#[derive(SystemParam)]
+pub struct Projectiles<'w> {
+    library: Res<'w, DefinitionLibrary<ProjectileDefinition>>,
+    assets: Res<'w, Assets<ProjectileDefinition>>,
+}
+
+impl<'w> Projectiles<'w> {
+
+    pub fn get(&self, id: &str) -> Option<&ProjectileDefinition> {
+        self.library.get(id, &self.assets)
+    }
+}
+
+/// Tuning of the projectile simulation and the pool. Editable at runtime in the inspector.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct ProjectileSettings {
+    /// Downward acceleration in meters per second squared.
+    pub gravity: f32,
+    /// Projectile entities created up front.
+    pub pool_size: usize,
+    /// Free entities kept available once the initial pool is used up. The pool grows
+    /// in small batches instead of spawning during a shot.
+    pub pool_reserve: usize,
+    /// Upper limit of pooled entities. Shots are dropped when all of them are in flight.
+    pub max_pooled: usize,
+    /// Seconds a projectile stays stuck in the world before it returns to the pool.
+    pub stuck_time: f32,
+    /// Launch point relative to the shooter, `-Z` is forward.
+    pub muzzle_offset: Vec3,
+    /// Seed of the [`ProjectileRng`]. Drawn from the clock, set a fixed seed to
+    /// reproduce the spread of a session.
+    pub seed: u64,
+}
+
+impl Default for ProjectileSettings {
+    fn default() -> Self {
+        Self {
+            gravity: 9.81,
+            pool_size: 128,
+            pool_reserve: 32,
+            max_pooled: 1024,
+            stuck_time: 8.0,
+            muzzle_offset: Vec3::new(0.0, 0.5, -0.6),
+            seed: clock_seed(),
+        }
+    }
+}
+
+/// Random source of the weapon spread, seeded from [`ProjectileSettings::seed`].
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct ProjectileRng(pub SeededRng);
+
+impl FromWorld for ProjectileRng {
+    fn from_world(world: &mut World) -> Self {
+        let settings = world.get_resource_or_init::<ProjectileSettings>();
+        Self(SeededRng::new(settings.seed))
+    }
+}
+
+/// Free projectile entities. Pooled entities keep their mesh and are hidden while free,
+/// firing only inserts a [`Projectile`] and moves them.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ProjectilePool {
+    free: Vec<Entity>,
+    total: usize,
+}
+
+impl ProjectilePool {
+
+    /// Number of pooled entities, free and in flight.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn free(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Number of entities to spawn so the pool has its initial size and enough free
+    /// entities, limited by the maximum pool size.
+    pub fn missing(&self, settings: &ProjectileSettings) -> usize {
+        let wanted = settings.pool_size
+            .saturating_sub(self.total)
+            .max(settings.pool_reserve.saturating_sub(self.free.len()));
+        wanted.min(settings.max_pooled.saturating_sub(self.total))
+    }
+
+    /// Adds a newly spawned, free entity.
+    pub fn add(&mut self, entity: Entity) {
+        self.total += 1;
+        self.free.push(entity);
+    }
+
+    /// Takes a free entity, `None` if all are in flight.
+    pub fn acquire(&mut self) -> Option<Entity> {
+        self.free.pop()
+    }
+
+    /// Returns an entity taken with [`ProjectilePool::acquire`].
+    pub fn release(&mut self, entity: Entity) {
+        if !self.free.contains(&entity) {
+            self.free.push(entity);
+        }
+    }
+}
+
+/// Marker of entities owned by the [`ProjectilePool`].
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct PooledProjectile;
+
+/// Projectile in flight. Integrated ballistically and swept with ray casts, so fast
+/// projectiles can't tunnel through thin colliders.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Projectile {
+    pub definition: String,
+    /// Entity which fired the projectile, never hit by it.
+    pub shooter: Option<Entity>,
+    pub velocity: Vec3,
+    /// Downward acceleration in meters per second squared.
+    pub gravity: f32,
+    /// Damage dealt to the next target.
+    pub damage: f32,
+    pub damage_type: DamageType,
+    pub penetration_falloff: f32,
+    /// Targets the projectile can still pass through.
+    pub penetration_left: u32,
+    /// Seconds since fired, or since stuck.
+    pub age: f32,
+    pub lifetime: f32,
+    /// Targets already hit, every target is hit once.
+    pub hit: Vec<Entity>,
+    /// The projectile hit the world and stays in place.
+    pub stuck: bool,
+}
+
+impl Projectile {
+
+    /// Creates a projectile of `definition` with the given launch velocity.
+    ///
+    /// # Parameters
+    /// * `definition` - Damage, penetration and lifetime of the projectile.
+    /// * `shooter` - Entity which fired the projectile.
+    /// * `velocity` - Launch velocity.
+    /// * `gravity` - Gravity of the world, scaled by the definition.
+    pub fn new(definition: &ProjectileDefinition, shooter: Option<Entity>, velocity: Vec3, gravity: f32) -> Self {
+        Self {
+            definition: definition.id.clone(),
+            shooter,
+            velocity,
+            gravity: gravity * definition.gravity,
+            damage: definition.damage,
+            damage_type: definition.damage_type,
+            penetration_falloff: definition.penetration_falloff,
+            penetration_left: definition.penetration,
+            age: 0.0,
+            lifetime: definition.lifetime,
+            hit: Vec::new(),
+            stuck: false,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.age >= self.lifetime
+    }
+
+    /// Advances the projectile by `delta` seconds with semi-implicit Euler.
+    ///
+    /// # Returns
+    /// The position at the end of the step, `position` for stuck projectiles.
+    pub fn integrate(&mut self, position: Vec3, delta: f32) -> Vec3 {
+        self.age += delta;
+        if self.stuck {
+            return position;
+        }
+        self.velocity.y -= self.gravity * delta;
+        position + self.velocity * delta
+    }
+
+    /// Registers a hit of `target`.
+    ///
+    /// # Returns
+    /// The damage dealt and whether the projectile passes through the target.
+    pub fn penetrate(&mut self, target: Entity) -> (f32, bool) {
+        let damage = self.damage;
+        self.hit.push(target);
+        if self.penetration_left == 0 {
+            return (damage, false);
+        }
+        self.penetration_left -= 1;
+        self.damage *= self.penetration_falloff;
+        (damage, true)
+    }
+
+    /// Stops the projectile in the world for `duration` seconds.
+    pub fn stick(&mut self, duration: f32) {
+        self.stuck = true;
+        self.velocity = Vec3::ZERO;
+        self.age = 0.0;
+        self.lifetime = duration;
+    }
+}
+
+/// Time of the last shot of a ranged weapon user.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct RangedCooldown {
+    /// Elapsed game seconds at which the next shot is possible.
+    pub ready_at: f32,
+}
+
+/// Random direction within a cone of `spread` degrees around `direction`.
+pub fn spread_direction(direction: Vec3, spread: f32, rng: &mut SeededRng) -> Vec3 {
+    if spread <= 0.0 {
+        return direction;
+    }
+    let (right, up) = direction.any_orthonormal_pair();
+    let angle = rng.range_f32(0.0, spread.to_radians() * 0.5);
+    let around = rng.range_f32(0.0, std::f32::consts::TAU);
+    let offset = (right * around.cos() + up * around.sin()) * angle.sin();
+    (direction * angle.cos() + offset).normalize_or(direction)
+}
+
+// =================================================================================================
+//
+//                                           Requests
+//
+// =================================================================================================
+
+/// Request: `entity` fires its equipped ranged weapon at `target`, straight ahead if `None`.
+#[derive(Message, Debug, Clone)]
+pub struct FireWeapon {
+    pub entity: Entity,
+    pub target: Option<Vec3>,
+}
+
+// =================================================================================================
+//
+//                                        Notifications
+//
+// =================================================================================================
+
+/// `shooter` fired a shot of `projectile`.
+#[derive(Message, Debug, Clone)]
+pub struct ProjectileFired {
+    pub shooter: Entity,
+    pub projectile: String,
+}
+
+/// A projectile hit a damageable `target`.
+#[derive(Message, Debug, Clone)]
+pub struct ProjectileHit {
+    pub projectile: String,
+    pub shooter: Option<Entity>,
+    pub target: Entity,
+    /// Damage before resistances.
+    pub damage: f32,
+}
+
+/// `entity` tried to fire without enough `ammo` in its inventory.
+#[derive(Message, Debug, Clone)]
+pub struct OutOfAmmo {
+    pub entity: Entity,
+    pub ammo: String,
+}
@@ -0,0 +1,397 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::definitions::{AppDefinitionExt, Definition};
+
+pub struct QuestsModule;
+
+impl Plugin for QuestsModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<QuestDefinition>();
+        app.init_resource::<QuestJournal>();
+        app.add_message::<StartQuest>()
+            .add_message::<QuestEvent>()
+            .add_message::<AreaReached>()
+            .add_message::<QuestStarted>()
+            .add_message::<QuestObjectiveProgressed>()
+            .add_message::<QuestStageCompleted>()
+            .add_message::<QuestCompleted>();
+        app.register_type::<QuestLog>()
+            .register_type::<QuestProgress>()
+            .register_type::<QuestStatus>()
+            .register_type::<QuestArea>();
+    }
+}
+
+// =================================================================================================
+//
+//                                          Definition
+//
+// =================================================================================================
+
+/// Quest authored as `assets/quests/<name>.quest.json`. Stages are completed one
+/// after another, a stage is done when all of its objectives are done.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct QuestDefinition {
+    pub id: String,
+    /// Localization key of the quest name.
+    pub name: String,
+    /// Localization key of the quest description.
+    pub description: String,
+    pub stages: Vec<QuestStage>,
+    /// Granted when the last stage is completed.
+    #[serde(default)]
+    pub rewards: Vec<QuestReward>,
+}
+
+impl Definition for QuestDefinition {
+    const FOLDER: &'static str = "quests";
+    const EXTENSIONS: &'static [&'static str] = &["quest.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// One step of a quest.
+#[derive(Deserialize, Debug, Clone)]
+pub struct QuestStage {
+    /// Localization key of the journal text of this stage.
+    pub description: String,
+    pub objectives: Vec<QuestObjective>,
+}
+
+/// Task of a [`QuestStage`].
+#[derive(Deserialize, Debug, Clone)]
+pub struct QuestObjective {
+    /// Localization key of the journal text.
+    pub description: String,
+    #[serde(flatten)]
+    pub kind: ObjectiveKind,
+}
+
+/// What has to be done to complete a [`QuestObjective`].
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObjectiveKind {
+    /// Kill `count` entities spawned from the entity definition `entity`.
+    Kill { entity: String, #[serde(default = "default_count")] count: u32 },
+    /// Carry `count` items of `item`.
+    Collect { item: String, #[serde(default = "default_count")] count: u32 },
+    /// Enter the [`QuestArea`] with the id `area`.
+    ReachArea { area: String },
+    /// Start a dialogue with an entity spawned from the entity definition `entity`.
+    TalkTo { entity: String },
+    /// Stay alive until the next dawn.
+    SurviveUntilDawn,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+impl ObjectiveKind {
+
+    /// Progress needed to complete the objective.
+    pub fn required(&self) -> u32 {
+        match self {
+            ObjectiveKind::Kill { count, .. } | ObjectiveKind::Collect { count, .. } => (*count).max(1),
+            _ => 1,
+        }
+    }
+
+    /// Returns the new progress of the objective after `event`, or `None` if the event
+    /// does not affect it.
+    pub fn progress_after(&self, event: &QuestEventKind, progress: u32) -> Option<u32> {
+        let progress = match (self, event) {
+            (ObjectiveKind::Kill { entity, .. }, QuestEventKind::Killed { entity: killed }) if entity == killed => progress + 1,
+            (ObjectiveKind::Collect { item, .. }, QuestEventKind::Carried { item: carried, count }) if item == carried => *count,
+            (ObjectiveKind::ReachArea { area }, QuestEventKind::AreaReached { area: reached }) if area == reached => 1,
+            (ObjectiveKind::TalkTo { entity }, QuestEventKind::TalkedTo { entity: talked }) if entity == talked => 1,
+            (ObjectiveKind::SurviveUntilDawn, QuestEventKind::Dawn) => 1,
+            _ => return None,
+        };
+        Some(progress.min(self.required()))
+    }
+}
+
+/// Reward of a completed quest.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuestReward {
+    Item { item: String, #[serde(default = "default_count")] count: u32 },
+    Flag { flag: String },
+    Experience { amount: u32 },
+    Reputation { faction: String, amount: i32 },
+}
+
+// =================================================================================================
+//
+//                                            Runtime
+//
+// =================================================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestStatus {
+    #[default]
+    Active,
+    Completed,
+}
+
+/// Progress of one quest in a [`QuestLog`].
+#[derive(Debug, Clone, PartialEq, Reflect, Deserialize, Serialize)]
+pub struct QuestProgress {
+    pub quest: String,
+    pub status: QuestStatus,
+    /// Index of the active stage.
+    pub stage: usize,
+    /// Progress of every objective of the active stage.
+    pub objectives: Vec<u32>,
+}
+
+/// Result of [`QuestProgress::advance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageAdvance {
+    /// The active stage still has open objectives.
+    Open,
+    /// The stage with the given index was completed and the next one started.
+    NextStage(usize),
+    /// The last stage was completed.
+    Completed,
+}
+
+impl QuestProgress {
+
+    pub fn new(definition: &QuestDefinition) -> Self {
+        let mut progress = Self {
+            quest: definition.id.clone(),
+            status: QuestStatus::Active,
+            stage: 0,
+            objectives: Vec::new(),
+        };
+        progress.reset_objectives(definition);
+        progress
+    }
+
+    pub fn current_stage<'a>(&self, definition: &'a QuestDefinition) -> Option<&'a QuestStage> {
+        definition.stages.get(self.stage)
+    }
+
+    fn reset_objectives(&mut self, definition: &QuestDefinition) {
+        let count = self.current_stage(definition).map_or(0, |stage| stage.objectives.len());
+        self.objectives = vec![0; count];
+    }
+
+    /// Applies `event` to the objectives of the active stage.
+    ///
+    /// # Returns
+    /// Indices of the objectives whose progress changed.
+    pub fn record(&mut self, definition: &QuestDefinition, event: &QuestEventKind) -> Vec<usize> {
+        if self.status != QuestStatus::Active {
+            return Vec::new();
+        }
+        let Some(stage) = definition.stages.get(self.stage) else {
+            return Vec::new();
+        };
+
+        let mut changed = Vec::new();
+        for (index, objective) in stage.objectives.iter().enumerate() {
+            let Some(current) = self.objectives.get_mut(index) else {
+                continue;
+            };
+            if let Some(progress) = objective.kind.progress_after(event, *current)
+                && progress != *current {
+                *current = progress;
+                changed.push(index);
+            }
+        }
+        changed
+    }
+
+    /// Completes the active stage if all of its objectives are done.
+    pub fn advance(&mut self, definition: &QuestDefinition) -> StageAdvance {
+        if self.status != QuestStatus::Active {
+            return StageAdvance::Open;
+        }
+        let done = self.current_stage(definition).is_none_or(|stage| stage
+            .objectives
+            .iter()
+            .zip(self.objectives.iter())
+            .all(|(objective, progress)| *progress >= objective.kind.required()));
+        if !done {
+            return StageAdvance::Open;
+        }
+
+        let completed = self.stage;
+        self.stage += 1;
+        if self.stage >= definition.stages.len() {
+            self.status = QuestStatus::Completed;
+            self.objectives.clear();
+            return StageAdvance::Completed;
+        }
+        self.reset_objectives(definition);
+        StageAdvance::NextStage(completed)
+    }
+}
+
+/// Quests known by an entity, usually the player.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct QuestLog {
+    pub quests: Vec<QuestProgress>,
+}
+
+impl QuestLog {
+
+    pub fn get(&self, quest: &str) -> Option<&QuestProgress> {
+        self.quests.iter().find(|progress| progress.quest == quest)
+    }
+
+    pub fn is_active(&self, quest: &str) -> bool {
+        self.get(quest).is_some_and(|progress| progress.status == QuestStatus::Active)
+    }
+
+    pub fn is_completed(&self, quest: &str) -> bool {
+        self.get(quest).is_some_and(|progress| progress.status == QuestStatus::Completed)
+    }
+
+    /// Starts the quest. Known quests (active or completed) are not started again.
+    ///
+    /// # Returns
+    /// `true` if the quest was started.
+    pub fn start(&mut self, definition: &QuestDefinition) -> bool {
+        if self.get(&definition.id).is_some() {
+            return false;
+        }
+        self.quests.push(QuestProgress::new(definition));
+        true
+    }
+
+    /// Serializable state for the [`GameSave`](crate::save_game::GameSave).
+    pub fn to_save(&self) -> QuestLogSave {
+        QuestLogSave { quests: self.quests.clone() }
+    }
+
+    /// Restores a saved state.
+    pub fn load(&mut self, save: QuestLogSave) {
+        self.quests = save.quests;
+    }
+}
+
+/// Saved state of a [`QuestLog`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct QuestLogSave {
+    pub quests: Vec<QuestProgress>,
+}
+
+/// Target area of [`ObjectiveKind::ReachArea`] objectives.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct QuestArea {
+    pub id: String,
+    pub radius: f32,
+}
+
+/// Localized quests of the player, prepared for the journal UI.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct QuestJournal {
+    /// Active quests first, completed ones after them.
+    pub entries: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournalEntry {
+    pub quest: String,
+    pub name: String,
+    pub description: String,
+    pub completed: bool,
+    /// Text of the active stage, empty once completed.
+    pub stage: String,
+    pub objectives: Vec<JournalObjective>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournalObjective {
+    pub text: String,
+    pub progress: u32,
+    pub required: u32,
+}
+
+// =================================================================================================
+//
+//                                           Requests
+//
+// =================================================================================================
+
+/// Request: starts the quest `quest` for `entity`. Written by dialogue effects too.
+#[derive(Message, Debug, Clone)]
+pub struct StartQuest {
+    pub entity: Entity,
+    pub quest: String,
+}
+
+/// Gameplay event relevant for objectives, translated from other messages by the
+/// quest logic.
+#[derive(Message, Debug, Clone)]
+pub struct QuestEvent {
+    /// Quest log the event belongs to, `None` for all.
+    pub entity: Option<Entity>,
+    pub kind: QuestEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuestEventKind {
+    /// An entity of the given definition was killed.
+    Killed { entity: String },
+    /// The inventory now holds `count` items of `item`.
+    Carried { item: String, count: u32 },
+    AreaReached { area: String },
+    /// A dialogue with an entity of the given definition was started.
+    TalkedTo { entity: String },
+    /// A new day started.
+    Dawn,
+}
+
+// =================================================================================================
+//
+//                                        Notifications
+//
+// =================================================================================================
+
+/// `entity` entered the [`QuestArea`] `area`.
+#[derive(Message, Debug, Clone)]
+pub struct AreaReached {
+    pub entity: Entity,
+    pub area: String,
+}
+
+#[derive(Message, Debug, Clone)]
+pub struct QuestStarted {
+    pub entity: Entity,
+    pub quest: String,
+}
+
+#[derive(Message, Debug, Clone)]
+pub struct QuestObjectiveProgressed {
+    pub entity: Entity,
+    pub quest: String,
+    pub stage: usize,
+    pub objective: usize,
+    pub progress: u32,
+    pub required: u32,
+}
+
+#[derive(Message, Debug, Clone)]
+pub struct QuestStageCompleted {
+    pub entity: Entity,
+    pub quest: String,
+    pub stage: usize,
+}
+
+#[derive(Message, Debug, Clone)]
+pub struct QuestCompleted {
+    pub entity: Entity,
+    pub quest: String,
+}
@@ -0,0 +1,234 @@
+use std::collections::VecDeque;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+use crate::definitions::{AppDefinitionExt, Definition, DefinitionLibrary};
+
+pub struct RagdollModule;
+
+impl Plugin for RagdollModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<RagdollDefinition>();
+        app.init_resource::<RagdollSettings>()
+            .init_resource::<ActiveRagdolls>();
+        app.register_type::<RagdollSettings>()
+            .register_type::<Ragdoll>()
+            .register_type::<RagdollBody>();
+    }
+}
+
+// =================================================================================================
+//
+//                                          Definition
+//
+// =================================================================================================
+
+/// Physics bodies built from the skeleton of a glTF character when it dies. Authored as
+/// `assets/ragdolls/<name>.ragdoll.json` and referenced by entity definitions.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct RagdollDefinition {
+    pub id: String,
+    /// Bones with a body. Parents have to be listed before their children.
+    pub bones: Vec<RagdollBone>,
+}
+
+impl Definition for RagdollDefinition {
+    const FOLDER: &'static str = "ragdolls";
+    const EXTENSIONS: &'static [&'static str] = &["ragdoll.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Capsule along the `+Y` axis of a bone, jointed to the body of `parent`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RagdollBone {
+    /// Name of the bone node in the scene.
+    pub bone: String,
+    /// Bone of the body this one is jointed to, `None` for the root body.
+    #[serde(default)]
+    pub parent: Option<String>,
+    pub length: f32,
+    pub radius: f32,
+    #[serde(default = "default_density")]
+    pub density: f32,
+    /// Degrees the bone can swing away from its rest direction.
+    #[serde(default = "default_swing")]
+    pub swing: f32,
+    /// Degrees the bone can twist around its own axis.
+    #[serde(default = "default_twist")]
+    pub twist: f32,
+}
+
+fn default_density() -> f32 {
+    1.0
+}
+
+fn default_swing() -> f32 {
+    45.0
+}
+
+fn default_twist() -> f32 {
+    20.0
+}
+
+impl RagdollBone {
+
+    /// Capsule from the origin of the bone towards its tip.
+    pub fn collider(&self) -> Collider {
+        let half_height = (self.length * 0.5 - self.radius).max(0.01);
+        Collider::compound(vec![(Vec3::Y * self.length * 0.5, Quat::IDENTITY, Collider::capsule_y(half_height, self.radius))])
+    }
+
+    /// Joint to the parent body, which keeps the rest pose of both bodies.
+    ///
+    /// # Parameters
+    /// * `parent` - World transform of the parent body.
+    /// * `child` - World transform of this body.
+    pub fn joint(&self, parent: &Transform, child: &Transform) -> SphericalJoint {
+        let (anchor, basis) = joint_frame(parent, child);
+        let swing = self.swing.to_radians();
+        let twist = self.twist.to_radians();
+        let mut joint = SphericalJointBuilder::new()
+            .local_anchor1(anchor)
+            .local_anchor2(Vec3::ZERO)
+            .limits(JointAxis::AngX, [-swing, swing])
+            .limits(JointAxis::AngY, [-twist, twist])
+            .limits(JointAxis::AngZ, [-swing, swing])
+            .build();
+        joint.set_local_basis1(basis).set_contacts_enabled(false);
+        joint
+    }
+}
+
+/// Anchor and orientation of the `child` body in the local space of the `parent` body.
+pub fn joint_frame(parent: &Transform, child: &Transform) -> (Vec3, Quat) {
+    let inverse = parent.rotation.inverse();
+    (inverse * (child.translation - parent.translation), inverse * child.rotation)
+}
+
+/// Tuning of the ragdolls. Editable at runtime in the inspector.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct RagdollSettings {
+    /// Ragdolls simulated at the same time. The oldest one freezes in its pose when
+    /// another character dies.
+    pub max_ragdolls: usize,
+    /// Speed away from the killer added to the velocity of the body.
+    pub blow_speed: f32,
+    /// Angular speed which tips over characters without a skeleton.
+    pub topple_speed: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+}
+
+impl Default for RagdollSettings {
+    fn default() -> Self {
+        Self {
+            max_ragdolls: 8,
+            blow_speed: 4.0,
+            topple_speed: 2.0,
+            linear_damping: 0.4,
+            angular_damping: 1.0,
+        }
+    }
+}
+
+/// Read only access to all loaded [`RagdollDefinition`]s by id.
+#[derive(SystemParam)]
+pub struct Ragdolls<'w> {
+    library: Res<'w, DefinitionLibrary<RagdollDefinition>>,
+    assets: Res<'w, Assets<RagdollDefinition>>,
+}
+
+impl<'w> Ragdolls<'w> {
+
+    pub fn get(&self, id: &str) -> Option<&RagdollDefinition> {
+        self.library.get(id, &self.assets)
+    }
+}
+
+// =================================================================================================
+//
+//                                            Runtime
+//
+// =================================================================================================
+
+/// Velocity of a ragdoll: the velocity of the character plus the killing blow, pushing
+/// it away from the killer.
+///
+/// # Parameters
+/// * `position` - Position of the dying character.
+/// * `killer` - Position of the killer, if known.
+/// * `inherited` - Velocity of the character when it died.
+/// * `blow_speed` - Speed of the killing blow.
+pub fn blow_velocity(position: Vec3, killer: Option<Vec3>, inherited: Vec3, blow_speed: f32) -> Vec3 {
+    let Some(killer) = killer else {
+        return inherited;
+    };
+    let away = Vec3::new(position.x - killer.x, 0.0, position.z - killer.z).normalize_or_zero();
+    inherited + (away + Vec3::Y * 0.3) * blow_speed
+}
+
+/// Ragdoll of a dead character. Characters without a skeleton have no links and
+/// ragdoll as a whole.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct Ragdoll {
+    /// Bones and their bodies, parents first.
+    pub links: Vec<RagdollLink>,
+    /// The ragdoll exceeded the cap and keeps its last pose without physics.
+    pub frozen: bool,
+}
+
+/// Bone driven by a ragdoll body.
+#[derive(Debug, Clone, Reflect)]
+pub struct RagdollLink {
+    pub bone: Entity,
+    pub body: Entity,
+    /// Scale of the bone in world space, the bodies are unscaled.
+    pub scale: Vec3,
+}
+
+/// Physics body of a bone. Despawned once its ragdoll froze or its character despawned.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct RagdollBody {
+    pub character: Entity,
+}
+
+/// Ragdolled characters, oldest first.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ActiveRagdolls {
+    entities: VecDeque<Entity>,
+}
+
+impl ActiveRagdolls {
+
+    /// Adds a ragdoll and keeps at most `max` ragdolls.
+    ///
+    /// # Returns
+    /// The oldest ragdolls which exceed the cap and have to freeze.
+    pub fn activate(&mut self, entity: Entity, max: usize) -> Vec<Entity> {
+        self.entities.push_back(entity);
+        let excess = self.entities.len().saturating_sub(max);
+        self.entities.drain(..excess).collect()
+    }
+
+    /// Drops ragdolls which don't exist anymore.
+    pub fn retain(&mut self, exists: impl Fn(Entity) -> bool) {
+        self.entities.retain(|entity| exists(*entity));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
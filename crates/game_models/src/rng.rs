@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Small deterministic random number generator (SplitMix64).
+///
+/// Used wherever gameplay needs reproducible randomness (AI, spawning, loot).
+/// The same seed always produces the same sequence on every platform.
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Deserialize, Serialize)]
+pub struct SeededRng {
+    state: u64,
+}
+
+/// Seed drawn from the clock, so every session rolls a different sequence.
+pub fn clock_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+impl Default for SeededRng {
+    fn default() -> Self {
+        Self::new(0x5EED_0F_4E11)
+    }
+}
+
+impl SeededRng {
+
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Creates an independent generator for a sub system, e.g. one per entity or per night.
+    pub fn derive(&self, salt: u64) -> Self {
+        let mut rng = Self::new(self.state ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        rng.next_u64();
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random float in range `0.0..1.0`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Random float in range `min..max`.
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Random integer in range `min..=max`.
+    pub fn range_u32(&mut self, min: u32, max: u32) -> u32 {
+        if max <= min {
+            return min;
+        }
+        min + (self.next_u64() % (max as u64 - min as u64 + 1)) as u32
+    }
+
+    /// Returns `true` with the given probability (`0.0..=1.0`).
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+
+    /// Picks a random index with a probability proportional to its weight.
+    /// Returns `None` if there is no positive weight.
+    pub fn weighted_index(&mut self, weights: &[f32]) -> Option<usize> {
+        let total: f32 = weights.iter().filter(|w| **w > 0.0).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut roll = self.next_f32() * total;
+        for (index, weight) in weights.iter().enumerate() {
+            if *weight <= 0.0 {
+                continue;
+            }
+            if roll < *weight {
+                return Some(index);
+            }
+            roll -= weight;
+        }
+        weights.iter().rposition(|w| *w > 0.0)
+    }
+
+    /// Random point on the XZ plane within `radius` around the origin.
+    pub fn point_in_circle(&mut self, radius: f32) -> Vec2 {
+        let angle = self.next_f32() * std::f32::consts::TAU;
+        let distance = radius * self.next_f32().sqrt();
+        Vec2::new(angle.cos(), angle.sin()) * distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_produces_same_sequence() {
+        let mut a = SeededRng::new(42);
+        let mut b = SeededRng::new(42);
+        for _ in 0..16 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn range_u32_stays_in_range() {
+        let mut rng = SeededRng::new(7);
+        for _ in 0..256 {
+            let value = rng.range_u32(3, 6);
+            assert!((3..=6).contains(&value));
+        }
+        assert_eq!(rng.range_u32(5, 5), 5);
+    }
+
+    #[test]
+    fn range_u32_handles_the_full_range() {
+        let mut rng = SeededRng::new(7);
+        for _ in 0..16 {
+            rng.range_u32(0, u32::MAX);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt::{Display, Formatter};
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::Path;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::dialogue::StoryFlags;
+use crate::entities::character::Character;
+use crate::quests::QuestLogSave;
+use crate::weather::WeatherSave;
+
+pub struct SaveGameModule;
+
+impl Plugin for SaveGameModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveGameSettings>();
+        app.add_message::<SaveGame>()
+            .add_message::<LoadGame>();
+        app.register_type::<SaveGameSettings>();
+    }
+}
+
+// =================================================================================================
+//
+//                                            Save
+//
+// =================================================================================================
+
+/// Where save games are written. Editable at runtime in the inspector.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct SaveGameSettings {
+    /// File of the quick save, relative to the working directory.
+    pub quick_save_path: String,
+}
+
+impl Default for SaveGameSettings {
+    fn default() -> Self {
+        Self {
+            quick_save_path: String::from("saves/quicksave.json"),
+        }
+    }
+}
+
+/// Errors which can occur while a [`GameSave`] is written or read.
+#[derive(Debug)]
+pub enum SaveGameError {
+    /// The file could not be read or written.
+    Io(std::io::Error),
+    /// The file content is not a valid save game.
+    Json(serde_json::Error),
+}
+
+impl Display for SaveGameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveGameError::Io(e) => write!(f, "Could not access save game: {}", e),
+            SaveGameError::Json(e) => write!(f, "Could not parse save game: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SaveGameError {}
+
+impl From<std::io::Error> for SaveGameError {
+    fn from(value: std::io::Error) -> Self {
+        SaveGameError::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for SaveGameError {
+    fn from(value: serde_json::Error) -> Self {
+        SaveGameError::Json(value)
+    }
+}
+
+/// Everything stored in a save game, written as JSON.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct GameSave {
+    /// Progression and reputation of the player.
+    #[serde(default)]
+    pub character: Character,
+    /// Quest log of the player.
+    #[serde(default)]
+    pub quests: QuestLogSave,
+    #[serde(default)]
+    pub story_flags: StoryFlags,
+    #[serde(default)]
+    pub weather: WeatherSave,
+}
+
+impl GameSave {
+
+    /// Writes the save game to `path` and creates missing folders.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), SaveGameError> {
+        let path = path.as_ref();
+        if let Some(folder) = path.parent() {
+            create_dir_all(folder)?;
+        }
+        write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Reads a save game written by [`GameSave::write`].
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SaveGameError> {
+        Ok(serde_json::from_str(&read_to_string(path)?)?)
+    }
+}
+
+// =================================================================================================
+//
+//                                           Requests
+//
+// =================================================================================================
+
+/// Saves the game to the quick save.
+#[derive(Message, Debug, Clone)]
+pub struct SaveGame;
+
+/// Loads the quick save.
+#[derive(Message, Debug, Clone)]
+pub struct LoadGame;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_save_round_trips_through_a_file() {
+        let path = std::env::temp_dir().join(format!("game_save_{}", std::process::id())).join("save.json");
+        let mut save = GameSave::default();
+        save.story_flags.set("met_survivors");
+        save.character.level = 3;
+        save.character.skills.push("sprinter".to_string());
+
+        save.write(&path).unwrap();
+        let loaded = GameSave::read(&path).unwrap();
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+
+        assert_eq!(loaded, save);
+    }
+
+    #[test]
+    fn missing_save_games_are_io_errors() {
+        let path = std::env::temp_dir().join("missing_game_save.json");
+        assert!(matches!(GameSave::read(path), Err(SaveGameError::Io(_))));
+    }
+}
@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::definitions::{AppDefinitionExt, Definition};
+use crate::rng::SeededRng;
+
+pub struct SpawningModule;
+
+impl Plugin for SpawningModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<NightSpawnDefinition>();
+        app.init_resource::<NightDirector>();
+        app.register_type::<NightDirector>()
+            .register_type::<NightSpawned>();
+    }
+}
+
+/// Entity which can be spawned at night.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SpawnEntry {
+    /// Id of the [`EntityDefinition`](crate::entities::definition::EntityDefinition).
+    pub entity: String,
+    /// Relative chance compared to the other entries.
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    /// Budget consumed by one spawn.
+    #[serde(default = "default_cost")]
+    pub cost: u32,
+    /// First night the entry is used.
+    #[serde(default = "default_min_night")]
+    pub min_night: u32,
+    /// Last night the entry is used. `None` keeps it forever.
+    #[serde(default)]
+    pub max_night: Option<u32>,
+}
+
+/// Distance band around the player in which enemies appear.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SpawnRing {
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// Relative chance compared to the other rings.
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+fn default_cost() -> u32 {
+    1
+}
+
+fn default_min_night() -> u32 {
+    1
+}
+
+/// Night spawning rules, authored as `assets/spawning/<id>.spawn.json`.
+///
+/// Every night `n` (starting with `1`) gets a budget of `base_budget + budget_per_night * (n - 1)`.
+/// Each spawn consumes the cost of the spawned entry until the budget is used up.
+#[derive(Asset, TypePath, Deserialize, Serialize, Debug, Clone)]
+pub struct NightSpawnDefinition {
+    pub id: String,
+    /// Seed of the spawn rolls. Every night derives its own generator from it.
+    pub seed: u64,
+    pub base_budget: u32,
+    #[serde(default)]
+    pub budget_per_night: u32,
+    /// Maximum number of night enemies alive at once.
+    pub base_max_alive: u32,
+    #[serde(default)]
+    pub max_alive_per_night: u32,
+    /// Seconds between two spawns.
+    pub spawn_interval: f32,
+    /// Random positions tried per spawn before it is postponed.
+    #[serde(default = "default_attempts")]
+    pub attempts: u32,
+    /// Enemies further away from the player are removed and refunded to the budget.
+    pub despawn_distance: f32,
+    pub rings: Vec<SpawnRing>,
+    /// Max health bonus per night after the first (`0.1` = +10%).
+    #[serde(default)]
+    pub health_per_night: f32,
+    /// Damage bonus per night after the first (`0.1` = +10%).
+    #[serde(default)]
+    pub damage_per_night: f32,
+    /// Night from which the difficulty stops growing. `None` scales forever.
+    #[serde(default)]
+    pub max_scaling_night: Option<u32>,
+    pub entries: Vec<SpawnEntry>,
+}
+
+fn default_attempts() -> u32 {
+    8
+}
+
+impl Definition for NightSpawnDefinition {
+    const FOLDER: &'static str = "spawning";
+    const EXTENSIONS: &'static [&'static str] = &["spawn.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl NightSpawnDefinition {
+
+    /// Number of nights after the first used for scaling, capped by `max_scaling_night`.
+    fn scaling_steps(&self, night: u32) -> u32 {
+        let night = self.max_scaling_night.map_or(night, |max| night.min(max));
+        night.saturating_sub(1)
+    }
+
+    /// Spawn budget of the given night.
+    pub fn budget(&self, night: u32) -> u32 {
+        self.base_budget + self.budget_per_night * self.scaling_steps(night)
+    }
+
+    /// Maximum number of enemies alive at once in the given night.
+    pub fn max_alive(&self, night: u32) -> u32 {
+        self.base_max_alive + self.max_alive_per_night * self.scaling_steps(night)
+    }
+
+    /// Max health bonus of enemies spawned in the given night.
+    pub fn health_bonus(&self, night: u32) -> f32 {
+        self.health_per_night * self.scaling_steps(night) as f32
+    }
+
+    /// Damage bonus of enemies spawned in the given night.
+    pub fn damage_bonus(&self, night: u32) -> f32 {
+        self.damage_per_night * self.scaling_steps(night) as f32
+    }
+
+    /// Picks an entry which is allowed in `night` and affordable with `budget`.
+    pub fn pick_entry(&self, night: u32, budget: u32, rng: &mut SeededRng) -> Option<&SpawnEntry> {
+        let weights: Vec<f32> = self.entries
+            .iter()
+            .map(|entry| {
+                let in_night = night >= entry.min_night && entry.max_night.is_none_or(|max| night <= max);
+                if in_night && entry.cost <= budget { entry.weight } else { 0.0 }
+            })
+            .collect();
+        rng.weighted_index(&weights).map(|index| &self.entries[index])
+    }
+
+    /// Picks a random offset on the XZ plane inside one of the rings.
+    pub fn pick_offset(&self, rng: &mut SeededRng) -> Option<Vec2> {
+        let weights: Vec<f32> = self.rings.iter().map(|ring| ring.weight).collect();
+        let ring = &self.rings[rng.weighted_index(&weights)?];
+        let angle = rng.next_f32() * std::f32::consts::TAU;
+        let distance = rng.range_f32(ring.min_distance, ring.max_distance);
+        Some(Vec2::new(angle.cos(), angle.sin()) * distance)
+    }
+}
+
+/// Runtime state of the night spawning.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct NightDirector {
+    /// Id of the [`NightSpawnDefinition`] in use.
+    pub definition: String,
+    /// Number of the current (or last) night, `0` before the first night.
+    pub night: u32,
+    /// `true` while enemies are spawned.
+    pub active: bool,
+    /// Budget left for this night.
+    pub remaining_budget: u32,
+    /// Seconds until the next spawn attempt.
+    pub cooldown: f32,
+    /// Random source of this night, derived from the definition seed and the night number.
+    pub rng: SeededRng,
+}
+
+impl Default for NightDirector {
+    fn default() -> Self {
+        Self {
+            definition: String::from("default"),
+            night: 0,
+            active: false,
+            remaining_budget: 0,
+            cooldown: 0.0,
+            rng: SeededRng::default(),
+        }
+    }
+}
+
+impl NightDirector {
+
+    /// Starts spawning for `night` with the budget and seed of `definition`.
+    pub fn start_night(&mut self, night: u32, definition: &NightSpawnDefinition) {
+        self.night = night;
+        self.active = true;
+        self.remaining_budget = definition.budget(night);
+        self.cooldown = 0.0;
+        self.rng = SeededRng::new(definition.seed).derive(night as u64);
+    }
+}
+
+/// Enemy spawned by the [`NightDirector`].
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct NightSpawned {
+    /// Night the enemy was spawned in.
+    pub night: u32,
+    /// Budget refunded when the enemy is removed for being too far away.
+    pub cost: u32,
+}
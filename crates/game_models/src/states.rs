@@ -104,4 +104,24 @@ pub fn is_state_in_game(state: Res<State<AppState>>) -> bool {
 /// `true` if the current app state matches `AppState::Screen(_)`, otherwise `false`.
 pub fn is_state_in_ui(state: Res<State<AppState>>) -> bool {
     matches!(*state.get(), AppState::Screen(_))
-}
\ No newline at end of file
+}
+
+/// Returns `true` while gameplay simulation (health, AI, survival, ...) should tick.
+///
+/// This is the case in the test scene (`AppState::Preload`) and in the playable
+/// in‐game substates `InGameStates::Game` and `InGameStates::Combat`. Paused and
+/// game over states freeze the simulation.
+///
+/// # Parameters
+///
+/// - `state`: A Res‐injected Bevy resource of type `State<AppState>`.
+///
+/// # Returns
+///
+/// `true` if the simulation is running, otherwise `false`.
+pub fn is_state_simulating(state: Res<State<AppState>>) -> bool {
+    matches!(
+        *state.get(),
+        AppState::Preload | AppState::InGame(InGameStates::Game) | AppState::InGame(InGameStates::Combat)
+    )
+}
@@ -0,0 +1,466 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::attributes::{AttributeModifier, AttributeModifiers};
+use crate::definitions::{AppDefinitionExt, Definition, DefinitionLibrary};
+use crate::health::DamageType;
+use crate::rng::{clock_seed, SeededRng};
+
+pub struct StatusEffectsModule;
+
+impl Plugin for StatusEffectsModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.register_definition::<StatusEffectDefinition>();
+        app.init_resource::<StatusEffectSettings>()
+            .init_resource::<StatusEffectRng>();
+        app.add_message::<ApplyStatusEffect>()
+            .add_message::<RemoveStatusEffect>()
+            .add_message::<StatusEffectApplied>()
+            .add_message::<StatusEffectRemoved>();
+        app.register_type::<StatusEffectSettings>()
+            .register_type::<StatusEffectRng>()
+            .register_type::<StatusEffects>()
+            .register_type::<ActiveEffect>()
+            .register_type::<StatusAura>()
+            .register_type::<StatusVisual>();
+    }
+}
+
+// =================================================================================================
+//
+//                                          Definition
+//
+// =================================================================================================
+
+/// Timed condition of a character, e.g. bleeding or burning. Authored as
+/// `assets/status_effects/<name>.effect.json`.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct StatusEffectDefinition {
+    pub id: String,
+    /// Localization key of the name shown in the HUD.
+    pub localized_name: String,
+    /// Asset path of the HUD icon, empty for effects without one.
+    #[serde(default)]
+    pub icon: String,
+    /// Seconds until the effect expires.
+    pub duration: f32,
+    /// Seconds between two damage ticks, `None` for effects without ticks.
+    #[serde(default)]
+    pub tick_interval: Option<f32>,
+    /// Damage per tick and stack.
+    #[serde(default)]
+    pub damage_per_tick: f32,
+    #[serde(default = "default_damage_type")]
+    pub damage_type: DamageType,
+    /// What happens when the effect is applied again while active.
+    #[serde(default)]
+    pub stacking: StackingRule,
+    #[serde(default = "default_max_stacks")]
+    pub max_stacks: u32,
+    /// Modifiers applied once per stack while the effect is active. The `source` is set
+    /// on application.
+    #[serde(default)]
+    pub modifiers: Vec<AttributeModifier>,
+    /// Ids of effects removed when this effect is applied, e.g. `wet` removes `burning`.
+    #[serde(default)]
+    pub cancels: Vec<String>,
+    /// Free form tags used by gameplay code, e.g. `"fear"` makes the AI flee. Immunities
+    /// can name tags as well.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Particle or material effect played on the affected character.
+    #[serde(default)]
+    pub visual: Option<String>,
+    /// Sound played when the effect is applied.
+    #[serde(default)]
+    pub sound: Option<String>,
+}
+
+impl Definition for StatusEffectDefinition {
+    const FOLDER: &'static str = "status_effects";
+    const EXTENSIONS: &'static [&'static str] = &["effect.json"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+fn default_damage_type() -> DamageType {
+    DamageType::Physical
+}
+
+fn default_max_stacks() -> u32 {
+    1
+}
+
+/// Behaviour of an effect which is applied again while active.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StackingRule {
+    /// Resets the duration.
+    #[default]
+    Refresh,
+    /// Adds a stack up to `max_stacks` and resets the duration.
+    Stack,
+    /// Keeps the running effect unchanged.
+    Ignore,
+}
+
+/// Chance to apply an effect, used by attacks, projectiles and items.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EffectApplication {
+    /// Id of the [`StatusEffectDefinition`].
+    pub effect: String,
+    /// Probability per hit or use (`0.0..=1.0`).
+    #[serde(default = "default_chance")]
+    pub chance: f32,
+}
+
+fn default_chance() -> f32 {
+    1.0
+}
+
+// =================================================================================================
+//
+//                                            Runtime
+//
+// =================================================================================================
+
+/// Read only access to all loaded [`StatusEffectDefinition`]s by id.
+#[derive(SystemParam)]
+pub struct Effects<'w> {
+    library: Res<'w, DefinitionLibrary<StatusEffectDefinition>>,
+    assets: Res<'w, Assets<StatusEffectDefinition>>,
+}
+
+impl<'w> Effects<'w> {
+
+    pub fn get(&self, id: &str) -> Option<&StatusEffectDefinition> {
+        self.library.get(id, &self.assets)
+    }
+
+    /// HUD data of all active effects of `effects`, in application order.
+    pub fn view(&self, effects: &StatusEffects) -> Vec<StatusEffectView> {
+        effects
+            .iter()
+            .filter_map(|active| {
+                let definition = self.get(&active.id)?;
+                Some(StatusEffectView {
+                    id: active.id.clone(),
+                    localized_name: definition.localized_name.clone(),
+                    icon: definition.icon.clone(),
+                    stacks: active.stacks,
+                    remaining: active.remaining,
+                    fraction: (active.remaining / definition.duration.max(f32::EPSILON)).clamp(0.0, 1.0),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Active effect as shown in the HUD.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusEffectView {
+    pub id: String,
+    pub localized_name: String,
+    pub icon: String,
+    pub stacks: u32,
+    /// Seconds left.
+    pub remaining: f32,
+    /// Share of the duration left (`0.0..=1.0`).
+    pub fraction: f32,
+}
+
+/// Tuning of the environmental effects. Editable at runtime in the inspector.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct StatusEffectSettings {
+    /// Seconds between two applications of auras and the weather.
+    pub environment_interval: f32,
+    /// Effect applied to all characters while it rains, `None` to disable.
+    pub rain_effect: Option<String>,
+    /// Minimum precipitation of the weather for the rain effect.
+    pub rain_threshold: f32,
+    /// Seed of the [`StatusEffectRng`]. Drawn from the clock, set a fixed seed to
+    /// reproduce the chance rolls of a session.
+    pub seed: u64,
+}
+
+impl Default for StatusEffectSettings {
+    fn default() -> Self {
+        Self {
+            environment_interval: 0.5,
+            rain_effect: Some(String::from("wet")),
+            rain_threshold: 200.0,
+            seed: clock_seed(),
+        }
+    }
+}
+
+/// Random source of the chance rolls of hits and used items, seeded from
+/// [`StatusEffectSettings::seed`].
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct StatusEffectRng(pub SeededRng);
+
+impl FromWorld for StatusEffectRng {
+    fn from_world(world: &mut World) -> Self {
+        let settings = world.get_resource_or_init::<StatusEffectSettings>();
+        Self(SeededRng::new(settings.seed))
+    }
+}
+
+/// Result of [`StatusEffects::apply`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyResult {
+    /// The effect was not active before.
+    Added,
+    /// The duration of the running effect was reset.
+    Refreshed,
+    /// A stack was added, holds the new stack count.
+    Stacked(u32),
+    /// The running effect was kept unchanged.
+    Ignored,
+    /// The character is immune to the effect.
+    Immune,
+}
+
+impl ApplyResult {
+
+    /// `true` if the stack count changed, so the modifiers have to be reapplied.
+    pub fn changes_stacks(&self) -> bool {
+        matches!(self, ApplyResult::Added | ApplyResult::Stacked(_))
+    }
+}
+
+/// Running instance of a [`StatusEffectDefinition`].
+#[derive(Debug, Clone, Reflect)]
+pub struct ActiveEffect {
+    pub id: String,
+    pub stacks: u32,
+    /// Seconds until the effect expires.
+    pub remaining: f32,
+    /// Seconds until the next damage tick.
+    pub tick_timer: f32,
+    /// Entity which applied the effect, credited with the tick damage.
+    pub source: Option<Entity>,
+    /// Tags of the definition, copied so queries don't need the definitions.
+    pub tags: Vec<String>,
+}
+
+impl ActiveEffect {
+
+    /// Advances the effect by `delta` seconds.
+    ///
+    /// # Returns
+    /// Number of damage ticks which fired during this step.
+    pub fn tick(&mut self, definition: &StatusEffectDefinition, delta: f32) -> u32 {
+        self.remaining -= delta;
+        let Some(interval) = definition.tick_interval.filter(|interval| *interval > 0.0) else {
+            return 0;
+        };
+        self.tick_timer -= delta;
+        let mut ticks = 0;
+        while self.tick_timer <= 0.0 {
+            self.tick_timer += interval;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining <= 0.0
+    }
+}
+
+/// Status effects of a character. Required by [`Health`](crate::health::Health), so
+/// everything which can take damage can be affected.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct StatusEffects {
+    effects: Vec<ActiveEffect>,
+    /// Effect ids or tags the character ignores.
+    pub immunities: Vec<String>,
+}
+
+impl StatusEffects {
+
+    pub fn with_immunities(immunities: Vec<String>) -> Self {
+        Self { immunities, ..default() }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ActiveEffect> {
+        self.effects.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ActiveEffect> {
+        self.effects.iter_mut()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ActiveEffect> {
+        self.effects.iter().find(|effect| effect.id == id)
+    }
+
+    pub fn has(&self, id: &str) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Stack count of `id`, zero if inactive.
+    pub fn stacks(&self, id: &str) -> u32 {
+        self.get(id).map_or(0, |effect| effect.stacks)
+    }
+
+    /// `true` if an active effect has the given tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.effects.iter().any(|effect| effect.tags.iter().any(|t| t == tag))
+    }
+
+    pub fn is_immune(&self, definition: &StatusEffectDefinition) -> bool {
+        self.immunities
+            .iter()
+            .any(|immunity| *immunity == definition.id || definition.tags.contains(immunity))
+    }
+
+    /// Applies `definition` following its [`StackingRule`].
+    ///
+    /// # Parameters
+    /// * `definition` - Applied effect.
+    /// * `source` - Entity which applied the effect.
+    pub fn apply(&mut self, definition: &StatusEffectDefinition, source: Option<Entity>) -> ApplyResult {
+        if self.is_immune(definition) {
+            return ApplyResult::Immune;
+        }
+        let Some(active) = self.effects.iter_mut().find(|effect| effect.id == definition.id) else {
+            self.effects.push(ActiveEffect {
+                id: definition.id.clone(),
+                stacks: 1,
+                remaining: definition.duration,
+                tick_timer: definition.tick_interval.unwrap_or_default(),
+                source,
+                tags: definition.tags.clone(),
+            });
+            return ApplyResult::Added;
+        };
+
+        match definition.stacking {
+            StackingRule::Ignore => ApplyResult::Ignored,
+            StackingRule::Refresh => {
+                active.remaining = definition.duration;
+                active.source = source.or(active.source);
+                ApplyResult::Refreshed
+            }
+            StackingRule::Stack => {
+                active.remaining = definition.duration;
+                active.source = source.or(active.source);
+                if active.stacks >= definition.max_stacks {
+                    return ApplyResult::Refreshed;
+                }
+                active.stacks += 1;
+                ApplyResult::Stacked(active.stacks)
+            }
+        }
+    }
+
+    /// Removes the effect `id`.
+    ///
+    /// # Returns
+    /// `true` if the effect was active.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let before = self.effects.len();
+        self.effects.retain(|effect| effect.id != id);
+        before != self.effects.len()
+    }
+
+    /// Removes and returns the ids of all expired effects.
+    pub fn take_expired(&mut self) -> Vec<String> {
+        let mut expired = Vec::new();
+        self.effects.retain(|effect| {
+            if effect.is_expired() {
+                expired.push(effect.id.clone());
+                return false;
+            }
+            true
+        });
+        expired
+    }
+}
+
+/// Source id of the attribute modifiers of the effect `id`.
+pub fn modifier_source(id: &str) -> String {
+    format!("status:{}", id)
+}
+
+/// Replaces the modifiers of `definition` in `modifiers` with one copy per stack.
+/// Zero stacks remove them.
+pub fn apply_effect_modifiers(modifiers: &mut AttributeModifiers, definition: &StatusEffectDefinition, stacks: u32) {
+    let source = modifier_source(&definition.id);
+    modifiers.remove_source(&source);
+    for _ in 0..stacks {
+        for modifier in definition.modifiers.iter() {
+            let mut modifier = modifier.clone();
+            modifier.source = source.clone();
+            modifiers.add(modifier);
+        }
+    }
+}
+
+/// Applies `effect` to characters within `radius` in a fixed interval, e.g. burning
+/// next to a fire.
+#[derive(Component, Debug, Clone, Reflect, Deserialize, Serialize)]
+#[reflect(Component)]
+pub struct StatusAura {
+    pub effect: String,
+    pub radius: f32,
+}
+
+/// Visual hook of an active effect, child of the affected character.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct StatusVisual {
+    pub effect: String,
+}
+
+// =================================================================================================
+//
+//                                           Requests
+//
+// =================================================================================================
+
+/// Request: apply `effect` to `target`.
+#[derive(Message, Debug, Clone)]
+pub struct ApplyStatusEffect {
+    pub target: Entity,
+    pub effect: String,
+    /// Entity which caused the effect, if any.
+    pub source: Option<Entity>,
+}
+
+/// Request: remove `effect` from `target`, e.g. a bandage stopping bleeding.
+#[derive(Message, Debug, Clone)]
+pub struct RemoveStatusEffect {
+    pub target: Entity,
+    pub effect: String,
+}
+
+// =================================================================================================
+//
+//                                        Notifications
+//
+// =================================================================================================
+
+/// `effect` was added to or stacked on `target`. Plays the visual and sound hooks.
+#[derive(Message, Debug, Clone)]
+pub struct StatusEffectApplied {
+    pub target: Entity,
+    pub effect: String,
+    pub stacks: u32,
+}
+
+/// `effect` ended on `target`, because it expired, was cancelled or removed.
+#[derive(Message, Debug, Clone)]
+pub struct StatusEffectRemoved {
+    pub target: Entity,
+    pub effect: String,
+}
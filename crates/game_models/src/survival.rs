@@ -0,0 +1,408 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::attributes::{AttributeKind, AttributeModifier, ModifierKind};
+use crate::health::DamageType;
+
+pub struct SurvivalModule;
+
+impl Plugin for SurvivalModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NeedsSettings>();
+        app.add_message::<ExertStamina>()
+            .add_message::<NeedDepleted>()
+            .add_message::<NeedRecovered>();
+        app.register_type::<NeedsSettings>()
+            .register_type::<Needs>()
+            .register_type::<NeedValue>()
+            .register_type::<HeatSource>();
+    }
+}
+
+// =================================================================================================
+//
+//                                           Settings
+//
+// =================================================================================================
+
+/// Survival needs of a character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NeedKind {
+    Stamina,
+    Hunger,
+    Thirst,
+    Warmth,
+}
+
+impl NeedKind {
+    pub const ALL: [NeedKind; 4] = [
+        NeedKind::Stamina,
+        NeedKind::Hunger,
+        NeedKind::Thirst,
+        NeedKind::Warmth,
+    ];
+
+    /// Source of the [`AttributeModifier`]s applied while the need is depleted.
+    pub fn modifier_source(&self) -> String {
+        format!("need:{:?}", self).to_lowercase()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+/// Change rates of the needs, all values per second unless noted otherwise.
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct NeedRates {
+    pub hunger_decay: f32,
+    pub thirst_decay: f32,
+    pub stamina_regen: f32,
+    /// Seconds after spending stamina before it regenerates.
+    pub stamina_regen_delay: f32,
+    pub sprint_cost: f32,
+    /// Stamina per jump.
+    pub jump_cost: f32,
+    /// Warmth lost at night.
+    pub warmth_loss_night: f32,
+    /// Warmth lost at full precipitation, scaled by the current precipitation.
+    pub warmth_loss_rain: f32,
+    /// Warmth regained during the day when nothing cools the character down.
+    pub warmth_regen: f32,
+}
+
+impl Default for NeedRates {
+    fn default() -> Self {
+        Self {
+            hunger_decay: 100.0 / 1800.0,
+            thirst_decay: 100.0 / 1200.0,
+            stamina_regen: 15.0,
+            stamina_regen_delay: 1.0,
+            sprint_cost: 12.0,
+            jump_cost: 10.0,
+            warmth_loss_night: 0.2,
+            warmth_loss_rain: 0.3,
+            warmth_regen: 1.0,
+        }
+    }
+}
+
+impl NeedRates {
+
+    /// Rates with all decays, losses and costs multiplied by `factor`. Regeneration is kept.
+    pub fn scaled(&self, factor: f32) -> Self {
+        Self {
+            hunger_decay: self.hunger_decay * factor,
+            thirst_decay: self.thirst_decay * factor,
+            sprint_cost: self.sprint_cost * factor,
+            jump_cost: self.jump_cost * factor,
+            warmth_loss_night: self.warmth_loss_night * factor,
+            warmth_loss_rain: self.warmth_loss_rain * factor,
+            ..self.clone()
+        }
+    }
+}
+
+/// Effect of a depleted need.
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct NeedPenalty {
+    /// Applied while the need is depleted. The source is replaced by
+    /// [`NeedKind::modifier_source`].
+    pub modifiers: Vec<AttributeModifier>,
+    pub damage_per_second: f32,
+    pub damage_type: DamageType,
+}
+
+/// Tuning of the needs. Editable at runtime in the inspector.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct NeedsSettings {
+    pub difficulty: Difficulty,
+    pub easy: NeedRates,
+    pub normal: NeedRates,
+    pub hard: NeedRates,
+    /// Seconds between two damage ticks of depleted needs.
+    pub damage_interval: f32,
+    pub stamina_penalty: NeedPenalty,
+    pub hunger_penalty: NeedPenalty,
+    pub thirst_penalty: NeedPenalty,
+    pub warmth_penalty: NeedPenalty,
+}
+
+impl Default for NeedsSettings {
+    fn default() -> Self {
+        let normal = NeedRates::default();
+        let slow = |value: f32| vec![AttributeModifier::new(AttributeKind::MoveSpeed, ModifierKind::Percent, value, "")];
+        Self {
+            difficulty: Difficulty::Normal,
+            easy: normal.scaled(0.5),
+            hard: normal.scaled(1.5),
+            normal,
+            damage_interval: 1.0,
+            stamina_penalty: NeedPenalty {
+                modifiers: slow(-0.3),
+                damage_per_second: 0.0,
+                damage_type: DamageType::True,
+            },
+            hunger_penalty: NeedPenalty {
+                modifiers: vec![AttributeModifier::new(AttributeKind::Damage, ModifierKind::Percent, -0.25, "")],
+                damage_per_second: 1.0,
+                damage_type: DamageType::True,
+            },
+            thirst_penalty: NeedPenalty {
+                modifiers: slow(-0.15),
+                damage_per_second: 1.5,
+                damage_type: DamageType::True,
+            },
+            warmth_penalty: NeedPenalty {
+                modifiers: slow(-0.2),
+                damage_per_second: 2.0,
+                damage_type: DamageType::Cold,
+            },
+        }
+    }
+}
+
+impl NeedsSettings {
+
+    /// Rates of the active difficulty.
+    pub fn rates(&self) -> &NeedRates {
+        match self.difficulty {
+            Difficulty::Easy => &self.easy,
+            Difficulty::Normal => &self.normal,
+            Difficulty::Hard => &self.hard,
+        }
+    }
+
+    pub fn penalty(&self, kind: NeedKind) -> &NeedPenalty {
+        match kind {
+            NeedKind::Stamina => &self.stamina_penalty,
+            NeedKind::Hunger => &self.hunger_penalty,
+            NeedKind::Thirst => &self.thirst_penalty,
+            NeedKind::Warmth => &self.warmth_penalty,
+        }
+    }
+}
+
+/// Amount of a need restored by an item, e.g. food or water.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NeedRestore {
+    pub need: NeedKind,
+    pub amount: f32,
+}
+
+// =================================================================================================
+//
+//                                            Runtime
+//
+// =================================================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct NeedValue {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl NeedValue {
+
+    /// Creates a full need with the given maximum.
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    /// Adds `amount` (negative to remove) within `0.0..=max` and returns the actual change.
+    pub fn change(&mut self, amount: f32) -> f32 {
+        let before = self.current;
+        self.current = (self.current + amount).clamp(0.0, self.max);
+        self.current - before
+    }
+
+    /// Changes the maximum and keeps the current value in range.
+    pub fn set_max(&mut self, max: f32) {
+        self.max = max.max(0.0);
+        self.current = self.current.min(self.max);
+    }
+
+    pub fn is_depleted(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    /// Current value in range `0.0..=1.0`.
+    pub fn fraction(&self) -> f32 {
+        if self.max <= 0.0 { 0.0 } else { self.current / self.max }
+    }
+}
+
+/// Surroundings of a character which influence its warmth.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NeedEnvironment {
+    pub night: bool,
+    /// Precipitation of the current weather (`0.0..=1.0`).
+    pub precipitation: f32,
+    /// Warmth per second given by nearby [`HeatSource`]s.
+    pub heat: f32,
+}
+
+/// Survival needs of a character. Depleted needs apply their [`NeedPenalty`].
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Needs {
+    pub stamina: NeedValue,
+    pub hunger: NeedValue,
+    pub thirst: NeedValue,
+    pub warmth: NeedValue,
+    /// Seconds until stamina regenerates again.
+    pub stamina_cooldown: f32,
+    /// Seconds until the next damage tick of depleted needs.
+    pub damage_timer: f32,
+    /// Needs whose penalty is applied.
+    pub depleted: Vec<NeedKind>,
+}
+
+impl Default for Needs {
+    fn default() -> Self {
+        Self {
+            stamina: NeedValue::new(100.0),
+            hunger: NeedValue::new(100.0),
+            thirst: NeedValue::new(100.0),
+            warmth: NeedValue::new(100.0),
+            stamina_cooldown: 0.0,
+            damage_timer: 0.0,
+            depleted: Vec::new(),
+        }
+    }
+}
+
+impl Needs {
+
+    pub fn get(&self, kind: NeedKind) -> &NeedValue {
+        match kind {
+            NeedKind::Stamina => &self.stamina,
+            NeedKind::Hunger => &self.hunger,
+            NeedKind::Thirst => &self.thirst,
+            NeedKind::Warmth => &self.warmth,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: NeedKind) -> &mut NeedValue {
+        match kind {
+            NeedKind::Stamina => &mut self.stamina,
+            NeedKind::Hunger => &mut self.hunger,
+            NeedKind::Thirst => &mut self.thirst,
+            NeedKind::Warmth => &mut self.warmth,
+        }
+    }
+
+    /// `true` if at least `amount` stamina is left.
+    pub fn has_stamina(&self, amount: f32) -> bool {
+        self.stamina.current >= amount
+    }
+
+    /// Spends stamina and pauses its regeneration for `regen_delay` seconds. Spends
+    /// nothing if not enough stamina is left.
+    ///
+    /// # Returns
+    /// `true` if the stamina was spent.
+    pub fn spend_stamina(&mut self, amount: f32, regen_delay: f32) -> bool {
+        if amount <= 0.0 {
+            return true;
+        }
+        if !self.has_stamina(amount) {
+            return false;
+        }
+        self.stamina.change(-amount);
+        self.stamina_cooldown = regen_delay;
+        true
+    }
+
+    /// Spends up to `amount` stamina and pauses its regeneration for `regen_delay`
+    /// seconds, even if no stamina is left. Keeps exhausted characters from
+    /// regenerating while they keep exerting themselves.
+    pub fn drain_stamina(&mut self, amount: f32, regen_delay: f32) {
+        if amount <= 0.0 {
+            return;
+        }
+        self.stamina.change(-amount.min(self.stamina.current));
+        self.stamina_cooldown = regen_delay;
+    }
+
+    /// Advances all needs by `delta` seconds. Stamina only regenerates once nothing
+    /// spent it for the regeneration delay.
+    pub fn tick(&mut self, rates: &NeedRates, environment: &NeedEnvironment, delta: f32) {
+        self.hunger.change(-rates.hunger_decay * delta);
+        self.thirst.change(-rates.thirst_decay * delta);
+
+        if self.stamina_cooldown > 0.0 {
+            self.stamina_cooldown = (self.stamina_cooldown - delta).max(0.0);
+        } else {
+            self.stamina.change(rates.stamina_regen * delta);
+        }
+
+        let night_loss = if environment.night { rates.warmth_loss_night } else { 0.0 };
+        let loss = night_loss + environment.precipitation.clamp(0.0, 1.0) * rates.warmth_loss_rain;
+        let regen = if loss <= 0.0 { rates.warmth_regen } else { 0.0 };
+        self.warmth.change((environment.heat + regen - loss) * delta);
+    }
+}
+
+/// Warms characters within `radius`, e.g. a campfire. The warmth fades out towards
+/// the edge of the radius.
+#[derive(Component, Debug, Clone, Reflect, Deserialize, Serialize)]
+#[reflect(Component)]
+pub struct HeatSource {
+    pub radius: f32,
+    /// Warmth per second at the center.
+    pub warmth: f32,
+}
+
+impl HeatSource {
+
+    /// Warmth per second at the given distance.
+    pub fn warmth_at(&self, distance: f32) -> f32 {
+        if self.radius <= 0.0 || distance >= self.radius {
+            return 0.0;
+        }
+        self.warmth * (1.0 - distance / self.radius)
+    }
+}
+
+// =================================================================================================
+//
+//                                           Requests
+//
+// =================================================================================================
+
+/// Request: `entity` spends `amount` stamina, e.g. for sprinting, jumping or attacking.
+/// Systems which need the stamina to act check [`Needs::has_stamina`] before.
+#[derive(Message, Debug, Clone)]
+pub struct ExertStamina {
+    pub entity: Entity,
+    pub amount: f32,
+}
+
+// =================================================================================================
+//
+//                                        Notifications
+//
+// =================================================================================================
+
+/// A need of `entity` reached zero and its penalty is applied.
+#[derive(Message, Debug, Clone)]
+pub struct NeedDepleted {
+    pub entity: Entity,
+    pub need: NeedKind,
+}
+
+/// A depleted need of `entity` was restored and its penalty removed.
+#[derive(Message, Debug, Clone)]
+pub struct NeedRecovered {
+    pub entity: Entity,
+    pub need: NeedKind,
+}
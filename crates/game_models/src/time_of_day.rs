@@ -0,0 +1,250 @@
+use std::f32::consts::TAU;
+use bevy::color::Mix;
+use bevy::prelude::*;
+
+pub struct TimeOfDayModule;
+
+impl Plugin for TimeOfDayModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeOfDaySettings>()
+            .init_resource::<TimeOfDay>();
+        app.add_message::<DayPhaseChanged>()
+            .add_message::<MidnightReached>();
+        app.register_type::<TimeOfDaySettings>()
+            .register_type::<TimeOfDay>()
+            .register_type::<DayPhase>()
+            .register_type::<Sun>()
+            .register_type::<Moon>();
+    }
+}
+
+/// Hours of one in game day.
+pub const HOURS_PER_DAY: f32 = 24.0;
+
+/// Section of the day. Gameplay (e.g. night spawning) reacts to the changes between them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum DayPhase {
+    Dawn,
+    #[default]
+    Day,
+    Dusk,
+    Night,
+}
+
+/// Key frame curve over the hours of a day. Values are interpolated linearly
+/// and wrap around midnight. Keys have to be sorted by hour.
+#[derive(Debug, Clone, Default, Reflect)]
+pub struct DayCurve {
+    pub keys: Vec<(f32, f32)>,
+}
+
+impl DayCurve {
+
+    pub fn new(keys: &[(f32, f32)]) -> Self {
+        Self { keys: keys.to_vec() }
+    }
+
+    pub fn sample(&self, hour: f32) -> f32 {
+        sample_keys(&self.keys, hour, |a, b, t| a.lerp(b, t)).unwrap_or(0.0)
+    }
+}
+
+/// Key frame colour curve over the hours of a day, see [`DayCurve`].
+#[derive(Debug, Clone, Default, Reflect)]
+pub struct ColorCurve {
+    pub keys: Vec<(f32, LinearRgba)>,
+}
+
+impl ColorCurve {
+
+    pub fn new(keys: &[(f32, [f32; 3])]) -> Self {
+        Self { keys: keys.iter().map(|(hour, [r, g, b])| (*hour, LinearRgba::rgb(*r, *g, *b))).collect() }
+    }
+
+    pub fn sample(&self, hour: f32) -> LinearRgba {
+        sample_keys(&self.keys, hour, |a, b, t| a.mix(&b, t)).unwrap_or(LinearRgba::WHITE)
+    }
+}
+
+/// Interpolates between the two keys around `hour`. Between the last and the first key
+/// the curve wraps over midnight.
+fn sample_keys<T: Copy>(keys: &[(f32, T)], hour: f32, lerp: impl Fn(T, T, f32) -> T) -> Option<T> {
+    let (first_hour, first) = *keys.first()?;
+    let (last_hour, last) = *keys.last()?;
+
+    let ((from_hour, from), (to_hour, to), hour) = match keys.iter().position(|(h, _)| *h > hour) {
+        Some(0) => ((last_hour - HOURS_PER_DAY, last), (first_hour, first), hour),
+        Some(i) => (keys[i - 1], keys[i], hour),
+        None => ((last_hour, last), (first_hour + HOURS_PER_DAY, first), hour),
+    };
+
+    let span = to_hour - from_hour;
+    if span <= f32::EPSILON {
+        return Some(to);
+    }
+    Some(lerp(from, to, ((hour - from_hour) / span).clamp(0.0, 1.0)))
+}
+
+/// Tuning of the day/night cycle. Editable at runtime in the inspector.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct TimeOfDaySettings {
+    /// Real seconds of one in game day.
+    pub day_length: f32,
+    /// Hour at which a new game starts.
+    pub start_hour: f32,
+    /// Hours at which the phases begin.
+    pub dawn_hour: f32,
+    pub day_hour: f32,
+    pub dusk_hour: f32,
+    pub night_hour: f32,
+    /// In game hours per real second while scrubbing with the debug keys.
+    pub scrub_speed: f32,
+    /// Sun illuminance in lux per hour.
+    pub sun_illuminance: DayCurve,
+    /// Sun colour per hour.
+    pub sun_color: ColorCurve,
+    /// Moon illuminance in lux when it stands at its highest point.
+    pub moon_illuminance: f32,
+    pub moon_color: LinearRgba,
+    /// Ambient light brightness per hour.
+    pub ambient_brightness: DayCurve,
+    /// Ambient light colour per hour.
+    pub ambient_color: ColorCurve,
+}
+
+impl Default for TimeOfDaySettings {
+    fn default() -> Self {
+        Self {
+            day_length: 1200.0,
+            start_hour: 8.0,
+            dawn_hour: 5.0,
+            day_hour: 7.0,
+            dusk_hour: 19.0,
+            night_hour: 21.0,
+            scrub_speed: 4.0,
+            sun_illuminance: DayCurve::new(&[
+                (5.5, 0.0),
+                (7.0, 6000.0),
+                (12.0, 12000.0),
+                (17.0, 6000.0),
+                (18.5, 0.0),
+            ]),
+            sun_color: ColorCurve::new(&[
+                (5.5, [1.0, 0.5, 0.3]),
+                (8.0, [1.0, 0.95, 0.85]),
+                (16.5, [1.0, 0.95, 0.85]),
+                (18.5, [1.0, 0.45, 0.25]),
+            ]),
+            moon_illuminance: 300.0,
+            moon_color: LinearRgba::rgb(0.55, 0.65, 1.0),
+            ambient_brightness: DayCurve::new(&[
+                (2.0, 8.0),
+                (5.0, 12.0),
+                (7.0, 60.0),
+                (12.0, 80.0),
+                (18.0, 60.0),
+                (21.0, 12.0),
+            ]),
+            ambient_color: ColorCurve::new(&[
+                (2.0, [0.25, 0.3, 0.55]),
+                (6.0, [0.9, 0.6, 0.5]),
+                (9.0, [1.0, 1.0, 1.0]),
+                (17.0, [1.0, 1.0, 1.0]),
+                (20.0, [0.8, 0.45, 0.4]),
+                (22.0, [0.25, 0.3, 0.55]),
+            ]),
+        }
+    }
+}
+
+impl TimeOfDaySettings {
+
+    /// Phase active at the given hour.
+    pub fn phase_at(&self, hour: f32) -> DayPhase {
+        if hour >= self.night_hour || hour < self.dawn_hour {
+            DayPhase::Night
+        } else if hour < self.day_hour {
+            DayPhase::Dawn
+        } else if hour < self.dusk_hour {
+            DayPhase::Day
+        } else {
+            DayPhase::Dusk
+        }
+    }
+}
+
+/// Current in game time. `hour` runs from `0.0` to `24.0`, `day` counts the days
+/// (and nights) since the start of the game, starting with `1`.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct TimeOfDay {
+    pub hour: f32,
+    pub day: u32,
+    /// Time does not advance while frozen (scrubbing still works).
+    pub frozen: bool,
+    /// Multiplier of the time flow.
+    pub speed: f32,
+}
+
+impl FromWorld for TimeOfDay {
+    fn from_world(world: &mut World) -> Self {
+        let settings = world.get_resource_or_init::<TimeOfDaySettings>();
+        Self {
+            hour: settings.start_hour,
+            day: 1,
+            frozen: false,
+            speed: 1.0,
+        }
+    }
+}
+
+impl TimeOfDay {
+
+    /// Moves the time by `hours` (negative to rewind) and updates the day counter.
+    /// The day never goes below `1`.
+    pub fn advance(&mut self, hours: f32) {
+        let total = self.hour + hours;
+        let days = (total / HOURS_PER_DAY).floor();
+        self.hour = total - days * HOURS_PER_DAY;
+        self.day = (self.day as i64 + days as i64).max(1) as u32;
+    }
+
+    /// Angle of the sun around the sky: `0` at sunrise (6:00), `PI / 2` at noon.
+    pub fn sun_angle(&self) -> f32 {
+        (self.hour - 6.0) / HOURS_PER_DAY * TAU
+    }
+
+    /// Height of the sun above the horizon in range `-1.0..=1.0`.
+    pub fn sun_elevation(&self) -> f32 {
+        self.sun_angle().sin()
+    }
+}
+
+/// Directional light following the sun.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct Sun;
+
+/// Directional light following the moon, opposite of the sun.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct Moon;
+
+/// Emitted when the [`DayPhase`] changes, including changes made by scrubbing.
+#[derive(Message, Debug, Clone)]
+pub struct DayPhaseChanged {
+    pub from: DayPhase,
+    pub to: DayPhase,
+    /// Day the new phase belongs to.
+    pub day: u32,
+}
+
+/// Emitted when the time passes midnight and a new day starts.
+#[derive(Message, Debug, Clone)]
+pub struct MidnightReached {
+    /// The day which just started.
+    pub day: u32,
+}
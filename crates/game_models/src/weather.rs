@@ -0,0 +1,371 @@
+use bevy::color::Mix;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::rng::SeededRng;
+
+pub struct WeatherModule;
+
+impl Plugin for WeatherModule {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WeatherSettings>()
+            .init_resource::<Weather>();
+        app.add_message::<WeatherChanged>();
+        app.register_type::<WeatherSettings>()
+            .register_type::<Weather>()
+            .register_type::<WeatherKind>()
+            .register_type::<WeatherProfile>()
+            .register_type::<ForecastEntry>()
+            .register_type::<Precipitation>()
+            .register_type::<RainDrop>();
+    }
+}
+
+/// States of the weather state machine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherKind {
+    #[default]
+    Clear,
+    Fog,
+    Rain,
+    Storm,
+}
+
+/// Visual and gameplay effect of a weather. Profiles are blended during transitions.
+#[derive(Debug, Clone, Default, Reflect)]
+pub struct WeatherProfile {
+    /// Distance at which the fog starts.
+    pub fog_start: f32,
+    /// Distance at which the fog fully covers the world.
+    pub fog_end: f32,
+    pub fog_color: LinearRgba,
+    /// Multiplier of the sun, moon and ambient light.
+    pub light: f32,
+    /// Wind speed in units per second, pushes rain sideways.
+    pub wind_strength: f32,
+    /// Number of rain particles around the camera.
+    pub precipitation: f32,
+    /// Multiplier of the AI sight range.
+    pub visibility: f32,
+    /// Fraction of the AI hearing range lost to the weather noise (`0.0..=1.0`).
+    pub noise_masking: f32,
+}
+
+impl WeatherProfile {
+
+    /// Interpolates every value between `self` and `other`.
+    pub fn lerp(&self, other: &WeatherProfile, t: f32) -> Self {
+        Self {
+            fog_start: self.fog_start.lerp(other.fog_start, t),
+            fog_end: self.fog_end.lerp(other.fog_end, t),
+            fog_color: self.fog_color.mix(&other.fog_color, t),
+            light: self.light.lerp(other.light, t),
+            wind_strength: self.wind_strength.lerp(other.wind_strength, t),
+            precipitation: self.precipitation.lerp(other.precipitation, t),
+            visibility: self.visibility.lerp(other.visibility, t),
+            noise_masking: self.noise_masking.lerp(other.noise_masking, t),
+        }
+    }
+}
+
+/// Profile, duration and outgoing transitions of one [`WeatherKind`].
+#[derive(Debug, Clone, Reflect)]
+pub struct WeatherState {
+    pub profile: WeatherProfile,
+    /// Seconds the weather lasts, rolled between both values.
+    pub min_duration: f32,
+    pub max_duration: f32,
+    /// Weighted weathers which can follow.
+    pub next: Vec<(WeatherKind, f32)>,
+}
+
+/// Tuning of the weather. Editable at runtime in the inspector.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct WeatherSettings {
+    /// Seed of the weather sequence.
+    pub seed: u64,
+    /// Seconds the blend between two weathers takes.
+    pub transition_duration: f32,
+    /// Number of upcoming weathers kept in the forecast.
+    pub forecast_length: usize,
+    pub clear: WeatherState,
+    pub fog: WeatherState,
+    pub rain: WeatherState,
+    pub storm: WeatherState,
+}
+
+impl Default for WeatherSettings {
+    fn default() -> Self {
+        Self {
+            seed: 0x57_0E_A7_4E,
+            transition_duration: 30.0,
+            forecast_length: 4,
+            clear: WeatherState {
+                profile: WeatherProfile {
+                    fog_start: 60.0,
+                    fog_end: 400.0,
+                    fog_color: LinearRgba::rgb(0.6, 0.65, 0.7),
+                    light: 1.0,
+                    wind_strength: 1.0,
+                    precipitation: 0.0,
+                    visibility: 1.0,
+                    noise_masking: 0.0,
+                },
+                min_duration: 300.0,
+                max_duration: 600.0,
+                next: vec![(WeatherKind::Fog, 1.0), (WeatherKind::Rain, 1.0)],
+            },
+            fog: WeatherState {
+                profile: WeatherProfile {
+                    fog_start: 5.0,
+                    fog_end: 40.0,
+                    fog_color: LinearRgba::rgb(0.55, 0.58, 0.6),
+                    light: 0.7,
+                    wind_strength: 0.5,
+                    precipitation: 0.0,
+                    visibility: 0.45,
+                    noise_masking: 0.1,
+                },
+                min_duration: 120.0,
+                max_duration: 300.0,
+                next: vec![(WeatherKind::Clear, 2.0), (WeatherKind::Rain, 1.0)],
+            },
+            rain: WeatherState {
+                profile: WeatherProfile {
+                    fog_start: 20.0,
+                    fog_end: 150.0,
+                    fog_color: LinearRgba::rgb(0.4, 0.43, 0.47),
+                    light: 0.6,
+                    wind_strength: 3.0,
+                    precipitation: 800.0,
+                    visibility: 0.7,
+                    noise_masking: 0.35,
+                },
+                min_duration: 180.0,
+                max_duration: 420.0,
+                next: vec![(WeatherKind::Clear, 1.0), (WeatherKind::Fog, 1.0), (WeatherKind::Storm, 1.0)],
+            },
+            storm: WeatherState {
+                profile: WeatherProfile {
+                    fog_start: 10.0,
+                    fog_end: 90.0,
+                    fog_color: LinearRgba::rgb(0.25, 0.27, 0.3),
+                    light: 0.35,
+                    wind_strength: 8.0,
+                    precipitation: 1500.0,
+                    visibility: 0.5,
+                    noise_masking: 0.6,
+                },
+                min_duration: 90.0,
+                max_duration: 240.0,
+                next: vec![(WeatherKind::Rain, 2.0), (WeatherKind::Clear, 1.0)],
+            },
+        }
+    }
+}
+
+impl WeatherSettings {
+
+    pub fn state(&self, kind: WeatherKind) -> &WeatherState {
+        match kind {
+            WeatherKind::Clear => &self.clear,
+            WeatherKind::Fog => &self.fog,
+            WeatherKind::Rain => &self.rain,
+            WeatherKind::Storm => &self.storm,
+        }
+    }
+}
+
+/// Upcoming weather of the forecast.
+#[derive(Debug, Clone, PartialEq, Reflect, Deserialize, Serialize)]
+pub struct ForecastEntry {
+    pub kind: WeatherKind,
+    /// Seconds the weather will last.
+    pub duration: f32,
+}
+
+/// Current weather and the forecast of the upcoming ones.
+///
+/// The whole sequence is rolled from [`WeatherSettings::seed`], so the same seed
+/// (or a loaded [`WeatherSave`]) always produces the same weather.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct Weather {
+    pub kind: WeatherKind,
+    /// Weather blended out during a transition.
+    pub previous: WeatherKind,
+    /// Profile at the moment the transition started. Blended to the profile of `kind`,
+    /// so a transition which starts during another one continues from where it was.
+    pub start: WeatherProfile,
+    /// Progress of the transition from `start` to `kind` (`0.0..=1.0`).
+    pub blend: f32,
+    /// Seconds until the next weather starts.
+    pub remaining: f32,
+    /// Normalized wind direction on the XZ plane.
+    pub wind_direction: Vec2,
+    /// Direction the wind turns to.
+    pub wind_target: Vec2,
+    /// Blended profile of the current moment.
+    pub current: WeatherProfile,
+    forecast: Vec<ForecastEntry>,
+    rng: SeededRng,
+}
+
+impl FromWorld for Weather {
+    fn from_world(world: &mut World) -> Self {
+        let settings = world.get_resource_or_init::<WeatherSettings>();
+        let mut weather = Self {
+            kind: WeatherKind::Clear,
+            previous: WeatherKind::Clear,
+            start: settings.clear.profile.clone(),
+            blend: 1.0,
+            remaining: settings.clear.min_duration,
+            wind_direction: Vec2::X,
+            wind_target: Vec2::X,
+            current: settings.clear.profile.clone(),
+            forecast: Vec::new(),
+            rng: SeededRng::new(settings.seed),
+        };
+        weather.fill_forecast(&settings);
+        weather
+    }
+}
+
+impl Weather {
+
+    /// Upcoming weathers, the next one first.
+    pub fn forecast(&self) -> &[ForecastEntry] {
+        &self.forecast
+    }
+
+    /// Seconds until the given weather starts according to the forecast, `Some(0.0)`
+    /// if it is the current weather.
+    pub fn time_until(&self, kind: WeatherKind) -> Option<f32> {
+        if self.kind == kind {
+            return Some(0.0);
+        }
+        let mut time = self.remaining;
+        for entry in self.forecast.iter() {
+            if entry.kind == kind {
+                return Some(time);
+            }
+            time += entry.duration;
+        }
+        None
+    }
+
+    /// Rolls forecast entries until `forecast_length` weathers are known.
+    pub fn fill_forecast(&mut self, settings: &WeatherSettings) {
+        while self.forecast.len() < settings.forecast_length.max(1) {
+            let last = self.forecast.last().map_or(self.kind, |entry| entry.kind);
+            let state = settings.state(last);
+            let weights: Vec<f32> = state.next.iter().map(|(_, weight)| *weight).collect();
+            let kind = self.rng.weighted_index(&weights).map_or(last, |index| state.next[index].0);
+            let next = settings.state(kind);
+            let duration = self.rng.range_f32(next.min_duration, next.max_duration);
+            self.forecast.push(ForecastEntry { kind, duration });
+        }
+    }
+
+    /// Starts the next weather of the forecast and rolls a new wind direction.
+    ///
+    /// # Returns
+    /// The previous and the new weather.
+    pub fn advance(&mut self, settings: &WeatherSettings) -> (WeatherKind, WeatherKind) {
+        self.fill_forecast(settings);
+        let next = self.forecast.remove(0);
+        let from = self.kind;
+
+        self.previous = from;
+        self.start = self.current.clone();
+        self.kind = next.kind;
+        self.remaining = next.duration;
+        self.blend = 0.0;
+        let angle = self.rng.next_f32() * std::f32::consts::TAU;
+        self.wind_target = Vec2::new(angle.cos(), angle.sin());
+
+        self.fill_forecast(settings);
+        (from, next.kind)
+    }
+
+    /// Recalculates the blended profile of the current moment.
+    pub fn update_profile(&mut self, settings: &WeatherSettings) {
+        let to = &settings.state(self.kind).profile;
+        self.current = self.start.lerp(to, self.blend.clamp(0.0, 1.0));
+    }
+
+    /// Serializable state for save games.
+    pub fn to_save(&self) -> WeatherSave {
+        WeatherSave {
+            kind: self.kind,
+            remaining: self.remaining,
+            wind_direction: self.wind_direction.to_array(),
+            forecast: self.forecast.clone(),
+            rng: self.rng.clone(),
+        }
+    }
+
+    /// Restores a saved state. The loaded weather is active immediately without a transition.
+    pub fn load(&mut self, save: WeatherSave, settings: &WeatherSettings) {
+        self.kind = save.kind;
+        self.previous = save.kind;
+        self.start = settings.state(save.kind).profile.clone();
+        self.blend = 1.0;
+        self.remaining = save.remaining;
+        self.wind_direction = Vec2::from_array(save.wind_direction).normalize_or(Vec2::X);
+        self.wind_target = self.wind_direction;
+        self.forecast = save.forecast;
+        self.rng = save.rng;
+        self.fill_forecast(settings);
+        self.update_profile(settings);
+    }
+}
+
+/// Saved state of the [`Weather`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WeatherSave {
+    pub kind: WeatherKind,
+    pub remaining: f32,
+    pub wind_direction: [f32; 2],
+    pub forecast: Vec<ForecastEntry>,
+    pub rng: SeededRng,
+}
+
+impl Default for WeatherSave {
+    fn default() -> Self {
+        Self {
+            kind: WeatherKind::default(),
+            remaining: 0.0,
+            wind_direction: [1.0, 0.0],
+            forecast: Vec::new(),
+            rng: SeededRng::default(),
+        }
+    }
+}
+
+/// Rain around the camera. All drops are drawn by one line mesh with a segment per
+/// drop, which the weather logic rebuilds every frame.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct Precipitation {
+    pub drops: Vec<RainDrop>,
+}
+
+/// One drop of the [`Precipitation`], recycled once it leaves the box around the camera.
+#[derive(Debug, Clone, Default, Reflect)]
+pub struct RainDrop {
+    /// World position of the lower end.
+    pub position: Vec3,
+    /// Fall speed in units per second.
+    pub fall_speed: f32,
+}
+
+/// Emitted when a new weather starts.
+#[derive(Message, Debug, Clone)]
+pub struct WeatherChanged {
+    pub from: WeatherKind,
+    pub to: WeatherKind,
+}
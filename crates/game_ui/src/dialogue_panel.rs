@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use bevy_extended_ui::html::HtmlSource;
+use bevy_extended_ui::io::HtmlAsset;
+use bevy_extended_ui::registry::UiRegistry;
+use bevy_extended_ui::styles::CssID;
+use bevy_extended_ui::widgets::{Headline, Paragraph};
+use game_models::config::GlobalConfig;
+use game_models::dialogue::{DialogueState, MAX_DIALOGUE_CHOICES};
+
+const DIALOGUE_UI: &str = "dialogue-ui";
+/// Number of choice rows in `html/dialogue_ui.html`.
+const CHOICE_ROWS: usize = MAX_DIALOGUE_CHOICES;
+
+/// HTML panel showing the running dialogue.
+pub struct DialoguePanelUi;
+
+impl Plugin for DialoguePanelUi {
+
+    #[coverage(off)]
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (toggle_dialogue_panel, fill_dialogue_panel).chain());
+    }
+}
+
+/// Shows the panel when a dialogue starts and removes it when the dialogue ends.
+#[coverage(off)]
+fn toggle_dialogue_panel(
+    mut shown: Local<bool>,
+    state: Res<DialogueState>,
+    asset_server: Res<AssetServer>,
+    mut registry: ResMut<UiRegistry>,
+) {
+    if state.is_active() == *shown {
+        return;
+    }
+    *shown = state.is_active();
+    if *shown {
+        let handle: Handle<HtmlAsset> = asset_server.load("html/dialogue_ui.html");
+        registry.add_and_use(DIALOGUE_UI.to_string(), HtmlSource::from_handle(handle));
+    } else {
+        registry.remove(DIALOGUE_UI);
+    }
+}
+
+/// Writes speaker, line and choices of the current node into the panel elements.
+/// Runs every frame while a dialogue is active, so freshly built elements are filled too.
+#[coverage(off)]
+fn fill_dialogue_panel(
+    state: Res<DialogueState>,
+    global_config: Res<GlobalConfig>,
+    mut headlines: Query<(&CssID, &mut Headline)>,
+    mut paragraphs: Query<(&CssID, &mut Paragraph)>,
+) {
+    let Some(session) = state.session.as_ref() else {
+        return;
+    };
+    let view = &session.view;
+
+    for (id, mut headline) in headlines.iter_mut() {
+        if id.0 == "dialogue-speaker" && headline.text != view.speaker {
+            headline.text = view.speaker.clone();
+        }
+    }
+
+    for (id, mut paragraph) in paragraphs.iter_mut() {
+        let text = match id.0.as_str() {
+            "dialogue-text" => view.text.clone(),
+            "dialogue-continue" if view.choices.is_empty() => format!("[{}]", global_config.input_config.interact),
+            "dialogue-continue" => String::new(),
+            other => match other.strip_prefix("dialogue-choice-").and_then(|row| row.parse::<usize>().ok()) {
+                Some(row) if (1..=CHOICE_ROWS).contains(&row) => view.choices
+                    .get(row - 1)
+                    .map(|choice| format!("{}. {}", row, choice))
+                    .unwrap_or_default(),
+                _ => continue,
+            },
+        };
+        if paragraph.text != text {
+            paragraph.text = text;
+        }
+    }
+}
@@ -1,11 +1,14 @@
 #![feature(coverage_attribute)]
 
+mod dialogue_panel;
+
 use bevy::camera::visibility::RenderLayers;
 use bevy::prelude::*;
 use bevy_extended_ui::{ExtendedUiConfiguration, ExtendedUiPlugin};
 use bevy_extended_ui::html::HtmlSource;
 use bevy_extended_ui::io::HtmlAsset;
 use bevy_extended_ui::registry::UiRegistry;
+use crate::dialogue_panel::DialoguePanelUi;
 
 pub struct GameUiPlugin;
 
@@ -17,7 +20,7 @@ impl Plugin for GameUiPlugin {
             enable_default_camera: false,
             ..default()
         });
-        app.add_plugins(ExtendedUiPlugin);
+        app.add_plugins((ExtendedUiPlugin, DialoguePanelUi));
         app.add_systems(Startup, (spawn_my_ui_camera, test_ui));
     }
 }